@@ -3,7 +3,8 @@
 use crate::co::{ACCESS_RIGHTS, STANDARD_RIGHTS};
 
 const_bitflag! { KEY: u32: "advapi";
-	/// [`HKEY::OpenKeyEx`](crate::prelude::AdvapiHkey::OpenKeyEx)
+	/// [`HKEY::OpenKeyEx`](crate::prelude::AdvapiHkey::OpenKeyEx) and
+	/// [`HKEY::CreateKeyEx`](crate::prelude::AdvapiHkey::CreateKeyEx)
 	/// `access_rights` (`u32`).
 	=>
 	=>
@@ -44,9 +45,31 @@ const_ordinary! { REG: u32: "advapi";
 	QWORD_LITTLE_ENDIAN 11
 }
 
+const_ordinary! { REG_DISPOSITION: u32: "advapi";
+	/// [`HKEY::CreateKeyEx`](crate::prelude::AdvapiHkey::CreateKeyEx)
+	/// disposition (`u32`).
+	=>
+	=>
+	CREATED_NEW_KEY 0x0000_0001
+	OPENED_EXISTING_KEY 0x0000_0002
+}
+
+const_bitflag! { REG_NOTIFY: u32: "advapi";
+	/// [`HKEY::NotifyChangeKeyValue`](crate::prelude::AdvapiHkey::NotifyChangeKeyValue)
+	/// `notify_filter` (`u32`).
+	=>
+	=>
+	CHANGE_NAME 0x0000_0001
+	CHANGE_ATTRIBUTES 0x0000_0002
+	CHANGE_LAST_SET 0x0000_0004
+	CHANGE_SECURITY 0x0000_0008
+	THREAD_AGNOSTIC 0x1000_0000
+}
+
 const_bitflag! { REG_OPTION: u32: "advapi";
-	/// [`HKEY::OpenKeyEx`](crate::prelude::AdvapiHkey::OpenKeyEx) `options`
-	/// (`u32`).
+	/// [`HKEY::OpenKeyEx`](crate::prelude::AdvapiHkey::OpenKeyEx) and
+	/// [`HKEY::CreateKeyEx`](crate::prelude::AdvapiHkey::CreateKeyEx)
+	/// `options` (`u32`).
 	=>
 	=>
 	/// None of the actual values (zero).
@@ -1,36 +1,97 @@
 use crate::co;
-use crate::kernel::decl::WString;
+use crate::kernel::decl::{ExpandEnvironmentStrings, WinResult};
 
 /// Registry value types.
 #[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum RegistryValue {
 	/// Binary value, defined as [`REG::BINARY`](crate::co::REG::BINARY).
 	Binary(Vec<u8>),
 	/// An `u32` integer value, defined as [`REG::DWORD`](crate::co::REG::DWORD).
 	Dword(u32),
+	/// An `u32` integer value stored in big-endian format, defined as
+	/// [`REG::DWORD_BIG_ENDIAN`](crate::co::REG::DWORD_BIG_ENDIAN).
+	DwordBigEndian(u32),
+	/// String value with unexpanded references to environment variables, like
+	/// `%PATH%`, defined as [`REG::EXPAND_SZ`](crate::co::REG::EXPAND_SZ).
+	///
+	/// To expand the references, call
+	/// [`expand`](crate::RegistryValue::expand).
+	ExpandSz(String),
+	/// Sequence of strings, defined as
+	/// [`REG::MULTI_SZ`](crate::co::REG::MULTI_SZ). The strings can't be
+	/// empty, because an empty string marks the end of the sequence.
+	MultiSz(Vec<String>),
 	/// An `u64` integer value, defined as [`REG::QWORD`](crate::co::REG::QWORD).
 	Qword(u64),
 	/// String value, defined as [`REG::SZ`](crate::co::REG::SZ).
-	Sz(WString),
-	/// No value, defined as [`REG::NONE`](crate::co::REG::NONE). Also used for
-	/// non-implemented value types.
+	Sz(String),
+	/// No value, defined as [`REG::NONE`](crate::co::REG::NONE).
 	None,
+	/// Any other value type, like [`REG::LINK`](crate::co::REG::LINK), with
+	/// its raw data.
+	Other(co::REG, Vec<u8>),
 }
 
 impl RegistryValue {
 	/// Creates a new `RegistryValue::Sz` value from a `&str`.
 	pub fn new_sz(s: &str) -> RegistryValue {
-		Self::Sz(WString::from_str(s))
+		Self::Sz(s.to_owned())
 	}
 
-	pub fn as_ptr(&self) -> *const std::ffi::c_void {
+	/// Parses the raw data of a registry value, as returned by
+	/// [`HKEY::QueryValueEx`](crate::prelude::AdvapiHkey::QueryValueEx).
+	///
+	/// Data which doesn't match its type – like a
+	/// [`REG::DWORD`](crate::co::REG::DWORD) which is not 4 bytes long – is
+	/// returned as [`RegistryValue::Other`](crate::RegistryValue::Other).
+	/// Strings don't need to be null-terminated.
+	pub fn from_bytes(reg_type: co::REG, data: Vec<u8>) -> RegistryValue {
+		match reg_type {
+			co::REG::NONE if data.is_empty() => Self::None,
+			co::REG::BINARY => Self::Binary(data),
+			co::REG::DWORD if data.len() == 4 =>
+				Self::Dword(u32::from_le_bytes(data.try_into().unwrap())),
+			co::REG::DWORD_BIG_ENDIAN if data.len() == 4 =>
+				Self::DwordBigEndian(u32::from_be_bytes(data.try_into().unwrap())),
+			co::REG::QWORD if data.len() == 8 =>
+				Self::Qword(u64::from_le_bytes(data.try_into().unwrap())),
+			co::REG::SZ => Self::Sz(parse_strs(&data).next().unwrap_or_default()),
+			co::REG::EXPAND_SZ => Self::ExpandSz(parse_strs(&data).next().unwrap_or_default()),
+			co::REG::MULTI_SZ => Self::MultiSz(
+				parse_strs(&data)
+					.take_while(|s| !s.is_empty()) // an empty string ends the sequence
+					.collect(),
+			),
+			_ => Self::Other(reg_type, data),
+		}
+	}
+
+	/// If the value is a [`RegistryValue::ExpandSz`](crate::RegistryValue::ExpandSz),
+	/// returns a [`RegistryValue::Sz`](crate::RegistryValue::Sz) with the
+	/// references to environment variables expanded by
+	/// [`ExpandEnvironmentStrings`](crate::ExpandEnvironmentStrings). Other
+	/// values are returned unchanged.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{HKEY, RegistryValue};
+	///
+	/// let path = HKEY::CURRENT_USER
+	///     .GetValue("Environment", "TEMP")?
+	///     .expand()?;
+	///
+	/// if let RegistryValue::Sz(path) = path {
+	///     println!("Temp: {}", path);
+	/// }
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	pub fn expand(self) -> WinResult<RegistryValue> {
 		match self {
-			Self::Binary(b) => b.as_ptr() as _,
-			Self::Dword(n) => *n as _,
-			Self::Qword(n) => *n as _,
-			Self::Sz(ws) => unsafe { ws.as_ptr() as _ },
-			Self::None => std::ptr::null(),
+			Self::ExpandSz(s) => ExpandEnvironmentStrings(&s).map(Self::Sz),
+			other => Ok(other),
 		}
 	}
 
@@ -39,20 +100,135 @@ impl RegistryValue {
 		match self {
 			Self::Binary(_) => co::REG::BINARY,
 			Self::Dword(_) => co::REG::DWORD,
+			Self::DwordBigEndian(_) => co::REG::DWORD_BIG_ENDIAN,
+			Self::ExpandSz(_) => co::REG::EXPAND_SZ,
+			Self::MultiSz(_) => co::REG::MULTI_SZ,
 			Self::Qword(_) => co::REG::QWORD,
 			Self::Sz(_) => co::REG::SZ,
 			Self::None => co::REG::NONE,
+			Self::Other(reg_type, _) => *reg_type,
 		}
 	}
 
-	/// Returns the length of the stored data.
+	/// Returns the length of the raw data, in bytes, as written to the
+	/// registry.
 	pub fn len(&self) -> usize {
 		match self {
-			Self::Binary(b) => b.len(),
-			Self::Dword(_) => std::mem::size_of::<u32>(),
+			Self::Binary(b) | Self::Other(_, b) => b.len(),
+			Self::Dword(_) | Self::DwordBigEndian(_) => std::mem::size_of::<u32>(),
 			Self::Qword(_) => std::mem::size_of::<u64>(),
-			Self::Sz(ws) => (ws.len() + 1) * std::mem::size_of::<u16>(), // including terminating null
+			Self::ExpandSz(s) | Self::Sz(s) => (s.encode_utf16().count() + 1) * 2, // including terminating null
+			Self::MultiSz(v) => (v.iter().map(|s| s.encode_utf16().count() + 1).sum::<usize>() + 1) * 2, // double terminating null
 			Self::None => 0,
 		}
 	}
+
+	/// Tells whether the raw data is empty, which happens only for
+	/// [`RegistryValue::None`](crate::RegistryValue::None) and empty
+	/// binary or unknown values.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the raw data, as written to the registry. Strings are encoded
+	/// in UTF-16, null-terminated.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		match self {
+			Self::Binary(b) | Self::Other(_, b) => b.clone(),
+			Self::Dword(n) => n.to_le_bytes().to_vec(),
+			Self::DwordBigEndian(n) => n.to_be_bytes().to_vec(),
+			Self::Qword(n) => n.to_le_bytes().to_vec(),
+			Self::ExpandSz(s) | Self::Sz(s) => str_to_bytes(s),
+			Self::MultiSz(v) => {
+				let mut buf: Vec<u8> = v.iter()
+					.flat_map(|s| str_to_bytes(s))
+					.collect();
+				buf.extend_from_slice(&[0, 0]); // double terminating null
+				buf
+			},
+			Self::None => Vec::default(),
+		}
+	}
+
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if a
+	/// string contains a null char, or if a
+	/// [`RegistryValue::MultiSz`](crate::RegistryValue::MultiSz) has an empty
+	/// string, because they would be read back truncated.
+	pub(in crate::advapi) fn validate(&self) -> WinResult<()> {
+		let valid = match self {
+			Self::ExpandSz(s) | Self::Sz(s) => !s.contains('\0'),
+			Self::MultiSz(v) => v.iter().all(|s| !s.is_empty() && !s.contains('\0')),
+			_ => true,
+		};
+		if valid { Ok(()) } else { Err(co::ERROR::INVALID_PARAMETER) }
+	}
+}
+
+/// Encodes the string in UTF-16, null-terminated.
+fn str_to_bytes(s: &str) -> Vec<u8> {
+	s.encode_utf16()
+		.chain(std::iter::once(0x0000))
+		.flat_map(|ch| ch.to_le_bytes())
+		.collect()
+}
+
+/// Decodes null-separated UTF-16 strings; a trailing odd byte is ignored.
+fn parse_strs(data: &[u8]) -> std::vec::IntoIter<String> {
+	let chars: Vec<u16> = data.chunks_exact(2)
+		.map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+		.collect();
+	let num_chars = chars.iter()
+		.rposition(|ch| *ch != 0x0000)
+		.map_or(0, |idx| idx + 1); // trailing nulls don't make more strings
+
+	let mut strs = Vec::default();
+	if num_chars > 0 {
+		strs = chars[..num_chars].split(|ch| *ch == 0x0000)
+			.map(String::from_utf16_lossy)
+			.collect();
+	}
+	strs.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn strs(v: &[&str]) -> RegistryValue {
+		RegistryValue::MultiSz(v.iter().map(|s| s.to_string()).collect())
+	}
+
+	#[test]
+	fn round_trip() {
+		let values = [
+			RegistryValue::Binary(vec![1, 2, 3]),
+			RegistryValue::Dword(0x1234_5678),
+			RegistryValue::DwordBigEndian(0x1234_5678),
+			RegistryValue::Qword(u64::MAX - 1),
+			RegistryValue::new_sz("ação"),
+			RegistryValue::new_sz(""),
+			RegistryValue::ExpandSz("%TEMP%\\x".to_owned()),
+			strs(&["a", "bc", "🦀"]),
+			strs(&[]),
+			RegistryValue::None,
+			RegistryValue::Other(co::REG::LINK, vec![9, 8]),
+		];
+		for value in values {
+			let data = value.to_bytes();
+			assert_eq!(data.len(), value.len());
+			assert_eq!(RegistryValue::from_bytes(value.reg_type(), data), value);
+		}
+	}
+
+	#[test]
+	fn validate_strings() {
+		assert!(RegistryValue::new_sz("a b").validate().is_ok());
+		assert!(strs(&["a", " "]).validate().is_ok());
+		assert_eq!(strs(&["a", "", "b"]).validate(), Err(co::ERROR::INVALID_PARAMETER));
+		assert_eq!(strs(&["a\0b"]).validate(), Err(co::ERROR::INVALID_PARAMETER));
+		assert_eq!(RegistryValue::new_sz("a\0b").validate(), Err(co::ERROR::INVALID_PARAMETER));
+		assert_eq!(RegistryValue::ExpandSz("\0".to_owned()).validate(),
+			Err(co::ERROR::INVALID_PARAMETER));
+	}
 }
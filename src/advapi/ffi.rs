@@ -6,9 +6,16 @@ extern_sys! { "advapi32";
 	EncryptionDisable(PCSTR, BOOL) -> BOOL
 	GetUserNameW(PSTR, *mut u32) -> BOOL
 	RegCloseKey(HANDLE) -> i32
+	RegCopyTreeW(HANDLE, PCSTR, HANDLE) -> i32
+	RegCreateKeyExW(HANDLE, PCSTR, u32, PSTR, u32, u32, PVOID, *mut HANDLE, *mut u32) -> i32
+	RegDeleteKeyExW(HANDLE, PCSTR, u32, u32) -> i32
+	RegDeleteTreeW(HANDLE, PCSTR) -> i32
+	RegDeleteValueW(HANDLE, PCSTR) -> i32
 	RegEnumKeyExW(HANDLE, u32, PSTR, *mut u32, *mut u32, PSTR, *mut u32, PVOID) -> i32
 	RegEnumValueW(HANDLE, u32, PSTR, *mut u32, *mut u32, *mut u32, *mut u8, *mut u32) -> i32
+	RegFlushKey(HANDLE) -> i32
 	RegGetValueW(HANDLE, PCSTR, PCSTR, u32, *mut u32, PVOID, *mut u32) -> i32
+	RegNotifyChangeKeyValue(HANDLE, BOOL, u32, HANDLE, BOOL) -> i32
 	RegOpenKeyExW(HANDLE, PCSTR, u32, u32, *mut HANDLE) -> i32
 	RegQueryInfoKeyW(HANDLE, PSTR, *mut u32, *mut u32, *mut u32, *mut u32, *mut u32, *mut u32, *mut u32, *mut u32, *mut u32, PVOID) -> i32
 	RegQueryValueExW(HANDLE, PCSTR, *mut u32, *mut u32, *mut u8, *mut u32) -> i32
@@ -3,8 +3,10 @@
 use std::marker::PhantomData;
 
 use crate::{advapi, co};
-use crate::advapi::decl::RegistryValue;
-use crate::kernel::decl::{FILETIME, WinResult, WString};
+use crate::advapi::decl::{HkeyGuard, RegFile, RegFileKey, RegFileValue,
+	RegistryValue};
+use crate::kernel::decl::{FILETIME, HEVENT, SECURITY_ATTRIBUTES, WinResult,
+	WString};
 use crate::prelude::Handle;
 
 impl_handle! { HKEY: "advapi";
@@ -40,6 +42,159 @@ pub trait AdvapiHkey: Handle {
 	predef_key!(DYN_DATA, 0x8000_0006);
 	predef_key!(CURRENT_USER_LOCAL_SETTINGS, 0x8000_0007);
 
+	/// Opens the key with
+	/// [`HKEY::CreateKeyEx`](crate::prelude::AdvapiHkey::CreateKeyEx),
+	/// creating it if it doesn't exist, and returns it within a guard, which
+	/// closes it automatically.
+	///
+	/// The key is non-volatile, and has no class.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{co, HKEY, RegistryValue};
+	///
+	/// let hkey = HKEY::CURRENT_USER.create_key(
+	///     "Software\\My Company\\My App",
+	///     co::KEY::ALL_ACCESS,
+	/// )?;
+	///
+	/// hkey.SetValueEx("Runs", RegistryValue::Dword(1))?;
+	/// # Ok::<_, co::ERROR>(())
+	/// ```
+	fn create_key(self,
+		sub_key: &str, access_rights: co::KEY) -> WinResult<HkeyGuard>
+	{
+		self.CreateKeyEx(sub_key, None, co::REG_OPTION::NON_VOLATILE,
+			access_rights, None)
+			.map(|(hkey, _)| HkeyGuard::new(hkey))
+	}
+
+	/// Reads the key and all its subkeys, recursively, into a
+	/// [`RegFile`](crate::RegFile), which can be serialized into a `.reg` file.
+	///
+	/// The key must be a predefined key, like `HKEY::CURRENT_USER`, or one of
+	/// its subkeys, given by `sub_key`, because the root key name is written
+	/// to the file. Otherwise, fails with
+	/// [`ERROR::INVALID_HANDLE`](crate::co::ERROR::INVALID_HANDLE).
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::HKEY;
+	///
+	/// HKEY::CURRENT_USER
+	///     .export_reg("Software\\My Company")?
+	///     .serialize_to_file("C:\\Temp\\backup.reg")?;
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	fn export_reg(self, sub_key: &str) -> WinResult<RegFile> {
+		let hkey = HKEY(unsafe { self.as_ptr() });
+		let root_name = ROOT_KEYS.iter()
+			.find(|(_, _, root)| *root == hkey)
+			.map(|(name, _, _)| *name)
+			.ok_or(co::ERROR::INVALID_HANDLE)?;
+
+		let mut path = root_name.to_owned();
+		if !sub_key.is_empty() {
+			path.push('\\');
+			path.push_str(sub_key.trim_matches('\\'));
+		}
+
+		let mut reg = RegFile::default();
+		export_key(hkey, sub_key, path, &mut reg.keys)?;
+		Ok(reg)
+	}
+
+	/// Writes a [`RegFile`](crate::RegFile) to the registry, like double
+	/// clicking a `.reg` file: keys are created if they don't exist, values are
+	/// set, and the keys and values marked to be deleted are deleted, if they
+	/// exist.
+	///
+	/// The key paths must start with a root key name, like
+	/// `HKEY_CURRENT_USER`, or its abbreviation, like `HKCU`. Otherwise, fails
+	/// with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER).
+	/// Deleting a root key also fails this way.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{HKEY, RegFile};
+	///
+	/// let reg = RegFile::parse_from_file("C:\\Temp\\backup.reg")
+	///     .map_err(|e| e.to_string())?;
+	/// HKEY::import_reg(&reg)?;
+	/// # Ok::<_, Box<dyn std::error::Error>>(())
+	/// ```
+	fn import_reg(reg: &RegFile) -> WinResult<()> {
+		for key in reg.keys.iter() {
+			let (root, sub_key) = split_root(&key.path)
+				.ok_or(co::ERROR::INVALID_PARAMETER)?;
+
+			if key.delete {
+				if sub_key.is_empty() {
+					return Err(co::ERROR::INVALID_PARAMETER);
+				}
+				match root.DeleteTree(Some(sub_key)) {
+					Ok(_) | Err(co::ERROR::FILE_NOT_FOUND) => continue,
+					Err(err) => return Err(err),
+				}
+			}
+
+			let hkey = root.create_key(sub_key, co::KEY::SET_VALUE)?;
+			for value in key.values.iter() {
+				match &value.data {
+					Some(data) => hkey.SetValueEx(&value.name, data.clone())?,
+					None => match hkey.DeleteValue(&value.name) {
+						Ok(_) | Err(co::ERROR::FILE_NOT_FOUND) => {},
+						Err(err) => return Err(err),
+					},
+				}
+			}
+			hkey.close()?;
+		}
+		Ok(())
+	}
+
+	/// Tells whether the key is one of the predefined keys, like
+	/// `HKEY::CURRENT_USER`, which are always open and don't need to be
+	/// closed.
+	fn is_predefined(self) -> bool {
+		[HKEY::CLASSES_ROOT, HKEY::CURRENT_USER, HKEY::LOCAL_MACHINE, HKEY::USERS,
+			HKEY::PERFORMANCE_DATA, HKEY::PERFORMANCE_TEXT, HKEY::PERFORMANCE_NLSTEXT,
+			HKEY::CURRENT_CONFIG, HKEY::DYN_DATA, HKEY::CURRENT_USER_LOCAL_SETTINGS,
+		].contains(&HKEY(unsafe { self.as_ptr() }))
+	}
+
+	/// Opens the key with
+	/// [`HKEY::OpenKeyEx`](crate::prelude::AdvapiHkey::OpenKeyEx), and returns
+	/// it within a guard, which closes it automatically.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{co, HKEY};
+	///
+	/// let hkey = HKEY::CURRENT_USER.open_key(
+	///     "Control Panel\\Mouse",
+	///     co::KEY::READ,
+	/// )?;
+	///
+	/// let speed = hkey.QueryValueEx("MouseSpeed")?;
+	/// # Ok::<_, co::ERROR>(())
+	/// ```
+	fn open_key(self,
+		sub_key: &str, access_rights: co::KEY) -> WinResult<HkeyGuard>
+	{
+		self.OpenKeyEx(sub_key, co::REG_OPTION::default(), access_rights)
+			.map(HkeyGuard::new)
+	}
+
 	/// [`RegCloseKey`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey)
 	/// method.
 	fn CloseKey(self) -> WinResult<()> {
@@ -49,6 +204,167 @@ pub trait AdvapiHkey: Handle {
 		}
 	}
 
+	/// [`RegCopyTree`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regcopytreew)
+	/// method.
+	///
+	/// Copies the values and subkeys of the key, or of its `sub_key`, into
+	/// `dest`, which must be open with
+	/// [`KEY::CREATE_SUB_KEY`](crate::co::KEY::CREATE_SUB_KEY) and
+	/// [`KEY::SET_VALUE`](crate::co::KEY::SET_VALUE) access rights.
+	fn CopyTree(self, sub_key: Option<&str>, dest: HKEY) -> WinResult<()> {
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegCopyTreeW(
+					self.as_ptr(),
+					WString::from_opt_str(sub_key).as_ptr(),
+					dest.0,
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
+		}
+	}
+
+	/// [`RegCreateKeyEx`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regcreatekeyexw)
+	/// method.
+	///
+	/// Opens the key, creating it if it doesn't exist, and tells which one
+	/// happened. Intermediate keys are also created.
+	///
+	/// **Note:** Must be paired with an
+	/// [`HKEY::CloseKey`](crate::prelude::AdvapiHkey::CloseKey) call. Or, use
+	/// [`HKEY::create_key`](crate::prelude::AdvapiHkey::create_key), which
+	/// closes the key automatically.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{co, HKEY};
+	///
+	/// let (hkey, disposition) = HKEY::CURRENT_USER.CreateKeyEx(
+	///     "Software\\My Company",
+	///     None,
+	///     co::REG_OPTION::NON_VOLATILE,
+	///     co::KEY::ALL_ACCESS,
+	///     None,
+	/// )?;
+	///
+	/// if disposition == co::REG_DISPOSITION::CREATED_NEW_KEY {
+	///     println!("First run.");
+	/// }
+	///
+	/// hkey.CloseKey()?;
+	/// # Ok::<_, co::ERROR>(())
+	/// ```
+	fn CreateKeyEx(self,
+		sub_key: &str,
+		class: Option<&str>,
+		options: co::REG_OPTION,
+		access_rights: co::KEY,
+		security_attributes: Option<&mut SECURITY_ATTRIBUTES>,
+	) -> WinResult<(HKEY, co::REG_DISPOSITION)>
+	{
+		let mut hkey = HKEY::NULL;
+		let mut disposition = co::REG_DISPOSITION::default();
+
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegCreateKeyExW(
+					self.as_ptr(),
+					WString::from_str(sub_key).as_ptr(),
+					0,
+					WString::from_opt_str(class).as_ptr() as _,
+					options.0,
+					access_rights.0,
+					security_attributes.map_or(std::ptr::null_mut(), |sa| sa as *mut _ as _),
+					&mut hkey.0,
+					&mut disposition.0,
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok((hkey, disposition)),
+			err => Err(err),
+		}
+	}
+
+	/// [`RegDeleteKeyEx`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletekeyexw)
+	/// method.
+	///
+	/// Deletes the subkey, which must not have subkeys itself; to delete a
+	/// whole tree, use
+	/// [`HKEY::DeleteTree`](crate::prelude::AdvapiHkey::DeleteTree).
+	///
+	/// The `platform_view` can be
+	/// [`KEY::WOW64_32KEY`](crate::co::KEY::WOW64_32KEY) or
+	/// [`KEY::WOW64_64KEY`](crate::co::KEY::WOW64_64KEY) to delete the key
+	/// from the 32 or 64-bit registry view, or `KEY::default()` to use the view
+	/// of the current process.
+	fn DeleteKeyEx(self, sub_key: &str, platform_view: co::KEY) -> WinResult<()> {
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegDeleteKeyExW(
+					self.as_ptr(),
+					WString::from_str(sub_key).as_ptr(),
+					platform_view.0,
+					0,
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
+		}
+	}
+
+	/// [`RegDeleteTree`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletetreew)
+	/// method.
+	///
+	/// Deletes the subkey with all its values and subkeys, recursively. If
+	/// `sub_key` is `None`, the values and subkeys of the key itself are
+	/// deleted, but the key is kept.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::HKEY;
+	///
+	/// HKEY::CURRENT_USER.DeleteTree(Some("Software\\My Company"))?;
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	fn DeleteTree(self, sub_key: Option<&str>) -> WinResult<()> {
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegDeleteTreeW(
+					self.as_ptr(),
+					WString::from_opt_str(sub_key).as_ptr(),
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
+		}
+	}
+
+	/// [`RegDeleteValue`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletevaluew)
+	/// method.
+	///
+	/// An empty `value` deletes the default value of the key.
+	fn DeleteValue(self, value: &str) -> WinResult<()> {
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegDeleteValueW(
+					self.as_ptr(),
+					WString::from_str(value).as_ptr(),
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
+		}
+	}
+
 	/// Returns an iterator over the names of the keys, which calls
 	/// [`RegEnumKeyEx`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regenumkeyexw)
 	/// repeatedly.
@@ -105,6 +421,18 @@ pub trait AdvapiHkey: Handle {
 		Ok(Box::new(EnumValueIter::new(HKEY(unsafe { self.as_ptr() }))?))
 	}
 
+	/// [`RegFlushKey`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regflushkey)
+	/// method.
+	///
+	/// Writes the changes of the key to disk right away. This is rarely
+	/// needed, since the registry is flushed lazily by the system.
+	fn FlushKey(self) -> WinResult<()> {
+		match co::ERROR(unsafe { advapi::ffi::RegFlushKey(self.as_ptr()) } as _) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
+		}
+	}
+
 	/// [`RegGetValue`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew)
 	/// method.
 	///
@@ -126,6 +454,7 @@ pub trait AdvapiHkey: Handle {
 	///     RegistryValue::Dword(n) => println!("Number u32: {}", n),
 	///     RegistryValue::Qword(n) => println!("Number u64: {}", n),
 	///     RegistryValue::Sz(str) => println!("String: {}", str),
+	///     RegistryValue::MultiSz(strs) => println!("Strings: {:?}", strs),
 	///     RegistryValue::Binary(bin) => {
 	///         println!("Binary:");
 	///         for b in bin.iter() {
@@ -134,6 +463,7 @@ pub trait AdvapiHkey: Handle {
 	///         println!("");
 	///     },
 	///     RegistryValue::None => println!("No value"),
+	///     other => println!("Type: {}", other.reg_type()),
 	/// }
 	/// # Ok::<_, winsafe::co::ERROR>(())
 	/// ```
@@ -181,21 +511,64 @@ pub trait AdvapiHkey: Handle {
 			err => return Err(err),
 		}
 
-		// Return the value according to type.
-		match co::REG(raw_data_type) {
-			co::REG::NONE => Ok(RegistryValue::None),
-			co::REG::DWORD => Ok(RegistryValue::Dword(
-				u32::from_ne_bytes(buf.try_into().unwrap())),
-			),
-			co::REG::QWORD => Ok(RegistryValue::Qword(
-				u64::from_ne_bytes(buf.try_into().unwrap())),
-			),
-			co::REG::SZ => {
-				let (_, vec16, _) = unsafe { buf.align_to::<u16>() };
-				Ok(RegistryValue::Sz(WString::from_wchars_slice(&vec16)))
-			},
-			co::REG::BINARY => Ok(RegistryValue::Binary(buf)),
-			_ => Ok(RegistryValue::None), // other types not implemented yet
+		buf.truncate(data_len as _);
+		Ok(RegistryValue::from_bytes(co::REG(raw_data_type), buf))
+	}
+
+	/// [`RegNotifyChangeKeyValue`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regnotifychangekeyvalue)
+	/// method.
+	///
+	/// If `event` is `None`, blocks until a change happens. Otherwise, returns
+	/// immediately, and the event is signaled when a change happens. Either
+	/// way, only the first change is reported, so the method must be called
+	/// again to keep watching.
+	///
+	/// The key must be open with
+	/// [`KEY::NOTIFY`](crate::co::KEY::NOTIFY) access right.
+	///
+	/// # Examples
+	///
+	/// Waiting up to 10 seconds for a change:
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{co, HEVENT, HKEY};
+	///
+	/// let hkey = HKEY::CURRENT_USER.open_key(
+	///     "Software\\My Company",
+	///     co::KEY::NOTIFY,
+	/// )?;
+	///
+	/// let hevent = HEVENT::CreateEvent(None, false, false, None)?;
+	/// hkey.NotifyChangeKeyValue(true,
+	///     co::REG_NOTIFY::CHANGE_NAME | co::REG_NOTIFY::CHANGE_LAST_SET,
+	///     Some(hevent))?;
+	///
+	/// if hevent.WaitForSingleObject(Some(10_000))? == co::WAIT::OBJECT_0 {
+	///     println!("Settings changed.");
+	/// }
+	///
+	/// hevent.CloseHandle()?;
+	/// # Ok::<_, co::ERROR>(())
+	/// ```
+	fn NotifyChangeKeyValue(self,
+		watch_subtree: bool,
+		notify_filter: co::REG_NOTIFY,
+		event: Option<HEVENT>) -> WinResult<()>
+	{
+		match co::ERROR(
+			unsafe {
+				advapi::ffi::RegNotifyChangeKeyValue(
+					self.as_ptr(),
+					watch_subtree as _,
+					notify_filter.0,
+					event.map_or(std::ptr::null_mut(), |h| h.0),
+					event.is_some() as _,
+				)
+			} as _,
+		) {
+			co::ERROR::SUCCESS => Ok(()),
+			err => Err(err),
 		}
 	}
 
@@ -203,7 +576,9 @@ pub trait AdvapiHkey: Handle {
 	/// method.
 	///
 	/// **Note:** Must be paired with an
-	/// [`HKEY::CloseKey`](crate::prelude::AdvapiHkey::CloseKey) call.
+	/// [`HKEY::CloseKey`](crate::prelude::AdvapiHkey::CloseKey) call. Or, use
+	/// [`HKEY::open_key`](crate::prelude::AdvapiHkey::open_key), which closes
+	/// the key automatically.
 	///
 	/// # Examples
 	///
@@ -223,7 +598,7 @@ pub trait AdvapiHkey: Handle {
 	fn OpenKeyEx(self, sub_key: &str,
 		options: co::REG_OPTION, access_rights: co::KEY) -> WinResult<HKEY>
 	{
-		let hKey = HKEY::NULL;
+		let mut hKey = HKEY::NULL;
 
 		match co::ERROR(
 			unsafe {
@@ -232,7 +607,7 @@ pub trait AdvapiHkey: Handle {
 					WString::from_str(sub_key).as_ptr(),
 					options.0,
 					access_rights.0,
-					&mut hKey.as_ptr(),
+					&mut hKey.0,
 				)
 			} as _,
 		) {
@@ -332,6 +707,7 @@ pub trait AdvapiHkey: Handle {
 	///     RegistryValue::Dword(n) => println!("Number u32: {}", n),
 	///     RegistryValue::Qword(n) => println!("Number u64: {}", n),
 	///     RegistryValue::Sz(str) => println!("String: {}", str),
+	///     RegistryValue::MultiSz(strs) => println!("Strings: {:?}", strs),
 	///     RegistryValue::Binary(bin) => {
 	///         println!("Binary:");
 	///         for b in bin.iter() {
@@ -340,6 +716,7 @@ pub trait AdvapiHkey: Handle {
 	///         println!("");
 	///     },
 	///     RegistryValue::None => println!("No value"),
+	///     other => println!("Type: {}", other.reg_type()),
 	/// }
 	///
 	/// hkey.CloseKey()?;
@@ -386,22 +763,8 @@ pub trait AdvapiHkey: Handle {
 			err => return Err(err),
 		}
 
-		// Return the value according to type.
-		match co::REG(raw_data_type) {
-			co::REG::NONE => Ok(RegistryValue::None),
-			co::REG::DWORD => Ok(RegistryValue::Dword(
-				u32::from_ne_bytes(buf.try_into().unwrap())),
-			),
-			co::REG::QWORD => Ok(RegistryValue::Qword(
-				u64::from_ne_bytes(buf.try_into().unwrap())),
-			),
-			co::REG::SZ => {
-				let (_, vec16, _) = unsafe { buf.align_to::<u16>() };
-				Ok(RegistryValue::Sz(WString::from_wchars_slice(&vec16)))
-			},
-			co::REG::BINARY => Ok(RegistryValue::Binary(buf)),
-			_ => Ok(RegistryValue::None), // other types not implemented yet
-		}
+		buf.truncate(data_len as _);
+		Ok(RegistryValue::from_bytes(co::REG(raw_data_type), buf))
 	}
 
 	/// [`RegSetKeyValue`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regsetvalueexw)
@@ -410,22 +773,30 @@ pub trait AdvapiHkey: Handle {
 	/// If the value doesn't exist, if will be created. If new type is different
 	/// from current type, new type will take over.
 	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if a
+	/// string has a null char, or a
+	/// [`RegistryValue::MultiSz`](crate::RegistryValue::MultiSz) has an empty
+	/// string.
+	///
 	/// # Examples
 	///
 	/// ```rust,no_run
 	/// use winsafe::prelude::*;
-	/// use winsafe::{HKEY, RegistryValue, WString};
+	/// use winsafe::{HKEY, RegistryValue};
 	///
 	/// HKEY::CURRENT_USER.SetKeyValue(
 	///     "Software\\My Company",
 	///     "Color",
-	///     RegistryValue::Sz(WString::from_str("blue")),
+	///     RegistryValue::Sz("blue".to_owned()),
 	/// )?;
 	/// # Ok::<_, winsafe::co::ERROR>(())
 	/// ```
 	fn SetKeyValue(self,
 		sub_key: &str, value: &str, data: RegistryValue) -> WinResult<()>
 	{
+		data.validate()?;
+		let raw_data = data.to_bytes();
 		match co::ERROR(
 			unsafe {
 				advapi::ffi::RegSetKeyValueW(
@@ -433,8 +804,8 @@ pub trait AdvapiHkey: Handle {
 					WString::from_str(sub_key).as_ptr(),
 					WString::from_str(value).as_ptr(),
 					data.reg_type().0,
-					data.as_ptr(),
-					data.len() as _,
+					raw_data.as_ptr() as _,
+					raw_data.len() as _,
 				)
 			} as _,
 		) {
@@ -449,11 +820,17 @@ pub trait AdvapiHkey: Handle {
 	/// If the value doesn't exist, if will be created. If new type is different
 	/// from current type, new type will prevail.
 	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if a
+	/// string has a null char, or a
+	/// [`RegistryValue::MultiSz`](crate::RegistryValue::MultiSz) has an empty
+	/// string.
+	///
 	/// # Examples
 	///
 	/// ```rust,no_run
 	/// use winsafe::prelude::*;
-	/// use winsafe::{co, HKEY, RegistryValue, WString};
+	/// use winsafe::{co, HKEY, RegistryValue};
 	///
 	/// let hkey = HKEY::CURRENT_USER.OpenKeyEx(
 	///     "Console\\Git Bash",
@@ -463,13 +840,19 @@ pub trait AdvapiHkey: Handle {
 	///
 	/// hkey.SetValueEx(
 	///     "Color",
-	///     RegistryValue::Sz(WString::from_str("blue")),
+	///     RegistryValue::Sz("blue".to_owned()),
+	/// )?;
+	/// hkey.SetValueEx(
+	///     "Palette",
+	///     RegistryValue::MultiSz(vec!["red".to_owned(), "green".to_owned()]),
 	/// )?;
 	///
 	/// hkey.CloseKey()?;
 	/// # Ok::<_, co::ERROR>(())
 	/// ```
 	fn SetValueEx(self, value: &str, data: RegistryValue) -> WinResult<()> {
+		data.validate()?;
+		let raw_data = data.to_bytes();
 		match co::ERROR(
 			unsafe {
 				advapi::ffi::RegSetValueExW(
@@ -477,8 +860,8 @@ pub trait AdvapiHkey: Handle {
 					WString::from_str(value).as_ptr(),
 					0,
 					data.reg_type().0,
-					data.as_ptr() as _,
-					data.len() as _,
+					raw_data.as_ptr(),
+					raw_data.len() as _,
 				)
 			} as _,
 		) {
@@ -610,3 +993,47 @@ impl<'a> EnumValueIter<'a> {
 		})
 	}
 }
+
+/// Names and abbreviations of the predefined keys which can appear in a `.reg`
+/// file.
+const ROOT_KEYS: [(&str, &str, HKEY); 5] = [
+	("HKEY_CLASSES_ROOT", "HKCR", HKEY::CLASSES_ROOT),
+	("HKEY_CURRENT_USER", "HKCU", HKEY::CURRENT_USER),
+	("HKEY_LOCAL_MACHINE", "HKLM", HKEY::LOCAL_MACHINE),
+	("HKEY_USERS", "HKU", HKEY::USERS),
+	("HKEY_CURRENT_CONFIG", "HKCC", HKEY::CURRENT_CONFIG),
+];
+
+/// Splits a full key path into its predefined root key and the subkey path.
+fn split_root(path: &str) -> Option<(HKEY, &str)> {
+	let (root_name, sub_key) = match path.split_once('\\') {
+		Some((root_name, sub_key)) => (root_name, sub_key.trim_matches('\\')),
+		None => (path, ""),
+	};
+	ROOT_KEYS.iter()
+		.find(|(name, abbrev, _)|
+			root_name.eq_ignore_ascii_case(name) || root_name.eq_ignore_ascii_case(abbrev))
+		.map(|(_, _, root)| (*root, sub_key))
+}
+
+/// Appends the key, its values and its subkeys to the list, recursively.
+fn export_key(parent: HKEY,
+	sub_key: &str, path: String, keys: &mut Vec<RegFileKey>) -> WinResult<()>
+{
+	let hkey = parent.open_key(sub_key, co::KEY::READ)?;
+
+	let mut values = Vec::default();
+	for name_and_type in hkey.EnumValue()? {
+		let (name, _) = name_and_type?;
+		let data = hkey.QueryValueEx(&name)?;
+		values.push(RegFileValue { name, data: Some(data) });
+	}
+
+	let sub_keys = hkey.EnumKeyEx()?.collect::<WinResult<Vec<_>>>()?;
+	keys.push(RegFileKey { path: path.clone(), delete: false, values });
+
+	for sub_key in sub_keys.iter() {
+		export_key(*hkey, sub_key, format!("{}\\{}", path, sub_key), keys)?;
+	}
+	Ok(())
+}
@@ -6,11 +6,13 @@ pub mod co;
 mod enums;
 mod funcs;
 mod handles;
+mod utilities;
 
 pub mod decl {
 	pub use super::enums::*;
 	pub use super::funcs::*;
 	pub use super::handles::decl::*;
+	pub use super::utilities::*;
 }
 
 pub mod traits {
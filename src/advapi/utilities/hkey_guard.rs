@@ -0,0 +1,81 @@
+use crate::advapi::decl::HKEY;
+use crate::kernel::decl::WinResult;
+use crate::prelude::AdvapiHkey;
+
+/// Owns an open [`HKEY`](crate::HKEY), which is closed with
+/// [`HKEY::CloseKey`](crate::prelude::AdvapiHkey::CloseKey) when the object
+/// goes out of scope, so the key is not leaked on early returns.
+///
+/// The guard dereferences to the `HKEY`, so all its methods can be called
+/// directly. It's usually created by
+/// [`HKEY::open_key`](crate::prelude::AdvapiHkey::open_key) or
+/// [`HKEY::create_key`](crate::prelude::AdvapiHkey::create_key).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::{co, HKEY, RegistryValue};
+///
+/// let hkey = HKEY::CURRENT_USER.create_key(
+///     "Software\\My Company",
+///     co::KEY::ALL_ACCESS,
+/// )?;
+///
+/// hkey.SetValueEx("Color", RegistryValue::new_sz("blue"))?;
+///
+/// // CloseKey() called automatically when hkey goes out of scope
+/// # Ok::<_, co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+pub struct HkeyGuard {
+	hkey: HKEY,
+}
+
+impl Drop for HkeyGuard {
+	fn drop(&mut self) {
+		if !self.hkey.is_predefined() {
+			self.hkey.CloseKey().ok(); // ignore errors
+		}
+	}
+}
+
+impl std::ops::Deref for HkeyGuard {
+	type Target = HKEY;
+
+	fn deref(&self) -> &Self::Target {
+		&self.hkey
+	}
+}
+
+impl HkeyGuard {
+	/// Takes ownership of an open key, which will be closed when the guard
+	/// goes out of scope. Predefined keys, like `HKEY::CURRENT_USER`, are
+	/// never closed.
+	pub const fn new(hkey: HKEY) -> HkeyGuard {
+		Self { hkey }
+	}
+
+	/// Closes the key right away, returning any error.
+	pub fn close(self) -> WinResult<()> {
+		let hkey = self.leak();
+		if hkey.is_predefined() {
+			Ok(())
+		} else {
+			hkey.CloseKey()
+		}
+	}
+
+	/// Returns the underlying key handle, which is still owned by the guard.
+	pub const fn hkey(&self) -> HKEY {
+		self.hkey
+	}
+
+	/// Releases the ownership of the key, which won't be closed by the guard
+	/// anymore, and returns it.
+	pub fn leak(self) -> HKEY {
+		let hkey = self.hkey;
+		std::mem::forget(self);
+		hkey
+	}
+}
@@ -0,0 +1,8 @@
+mod hkey_guard;
+mod reg_file;
+
+#[cfg(feature = "serde")] pub mod reg_serde;
+
+pub use hkey_guard::HkeyGuard;
+pub use reg_file::{RegFile, RegFileError, RegFileErrorKind, RegFileKey,
+	RegFileValue, RegFileVersion};
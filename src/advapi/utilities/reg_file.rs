@@ -0,0 +1,659 @@
+use crate::co;
+use crate::advapi::decl::RegistryValue;
+use crate::kernel::decl::{Encoding, ErrResult, FileAccess, FileMapped, HFILE,
+	WinResult, WString};
+use crate::prelude::{HandleClose, KernelHfile};
+
+const HEADER_V4: &str = "REGEDIT4";
+const HEADER_V5: &str = "Windows Registry Editor Version 5.00";
+
+/// Keeps the keys and values of a `.reg` file, as written by `regedit.exe`,
+/// also doing parsing and serialization of the data.
+///
+/// Parsing and serialization are done in pure Rust, without touching the
+/// registry. To read the data from the registry, use
+/// [`HKEY::export_reg`](crate::prelude::AdvapiHkey::export_reg); to write it
+/// back, use
+/// [`HKEY::import_reg`](crate::prelude::AdvapiHkey::import_reg).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::{RegFile, RegistryValue};
+///
+/// let reg = RegFile::parse_str(
+///     "Windows Registry Editor Version 5.00\r\n\
+///     \r\n\
+///     [HKEY_CURRENT_USER\\Software\\My Company]\r\n\
+///     \"Color\"=\"blue\"\r\n\
+///     \"Size\"=dword:0000000a\r\n",
+/// )?;
+///
+/// let key = &reg.keys[0];
+/// assert_eq!(key.path, "HKEY_CURRENT_USER\\Software\\My Company");
+/// assert_eq!(key.values[1].data, Some(RegistryValue::Dword(10)));
+/// # Ok::<_, winsafe::RegFileError>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegFile {
+	/// The format version, given by the header line.
+	pub version: RegFileVersion,
+	/// The keys, in the order they appear in the file.
+	pub keys: Vec<RegFileKey>,
+}
+
+/// The format version of a [`RegFile`](crate::RegFile).
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RegFileVersion {
+	/// `REGEDIT4` header, used by old Windows versions. The file is written
+	/// in [`Encoding::Win1252`](crate::Encoding::Win1252), and strings within
+	/// `hex(n):` data have one byte per char.
+	Regedit4,
+	/// `Windows Registry Editor Version 5.00` header. The file is written in
+	/// [`Encoding::Utf16le`](crate::Encoding::Utf16le), and strings within
+	/// `hex(n):` data are UTF-16. This is the default.
+	#[default]
+	Regedit5,
+}
+
+/// A key of a [`RegFile`](crate::RegFile), written as `[path]`, or `[-path]`
+/// if the key is to be deleted.
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegFileKey {
+	/// Full path of the key, starting with the root key name, like
+	/// `HKEY_CURRENT_USER\Software`.
+	pub path: String,
+	/// If `true`, the key and all its subkeys are to be deleted. A key to be
+	/// deleted has no values.
+	pub delete: bool,
+	/// The values of the key, in the order they appear in the file.
+	pub values: Vec<RegFileValue>,
+}
+
+/// A value of a [`RegFileKey`](crate::RegFileKey).
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegFileValue {
+	/// Name of the value. An empty name is the default value of the key,
+	/// written as `@`.
+	pub name: String,
+	/// The data of the value, or `None` if the value is to be deleted, which
+	/// is written as `"name"=-`.
+	pub data: Option<RegistryValue>,
+}
+
+/// Error returned by [`RegFile::parse_str`](crate::RegFile::parse_str).
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegFileError {
+	/// The line where the error occurred, starting at 1. If the entry spans
+	/// many lines, this is its first line.
+	pub line: usize,
+	/// The kind of the error.
+	pub kind: RegFileErrorKind,
+}
+
+/// The kind of a [`RegFileError`](crate::RegFileError).
+#[cfg_attr(docsrs, doc(cfg(feature = "advapi")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegFileErrorKind {
+	/// The first line is neither `REGEDIT4` nor
+	/// `Windows Registry Editor Version 5.00`.
+	MissingHeader,
+	/// A key without the closing `]`, or followed by something which is not
+	/// a comment.
+	UnterminatedKey,
+	/// A key with an empty path.
+	EmptyKey,
+	/// A value before the first key, or under a key to be deleted.
+	ValueOutsideKey,
+	/// A value name which is neither `@` nor a quoted string.
+	InvalidName,
+	/// A value name not followed by `=`.
+	MissingEquals,
+	/// Value data which cannot be parsed.
+	InvalidData,
+}
+
+impl std::error::Error for RegFileError {}
+
+impl std::fmt::Display for RegFileError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Line {}: {}", self.line, self.kind)
+	}
+}
+
+impl std::fmt::Display for RegFileErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", match self {
+			Self::MissingHeader => "missing registry file header",
+			Self::UnterminatedKey => "key without closing bracket",
+			Self::EmptyKey => "empty key path",
+			Self::ValueOutsideKey => "value outside of a key",
+			Self::InvalidName => "invalid value name",
+			Self::MissingEquals => "value name not followed by equals sign",
+			Self::InvalidData => "invalid value data",
+		})
+	}
+}
+
+impl RegFile {
+	/// Parses a `RegFile` from a string.
+	///
+	/// Lines ending with `\` continue on the next line, and lines starting
+	/// with `;` are comments, which are discarded.
+	pub fn parse_str(contents: &str) -> Result<RegFile, RegFileError> {
+		let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents); // BOM
+		let mut lines = LogicalLines::new(contents);
+		let err = |line, kind| RegFileError { line, kind };
+
+		let version = match lines.next() {
+			Some((_, header)) if header == HEADER_V4 => RegFileVersion::Regedit4,
+			Some((_, header)) if header == HEADER_V5 => RegFileVersion::Regedit5,
+			Some((line_no, _)) => return Err(err(line_no, RegFileErrorKind::MissingHeader)),
+			None => return Err(err(1, RegFileErrorKind::MissingHeader)),
+		};
+		let mut reg = RegFile { version, keys: Vec::default() };
+
+		for (line_no, line) in lines {
+			if let Some(rest) = line.strip_prefix('[') {
+				let close = rest.rfind(']')
+					.ok_or_else(|| err(line_no, RegFileErrorKind::UnterminatedKey))?;
+				if !is_blank_or_comment(&rest[close + 1..]) {
+					return Err(err(line_no, RegFileErrorKind::UnterminatedKey));
+				}
+				let (delete, path) = match rest[..close].strip_prefix('-') {
+					Some(path) => (true, path),
+					None => (false, &rest[..close]),
+				};
+				let path = path.trim();
+				if path.is_empty() {
+					return Err(err(line_no, RegFileErrorKind::EmptyKey));
+				}
+				reg.keys.push(RegFileKey { path: path.to_owned(), delete, values: Vec::default() });
+
+			} else {
+				let key = reg.keys.last_mut()
+					.filter(|key| !key.delete)
+					.ok_or_else(|| err(line_no, RegFileErrorKind::ValueOutsideKey))?;
+				let value = parse_value(&line, version)
+					.map_err(|kind| err(line_no, kind))?;
+				key.values.push(value);
+			}
+		}
+
+		Ok(reg)
+	}
+
+	/// Parses a `RegFile` from raw bytes. The [`Encoding`](crate::Encoding)
+	/// will be guessed with
+	/// [`WString::detect_encoding`](crate::WString::detect_encoding).
+	pub fn parse_bytes(bytes: &[u8]) -> ErrResult<RegFile> {
+		Ok(Self::parse_str(&WString::parse_str(bytes)?.to_string())?)
+	}
+
+	/// Parses a `RegFile` directly from a file.
+	pub fn parse_from_file(reg_path: &str) -> ErrResult<RegFile> {
+		let fin = FileMapped::open(reg_path, FileAccess::ExistingReadOnly)?;
+		Self::parse_bytes(fin.as_slice())
+	}
+
+	/// Serializes the data to a string, in the same layout of `regedit.exe`,
+	/// with `\r\n` line breaks.
+	///
+	/// Strings with line breaks or null chars are written as `hex(1):`, and
+	/// long binary data is broken into lines of up to 80 chars. With
+	/// [`RegFileVersion::Regedit4`](crate::RegFileVersion::Regedit4), chars
+	/// above U+00FF within `hex(n):` strings are written as `?`.
+	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if a
+	/// key path or a value name has a line break or a null char, which can't be
+	/// written to the file.
+	pub fn serialize_to_str(&self) -> WinResult<String> {
+		let mut buf = String::default();
+		buf.push_str(match self.version {
+			RegFileVersion::Regedit4 => HEADER_V4,
+			RegFileVersion::Regedit5 => HEADER_V5,
+		});
+		buf.push_str("\r\n\r\n");
+
+		for key in self.keys.iter() {
+			check_line(&key.path)?;
+			buf.push('[');
+			if key.delete {
+				buf.push('-');
+			}
+			buf.push_str(&key.path);
+			buf.push_str("]\r\n");
+
+			if !key.delete {
+				for value in key.values.iter() {
+					serialize_value(&mut buf, value, self.version)?;
+				}
+			}
+			buf.push_str("\r\n");
+		}
+
+		Ok(buf)
+	}
+
+	/// Serializes the data to raw bytes: UTF-16 with BOM for
+	/// [`RegFileVersion::Regedit5`](crate::RegFileVersion::Regedit5), and
+	/// Windows-1252 for
+	/// [`RegFileVersion::Regedit4`](crate::RegFileVersion::Regedit4), just
+	/// like `regedit.exe`.
+	///
+	/// Fails with
+	/// [`ERROR::NO_UNICODE_TRANSLATION`](crate::co::ERROR::NO_UNICODE_TRANSLATION)
+	/// if a `REGEDIT4` text has a char which cannot be represented in
+	/// Windows-1252, and like
+	/// [`serialize_to_str`](crate::RegFile::serialize_to_str).
+	pub fn serialize_to_bytes(&self) -> WinResult<Vec<u8>> {
+		WString::from_str(&self.serialize_to_str()?)
+			.serialize(match self.version {
+				RegFileVersion::Regedit4 => Encoding::Win1252,
+				RegFileVersion::Regedit5 => Encoding::Utf16le,
+			}, true)
+	}
+
+	/// Serializes the data directly to a file.
+	pub fn serialize_to_file(&self, reg_path: &str) -> WinResult<()> {
+		let (fout, _) = HFILE::CreateFile(reg_path, co::GENERIC::WRITE,
+			co::FILE_SHARE::NoValue, None, co::DISPOSITION::CREATE_ALWAYS,
+			co::FILE_ATTRIBUTE::NORMAL, None)?;
+
+		fout.WriteFile(&self.serialize_to_bytes()?, None)?;
+		fout.CloseHandle()?;
+		Ok(())
+	}
+}
+
+/// Iterates over the lines of the text, joining the lines ending with `\` and
+/// skipping blank lines and comments. Returns the number of the first line of
+/// each logical line, starting at 1.
+struct LogicalLines<'a> {
+	lines: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+impl<'a> LogicalLines<'a> {
+	fn new(contents: &'a str) -> Self {
+		Self { lines: contents.lines().enumerate() }
+	}
+}
+
+impl<'a> Iterator for LogicalLines<'a> {
+	type Item = (usize, std::borrow::Cow<'a, str>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (idx, first) = self.lines
+			.find(|(_, line)| !is_blank_or_comment(line))?;
+		let first = first.trim();
+
+		match first.strip_suffix('\\') {
+			None => Some((idx + 1, first.into())),
+			Some(first) => {
+				let mut joined = first.to_owned();
+				for (_, line) in self.lines.by_ref() {
+					let line = line.trim();
+					match line.strip_suffix('\\') {
+						Some(line) => joined.push_str(line),
+						None => {
+							joined.push_str(line);
+							break;
+						},
+					}
+				}
+				Some((idx + 1, joined.into()))
+			},
+		}
+	}
+}
+
+fn is_blank_or_comment(s: &str) -> bool {
+	let s = s.trim_start();
+	s.is_empty() || s.starts_with(';')
+}
+
+/// Parses a `"name"=data` or `@=data` line.
+fn parse_value(line: &str,
+	version: RegFileVersion) -> Result<RegFileValue, RegFileErrorKind>
+{
+	let (name, rest) = match line.strip_prefix('@') {
+		Some(rest) => (String::default(), rest),
+		None => parse_quoted(line).ok_or(RegFileErrorKind::InvalidName)?,
+	};
+
+	let data = rest.trim_start()
+		.strip_prefix('=')
+		.ok_or(RegFileErrorKind::MissingEquals)?
+		.trim_start();
+
+	let (data, rest) = if let Some(rest) = data.strip_prefix('-') {
+		(None, rest)
+	} else if data.starts_with('"') {
+		let (s, rest) = parse_quoted(data).ok_or(RegFileErrorKind::InvalidData)?;
+		(Some(RegistryValue::Sz(s)), rest)
+	} else if let Some(rest) = strip_prefix_ignore_case(data, "dword:") {
+		let digits = rest.find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(rest.len());
+		let n = match digits {
+			1..=8 => u32::from_str_radix(&rest[..digits], 16).unwrap(),
+			_ => return Err(RegFileErrorKind::InvalidData),
+		};
+		(Some(RegistryValue::Dword(n)), &rest[digits..])
+	} else if let Some(rest) = strip_prefix_ignore_case(data, "hex") {
+		let (reg_type, rest) = match rest.strip_prefix('(') {
+			Some(rest) => {
+				let close = rest.find(')').ok_or(RegFileErrorKind::InvalidData)?;
+				let reg_type = u32::from_str_radix(&rest[..close], 16)
+					.map_err(|_| RegFileErrorKind::InvalidData)?;
+				(co::REG(reg_type), &rest[close + 1..])
+			},
+			None => (co::REG::BINARY, rest),
+		};
+		let rest = rest.strip_prefix(':').ok_or(RegFileErrorKind::InvalidData)?;
+		let (bytes, rest) = parse_hex_bytes(rest)?;
+		(Some(hex_to_value(reg_type, bytes, version)), rest)
+	} else {
+		return Err(RegFileErrorKind::InvalidData);
+	};
+
+	if !is_blank_or_comment(rest) {
+		return Err(RegFileErrorKind::InvalidData);
+	}
+	Ok(RegFileValue { name, data })
+}
+
+/// Parses a string between quotes, with `\\` and `\"` escapes, returning it
+/// along with the rest of the text.
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+	let s = s.strip_prefix('"')?;
+	let mut parsed = String::default();
+	let mut chars = s.char_indices();
+
+	while let Some((idx, ch)) = chars.next() {
+		match ch {
+			'"' => return Some((parsed, &s[idx + 1..])),
+			'\\' => match chars.next() {
+				Some((_, esc @ ('\\' | '"'))) => parsed.push(esc),
+				_ => return None,
+			},
+			ch => parsed.push(ch),
+		}
+	}
+	None // closing quote not found
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+	match s.get(..prefix.len()) {
+		Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+		_ => None,
+	}
+}
+
+/// Parses comma-separated hex bytes, returning them along with the rest of the
+/// text.
+fn parse_hex_bytes(s: &str) -> Result<(Vec<u8>, &str), RegFileErrorKind> {
+	let mut bytes = Vec::default();
+	let mut rest = s.trim_start();
+
+	loop {
+		let digits = rest.find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(rest.len());
+		match digits {
+			0 => break, // no more bytes; a trailing comma is accepted
+			1 | 2 => bytes.push(u8::from_str_radix(&rest[..digits], 16).unwrap()),
+			_ => return Err(RegFileErrorKind::InvalidData),
+		}
+		rest = rest[digits..].trim_start();
+		match rest.strip_prefix(',') {
+			Some(after_comma) => rest = after_comma.trim_start(),
+			None => break,
+		}
+	}
+
+	Ok((bytes, rest))
+}
+
+/// Converts `hex(n):` data to a value. `REGEDIT4` strings have one byte per
+/// char.
+fn hex_to_value(reg_type: co::REG,
+	bytes: Vec<u8>, version: RegFileVersion) -> RegistryValue
+{
+	match (version, reg_type) {
+		(RegFileVersion::Regedit4, co::REG::SZ | co::REG::EXPAND_SZ | co::REG::MULTI_SZ) =>
+			RegistryValue::from_bytes(reg_type,
+				bytes.iter().flat_map(|by| [*by, 0x00]).collect()), // Latin-1 to UTF-16
+		_ => RegistryValue::from_bytes(reg_type, bytes),
+	}
+}
+
+fn serialize_value(buf: &mut String,
+	value: &RegFileValue, version: RegFileVersion) -> WinResult<()>
+{
+	let line_start = buf.len();
+	if value.name.is_empty() {
+		buf.push('@');
+	} else {
+		check_line(&value.name)?;
+		push_quoted(buf, &value.name);
+	}
+	buf.push('=');
+
+	match &value.data {
+		None => buf.push('-'),
+		Some(RegistryValue::Sz(s)) if !s.contains(['\r', '\n', '\0']) => push_quoted(buf, s),
+		Some(RegistryValue::Dword(n)) => buf.push_str(&format!("dword:{:08x}", n)),
+		Some(RegistryValue::Binary(bytes)) => {
+			buf.push_str("hex:");
+			push_hex_bytes(buf, line_start, bytes);
+		},
+		Some(data) => {
+			buf.push_str(&format!("hex({:x}):", data.reg_type().0));
+			let bytes = match (version, data) {
+				(RegFileVersion::Regedit4,
+					RegistryValue::Sz(_) | RegistryValue::ExpandSz(_) | RegistryValue::MultiSz(_)) =>
+				{
+					data.to_bytes()
+						.chunks_exact(2)
+						.map(|pair| match u16::from_le_bytes([pair[0], pair[1]]) {
+							ch @ 0x0000..=0x00ff => ch as u8,
+							_ => b'?', // not representable in one byte
+						})
+						.collect()
+				},
+				_ => data.to_bytes(),
+			};
+			push_hex_bytes(buf, line_start, &bytes);
+		},
+	}
+
+	buf.push_str("\r\n");
+	Ok(())
+}
+
+/// Fails if the text would break its line; only data can be written as hex.
+fn check_line(s: &str) -> WinResult<()> {
+	if s.contains(['\r', '\n', '\0']) {
+		Err(co::ERROR::INVALID_PARAMETER)
+	} else {
+		Ok(())
+	}
+}
+
+fn push_quoted(buf: &mut String, s: &str) {
+	buf.push('"');
+	for ch in s.chars() {
+		if ch == '\\' || ch == '"' {
+			buf.push('\\');
+		}
+		buf.push(ch);
+	}
+	buf.push('"');
+}
+
+/// Writes comma-separated hex bytes, breaking the line with a `\` so no line
+/// goes beyond 80 chars.
+fn push_hex_bytes(buf: &mut String, line_start: usize, bytes: &[u8]) {
+	let mut col = buf[line_start..].chars().count();
+
+	for (idx, by) in bytes.iter().enumerate() {
+		if idx > 0 {
+			buf.push(',');
+			col += 1;
+			if col + 3 > 79 { // room for the next byte, its comma and the backslash
+				buf.push_str("\\\r\n  ");
+				col = 2;
+			}
+		}
+		buf.push_str(&format!("{:02x}", by));
+		col += 2;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn value(name: &str, data: Option<RegistryValue>) -> RegFileValue {
+		RegFileValue { name: name.to_owned(), data }
+	}
+
+	fn strs(v: &[&str]) -> RegistryValue {
+		RegistryValue::MultiSz(v.iter().map(|s| s.to_string()).collect())
+	}
+
+	#[test]
+	fn parse_v5() {
+		let reg = RegFile::parse_str(concat!(
+			"\u{feff}Windows Registry Editor Version 5.00\r\n",
+			"\r\n",
+			"; a comment\r\n",
+			"[HKEY_CURRENT_USER\\Software\\A]\r\n",
+			"@=\"default\"\r\n",
+			"\"Say \\\"hi\\\"\"=\"C:\\\\dir\\\\\"\r\n",
+			"\"Expand\"=hex(2):25,00,54,00,45,00,4d,00,50,00,25,00,00,00\r\n",
+			"\"Multi\"=hex(7):61,00,00,00,62,00,63,00,00,\\\r\n",
+			"  00,00,00\r\n",
+			"\"Big\"=hex(b):ff,00,00,00,00,00,00,01\r\n",
+			"\"Size\"=DWORD:0000000A ; ten\r\n",
+			"\"Gone\"=-\r\n",
+			"\r\n",
+			"[-HKEY_CURRENT_USER\\Software\\B]\r\n",
+		)).unwrap();
+
+		assert_eq!(reg.version, RegFileVersion::Regedit5);
+		assert_eq!(reg.keys.len(), 2);
+		assert_eq!(reg.keys[0].path, "HKEY_CURRENT_USER\\Software\\A");
+		assert!(!reg.keys[0].delete);
+		assert_eq!(reg.keys[0].values, [
+			value("", Some(RegistryValue::new_sz("default"))),
+			value("Say \"hi\"", Some(RegistryValue::new_sz("C:\\dir\\"))),
+			value("Expand", Some(RegistryValue::ExpandSz("%TEMP%".to_owned()))),
+			value("Multi", Some(strs(&["a", "bc"]))),
+			value("Big", Some(RegistryValue::Qword(0x0100_0000_0000_00ff))),
+			value("Size", Some(RegistryValue::Dword(10))),
+			value("Gone", None),
+		]);
+		assert_eq!(reg.keys[1].path, "HKEY_CURRENT_USER\\Software\\B");
+		assert!(reg.keys[1].delete);
+	}
+
+	#[test]
+	fn parse_v4() {
+		let reg = RegFile::parse_str(concat!(
+			"REGEDIT4\r\n",
+			"\r\n",
+			"[HKEY_CURRENT_USER\\A]\r\n",
+			"\"Expand\"=hex(2):25,50,25,e9,00\r\n",
+			"\"Multi\"=hex(7):61,00,62,00,00\r\n",
+		)).unwrap();
+
+		assert_eq!(reg.version, RegFileVersion::Regedit4);
+		assert_eq!(reg.keys[0].values, [
+			value("Expand", Some(RegistryValue::ExpandSz("%P%é".to_owned()))),
+			value("Multi", Some(strs(&["a", "b"]))),
+		]);
+	}
+
+	#[test]
+	fn parse_errors() {
+		let err = |contents: &str| RegFile::parse_str(contents).unwrap_err();
+		let v5 = "Windows Registry Editor Version 5.00\r\n";
+
+		assert_eq!(err("REGEDIT5\r\n").kind, RegFileErrorKind::MissingHeader);
+		assert_eq!(err("").kind, RegFileErrorKind::MissingHeader);
+		assert_eq!(err(&format!("{}\"x\"=\"y\"", v5)),
+			RegFileError { line: 2, kind: RegFileErrorKind::ValueOutsideKey });
+		assert_eq!(err(&format!("{}[-A]\r\n\"x\"=-", v5)).kind, RegFileErrorKind::ValueOutsideKey);
+		assert_eq!(err(&format!("{}[A\r\n", v5)).kind, RegFileErrorKind::UnterminatedKey);
+		assert_eq!(err(&format!("{}[ ]\r\n", v5)).kind, RegFileErrorKind::EmptyKey);
+		assert_eq!(err(&format!("{}[A]\r\nx=\"y\"", v5)).kind, RegFileErrorKind::InvalidName);
+		assert_eq!(err(&format!("{}[A]\r\n\"x\" \"y\"", v5)).kind, RegFileErrorKind::MissingEquals);
+		assert_eq!(err(&format!("{}[A]\r\n\"x\"=dword:123456789", v5)).kind,
+			RegFileErrorKind::InvalidData);
+		assert_eq!(err(&format!("{}[A]\r\n\"x\"=hex:1,\\\r\n  zz", v5)),
+			RegFileError { line: 3, kind: RegFileErrorKind::InvalidData }); // first line of the entry
+	}
+
+	#[test]
+	fn parse_bytes_unpaired_surrogate() {
+		let text = "Windows Registry Editor Version 5.00\r\n[A]\r\n\"x\"=\"";
+		let mut bytes = vec![0xff, 0xfe]; // BOM
+		bytes.extend(text.encode_utf16()
+			.chain([0xd800, '"' as u16])
+			.flat_map(|ch| ch.to_le_bytes()));
+
+		let reg = RegFile::parse_bytes(&bytes).unwrap();
+		assert_eq!(reg.keys[0].values, [value("x", Some(RegistryValue::new_sz("\u{fffd}")))]);
+	}
+
+	#[test]
+	fn serialize_round_trip() {
+		for version in [RegFileVersion::Regedit4, RegFileVersion::Regedit5] {
+			let reg = RegFile {
+				version,
+				keys: vec![
+					RegFileKey {
+						path: "HKEY_CURRENT_USER\\Software\\A".to_owned(),
+						delete: false,
+						values: vec![
+							value("", Some(RegistryValue::new_sz("x"))),
+							value("q\"b\\", Some(RegistryValue::new_sz("two\r\nlines"))),
+							value("e", Some(RegistryValue::ExpandSz("%TEMP%".to_owned()))),
+							value("m", Some(strs(&["a", "b"]))),
+							value("d", Some(RegistryValue::Dword(7))),
+							value("b", Some(RegistryValue::Binary((0..100).collect()))),
+							value("gone", None),
+						],
+					},
+					RegFileKey {
+						path: "HKEY_CURRENT_USER\\Software\\B".to_owned(),
+						delete: true,
+						values: Vec::default(),
+					},
+				],
+			};
+
+			let text = reg.serialize_to_str().unwrap();
+			assert!(text.lines().all(|line| line.len() <= 80), "{}", text);
+			assert_eq!(RegFile::parse_str(&text).unwrap(), reg);
+		}
+	}
+
+	#[test]
+	fn serialize_rejects_line_breaks() {
+		let mut reg = RegFile::default();
+		reg.keys.push(RegFileKey { path: "A".to_owned(), ..Default::default() });
+		reg.keys[0].values.push(value("x\r\n\"y\"", Some(RegistryValue::Dword(1))));
+		assert_eq!(reg.serialize_to_str(), Err(co::ERROR::INVALID_PARAMETER));
+
+		reg.keys[0].values[0].name = "x".to_owned();
+		assert!(reg.serialize_to_str().is_ok());
+
+		reg.keys[0].path = "A]\n[B".to_owned();
+		assert_eq!(reg.serialize_to_str(), Err(co::ERROR::INVALID_PARAMETER));
+	}
+}
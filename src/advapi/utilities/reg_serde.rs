@@ -0,0 +1,1213 @@
+//! [Serde](https://serde.rs) serialization and deserialization of registry
+//! keys, which allows persisting a whole settings struct with one call.
+//!
+//! Data is mapped as follows:
+//!
+//! * the top-level value must be a struct or a map, which is written to the
+//!   given key;
+//! * fields which are structs or maps become subkeys, recursively;
+//! * `bool` and integers up to 32 bits become
+//!   [`REG::DWORD`](crate::co::REG::DWORD), and 64-bit integers become
+//!   [`REG::QWORD`](crate::co::REG::QWORD); signed integers are stored as
+//!   two's complement;
+//! * strings, chars, floats and unit enum variants become
+//!   [`REG::SZ`](crate::co::REG::SZ);
+//! * `Vec<u8>` and byte buffers become [`REG::BINARY`](crate::co::REG::BINARY);
+//! * `Vec<String>` and other sequences become
+//!   [`REG::MULTI_SZ`](crate::co::REG::MULTI_SZ), whose items cannot be empty;
+//! * `Option` fields are `None` when the value is absent; when serializing,
+//!   `None` fields delete their values or subkeys.
+//!
+//! Values and subkeys which are not fields are kept when serializing, and
+//! ignored when deserializing. Names are matched case-insensitively against
+//! the struct fields.
+//!
+//! The registry is accessed through the [`RegTree`](crate::reg_serde::RegTree)
+//! trait, which is implemented by [`HKEY`](crate::HKEY) and by the in-memory
+//! [`MemRegTree`](crate::reg_serde::MemRegTree).
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use serde::{Deserialize, Serialize};
+//! use winsafe::{HKEY, reg_serde};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Settings {
+//!     theme: String,
+//!     font_size: u32,
+//!     recent_files: Vec<String>,
+//!     window: Window,
+//! }
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Window {
+//!     width: i32,
+//!     height: i32,
+//!     maximized: Option<bool>,
+//! }
+//!
+//! let mut settings: Settings = reg_serde::from_key(
+//!     &HKEY::CURRENT_USER, "Software\\My Company\\My App")?;
+//!
+//! settings.font_size += 1;
+//!
+//! reg_serde::to_key(
+//!     &mut HKEY::CURRENT_USER, "Software\\My Company\\My App", &settings)?;
+//! # Ok::<_, reg_serde::Error>(())
+//! ```
+
+use serde::{de, ser};
+use serde::de::IntoDeserializer;
+
+use crate::co;
+use crate::advapi::decl::{HKEY, RegistryValue};
+use crate::kernel::decl::WinResult;
+use crate::prelude::AdvapiHkey;
+
+/// Error returned by the [`reg_serde`](crate::reg_serde) functions.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "advapi", feature = "serde"))))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// A registry operation failed.
+	Registry(co::ERROR),
+	/// The data could not be mapped.
+	Message(String),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Registry(err) => write!(f, "{}", err),
+			Self::Message(msg) => write!(f, "{}", msg),
+		}
+	}
+}
+
+impl From<co::ERROR> for Error {
+	fn from(err: co::ERROR) -> Self {
+		Self::Registry(err)
+	}
+}
+
+impl de::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Self::Message(msg.to_string())
+	}
+}
+
+impl ser::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Self::Message(msg.to_string())
+	}
+}
+
+/// A tree of registry keys, where [`reg_serde`](crate::reg_serde) reads and
+/// writes the data.
+///
+/// Keys are given by their paths relative to the root of the tree, with
+/// components separated by `\`; an empty path is the root itself. Operations
+/// on a key which doesn't exist fail with
+/// [`ERROR::FILE_NOT_FOUND`](crate::co::ERROR::FILE_NOT_FOUND).
+#[cfg_attr(docsrs, doc(cfg(all(feature = "advapi", feature = "serde"))))]
+pub trait RegTree {
+	/// Returns the names of the subkeys of the key.
+	fn keys(&self, path: &str) -> WinResult<Vec<String>>;
+
+	/// Returns the names and data of the values of the key.
+	fn values(&self, path: &str) -> WinResult<Vec<(String, RegistryValue)>>;
+
+	/// Creates the key, along with any intermediate keys. Succeeds if the key
+	/// already exists.
+	fn add_key(&mut self, path: &str) -> WinResult<()>;
+
+	/// Deletes the key, along with all its values and subkeys.
+	fn remove_key(&mut self, path: &str) -> WinResult<()>;
+
+	/// Sets a value of the key, replacing it if it already exists.
+	fn put_value(&mut self, path: &str, name: &str, data: RegistryValue) -> WinResult<()>;
+
+	/// Deletes a value of the key.
+	fn remove_value(&mut self, path: &str, name: &str) -> WinResult<()>;
+}
+
+/// The paths are relative to the `HKEY`, which is usually a predefined key,
+/// like `HKEY::CURRENT_USER`.
+impl RegTree for HKEY {
+	fn keys(&self, path: &str) -> WinResult<Vec<String>> {
+		self.open_key(path, co::KEY::READ)?
+			.EnumKeyEx()?
+			.collect()
+	}
+
+	fn values(&self, path: &str) -> WinResult<Vec<(String, RegistryValue)>> {
+		let hkey = self.open_key(path, co::KEY::READ)?;
+		let mut values = Vec::default();
+		for name_and_type in hkey.EnumValue()? {
+			let (name, _) = name_and_type?;
+			let data = hkey.QueryValueEx(&name)?;
+			values.push((name, data));
+		}
+		Ok(values)
+	}
+
+	fn add_key(&mut self, path: &str) -> WinResult<()> {
+		self.create_key(path, co::KEY::READ)?.close()
+	}
+
+	fn remove_key(&mut self, path: &str) -> WinResult<()> {
+		if path.is_empty() { // would delete the contents of the HKEY itself
+			return Err(co::ERROR::INVALID_PARAMETER);
+		}
+		self.DeleteTree(Some(path))
+	}
+
+	fn put_value(&mut self, path: &str, name: &str, data: RegistryValue) -> WinResult<()> {
+		self.open_key(path, co::KEY::SET_VALUE)?
+			.SetValueEx(name, data)
+	}
+
+	fn remove_value(&mut self, path: &str, name: &str) -> WinResult<()> {
+		self.open_key(path, co::KEY::SET_VALUE)?
+			.DeleteValue(name)
+	}
+}
+
+/// An in-memory [`RegTree`](crate::reg_serde::RegTree), which mimics the
+/// registry, including case-insensitive names. Useful to exercise the
+/// mapping without touching the registry.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use serde::{Deserialize, Serialize};
+/// use winsafe::{reg_serde, RegistryValue};
+/// use winsafe::reg_serde::MemRegTree;
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Settings {
+///     theme: String,
+///     font_size: u32,
+/// }
+///
+/// let settings = Settings { theme: "dark".to_owned(), font_size: 12 };
+///
+/// let mut tree = MemRegTree::default();
+/// reg_serde::to_key(&mut tree, "My App", &settings)?;
+///
+/// let key = tree.key("my app").unwrap();
+/// assert_eq!(key.value("FontSize"), None);
+/// assert_eq!(key.value("font_size"), Some(&RegistryValue::Dword(12)));
+///
+/// let read: Settings = reg_serde::from_key(&tree, "My App")?;
+/// assert_eq!(read, settings);
+/// # Ok::<_, reg_serde::Error>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(all(feature = "advapi", feature = "serde"))))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemRegTree {
+	/// The values of the key, in insertion order.
+	pub values: Vec<(String, RegistryValue)>,
+	/// The subkeys of the key, in insertion order.
+	pub sub_keys: Vec<(String, MemRegTree)>,
+}
+
+impl RegTree for MemRegTree {
+	fn keys(&self, path: &str) -> WinResult<Vec<String>> {
+		self.key(path)
+			.map(|key| key.sub_keys.iter().map(|(name, _)| name.clone()).collect())
+			.ok_or(co::ERROR::FILE_NOT_FOUND)
+	}
+
+	fn values(&self, path: &str) -> WinResult<Vec<(String, RegistryValue)>> {
+		self.key(path)
+			.map(|key| key.values.clone())
+			.ok_or(co::ERROR::FILE_NOT_FOUND)
+	}
+
+	fn add_key(&mut self, path: &str) -> WinResult<()> {
+		let mut key = self;
+		for name in split_path(path) {
+			let idx = match key.sub_keys.iter().position(|(n, _)| names_eq(n, name)) {
+				Some(idx) => idx,
+				None => {
+					key.sub_keys.push((name.to_owned(), MemRegTree::default()));
+					key.sub_keys.len() - 1
+				},
+			};
+			key = &mut key.sub_keys[idx].1;
+		}
+		Ok(())
+	}
+
+	fn remove_key(&mut self, path: &str) -> WinResult<()> {
+		let (parent_path, name) = path.trim_end_matches('\\')
+			.rsplit_once('\\')
+			.unwrap_or(("", path));
+		if name.is_empty() {
+			return Err(co::ERROR::INVALID_PARAMETER);
+		}
+		let parent = self.key_mut(parent_path).ok_or(co::ERROR::FILE_NOT_FOUND)?;
+		let idx = parent.sub_keys.iter()
+			.position(|(n, _)| names_eq(n, name))
+			.ok_or(co::ERROR::FILE_NOT_FOUND)?;
+		parent.sub_keys.remove(idx);
+		Ok(())
+	}
+
+	fn put_value(&mut self, path: &str, name: &str, data: RegistryValue) -> WinResult<()> {
+		let key = self.key_mut(path).ok_or(co::ERROR::FILE_NOT_FOUND)?;
+		match key.values.iter_mut().find(|(n, _)| names_eq(n, name)) {
+			Some((_, existing)) => *existing = data,
+			None => key.values.push((name.to_owned(), data)),
+		}
+		Ok(())
+	}
+
+	fn remove_value(&mut self, path: &str, name: &str) -> WinResult<()> {
+		let key = self.key_mut(path).ok_or(co::ERROR::FILE_NOT_FOUND)?;
+		let idx = key.values.iter()
+			.position(|(n, _)| names_eq(n, name))
+			.ok_or(co::ERROR::FILE_NOT_FOUND)?;
+		key.values.remove(idx);
+		Ok(())
+	}
+}
+
+impl MemRegTree {
+	/// Returns the key at the given path, if any; an empty path returns the
+	/// key itself.
+	pub fn key(&self, path: &str) -> Option<&MemRegTree> {
+		split_path(path).try_fold(self, |key, name| {
+			key.sub_keys.iter()
+				.find(|(n, _)| names_eq(n, name))
+				.map(|(_, sub_key)| sub_key)
+		})
+	}
+
+	/// Returns the data of the given value of the key, if any.
+	pub fn value(&self, name: &str) -> Option<&RegistryValue> {
+		self.values.iter()
+			.find(|(n, _)| names_eq(n, name))
+			.map(|(_, data)| data)
+	}
+
+	fn key_mut(&mut self, path: &str) -> Option<&mut MemRegTree> {
+		split_path(path).try_fold(self, |key, name| {
+			key.sub_keys.iter_mut()
+				.find(|(n, _)| names_eq(n, name))
+				.map(|(_, sub_key)| sub_key)
+		})
+	}
+}
+
+/// Deserializes a value from the given key of the tree.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "advapi", feature = "serde"))))]
+pub fn from_key<T, R>(tree: &R, path: &str) -> Result<T, Error>
+	where T: de::DeserializeOwned,
+		R: RegTree,
+{
+	T::deserialize(Node::Key(tree, path.to_owned()))
+}
+
+/// Serializes a value into the given key of the tree, which is created if it
+/// doesn't exist. Values and subkeys which are not fields are kept, and
+/// `None` fields delete their values or subkeys.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "advapi", feature = "serde"))))]
+pub fn to_key<T, R>(tree: &mut R, path: &str, value: &T) -> Result<(), Error>
+	where T: ser::Serialize + ?Sized,
+		R: RegTree,
+{
+	match value.serialize(FieldSerializer)? {
+		Field::Key(entries) => write_key(tree, path, entries),
+		_ => Err(Error::Message("top-level value must be a struct or a map".to_owned())),
+	}
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+	path.split('\\').filter(|name| !name.is_empty())
+}
+
+fn join_path(path: &str, name: &str) -> String {
+	if path.is_empty() {
+		name.to_owned()
+	} else {
+		format!("{}\\{}", path, name)
+	}
+}
+
+fn names_eq(a: &str, b: &str) -> bool {
+	a.eq_ignore_ascii_case(b) || a.to_lowercase() == b.to_lowercase()
+}
+
+fn invalid_value(val: &RegistryValue, expected: &str) -> Error {
+	Error::Message(format!("invalid value {:?}, expected {}", val, expected))
+}
+
+//------------------------------------------------------------------------------
+
+/// A value being deserialized: either the data of a value, or a whole key.
+enum Node<'a> {
+	Value(RegistryValue),
+	Key(&'a dyn RegTree, String),
+}
+
+macro_rules! deserialize_int {
+	($method:ident, $visit:ident, $ty:ty) => {
+		fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+			let val = self.value()?;
+			let num = match &val {
+				RegistryValue::Dword(n) | RegistryValue::DwordBigEndian(n) =>
+					<$ty>::try_from(*n).ok()
+						.or_else(|| <$ty>::try_from(*n as i32).ok()), // two's complement
+				RegistryValue::Qword(n) =>
+					<$ty>::try_from(*n).ok()
+						.or_else(|| <$ty>::try_from(*n as i64).ok()),
+				RegistryValue::Sz(s) => s.trim().parse::<$ty>().ok(),
+				_ => None,
+			}.ok_or_else(|| invalid_value(&val, stringify!($ty)))?;
+			visitor.$visit(num)
+		}
+	};
+}
+
+macro_rules! deserialize_float {
+	($method:ident, $visit:ident, $ty:ty) => {
+		fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+			let val = self.value()?;
+			let num = match &val {
+				RegistryValue::Dword(n) => Some(*n as $ty),
+				RegistryValue::Qword(n) => Some(*n as $ty),
+				RegistryValue::Sz(s) => s.trim().parse::<$ty>().ok(),
+				_ => None,
+			}.ok_or_else(|| invalid_value(&val, stringify!($ty)))?;
+			visitor.$visit(num)
+		}
+	};
+}
+
+impl<'a, 'de> de::Deserializer<'de> for Node<'a> {
+	type Error = Error;
+
+	fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self {
+			Self::Value(RegistryValue::Dword(n))
+				| Self::Value(RegistryValue::DwordBigEndian(n)) => visitor.visit_u32(n),
+			Self::Value(RegistryValue::Qword(n)) => visitor.visit_u64(n),
+			Self::Value(RegistryValue::Sz(s))
+				| Self::Value(RegistryValue::ExpandSz(s)) => visitor.visit_string(s),
+			Self::Value(RegistryValue::MultiSz(_)) => self.deserialize_seq(visitor),
+			Self::Value(RegistryValue::Binary(b))
+				| Self::Value(RegistryValue::Other(_, b)) => visitor.visit_byte_buf(b),
+			Self::Value(RegistryValue::None) => visitor.visit_unit(),
+			Self::Key(..) => self.deserialize_map(visitor),
+		}
+	}
+
+	fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let val = self.value()?;
+		let b = match &val {
+			RegistryValue::Dword(n) => Some(*n != 0),
+			RegistryValue::Qword(n) => Some(*n != 0),
+			RegistryValue::Sz(s) => match s.trim() {
+				s if s.eq_ignore_ascii_case("true") || s == "1" => Some(true),
+				s if s.eq_ignore_ascii_case("false") || s == "0" => Some(false),
+				_ => None,
+			},
+			_ => None,
+		}.ok_or_else(|| invalid_value(&val, "a boolean"))?;
+		visitor.visit_bool(b)
+	}
+
+	deserialize_int!(deserialize_i8, visit_i8, i8);
+	deserialize_int!(deserialize_i16, visit_i16, i16);
+	deserialize_int!(deserialize_i32, visit_i32, i32);
+	deserialize_int!(deserialize_i64, visit_i64, i64);
+	deserialize_int!(deserialize_u8, visit_u8, u8);
+	deserialize_int!(deserialize_u16, visit_u16, u16);
+	deserialize_int!(deserialize_u32, visit_u32, u32);
+	deserialize_int!(deserialize_u64, visit_u64, u64);
+	deserialize_float!(deserialize_f32, visit_f32, f32);
+	deserialize_float!(deserialize_f64, visit_f64, f64);
+
+	fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let s = self.string()?;
+		let mut chars = s.chars();
+		match (chars.next(), chars.next()) {
+			(Some(ch), None) => visitor.visit_char(ch),
+			_ => Err(invalid_value(&RegistryValue::Sz(s), "a single char")),
+		}
+	}
+
+	fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_string(self.string()?)
+	}
+
+	fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self.value()? {
+			RegistryValue::Binary(b) | RegistryValue::Other(_, b) => visitor.visit_byte_buf(b),
+			val => Err(invalid_value(&val, "binary data")),
+		}
+	}
+
+	fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self {
+			Self::Value(RegistryValue::None) => visitor.visit_none(),
+			_ => visitor.visit_some(self),
+		}
+	}
+
+	fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self.value()? {
+			RegistryValue::None => visitor.visit_unit(),
+			val => Err(invalid_value(&val, "no value")),
+		}
+	}
+
+	fn deserialize_unit_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_unit(visitor)
+	}
+
+	fn deserialize_newtype_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self.value()? {
+			RegistryValue::MultiSz(strs) => {
+				let mut seq = de::value::SeqDeserializer::<_, Error>::new(
+					strs.into_iter().map(|s| Node::Value(RegistryValue::Sz(s))));
+				let ret = visitor.visit_seq(&mut seq)?;
+				seq.end()?;
+				Ok(ret)
+			},
+			RegistryValue::Binary(b) => {
+				let mut seq = de::value::SeqDeserializer::<_, Error>::new(b.into_iter());
+				let ret = visitor.visit_seq(&mut seq)?;
+				seq.end()?;
+				Ok(ret)
+			},
+			val => Err(invalid_value(&val, "a list")),
+		}
+	}
+
+	fn deserialize_tuple<V: de::Visitor<'de>>(self,
+		_: usize, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, _: usize, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let (tree, path) = self.key()?;
+		visitor.visit_map(KeyMapAccess::new(tree, &path, &[])?)
+	}
+
+	fn deserialize_struct<V: de::Visitor<'de>>(self,
+		_: &'static str,
+		fields: &'static [&'static str],
+		visitor: V) -> Result<V::Value, Error>
+	{
+		let (tree, path) = self.key()?;
+		visitor.visit_map(KeyMapAccess::new(tree, &path, fields)?)
+	}
+
+	fn deserialize_enum<V: de::Visitor<'de>>(self,
+		_: &'static str,
+		_: &'static [&'static str],
+		visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_enum(
+			IntoDeserializer::<Error>::into_deserializer(self.string()?),
+		)
+	}
+
+	fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_unit()
+	}
+}
+
+impl<'a, 'de> IntoDeserializer<'de, Error> for Node<'a> {
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self::Deserializer {
+		self
+	}
+}
+
+impl<'a> Node<'a> {
+	fn value(self) -> Result<RegistryValue, Error> {
+		match self {
+			Self::Value(val) => Ok(val),
+			Self::Key(_, path) => Err(Error::Message(
+				format!("expected a value, found key \"{}\"", path))),
+		}
+	}
+
+	fn string(self) -> Result<String, Error> {
+		match self.value()? {
+			RegistryValue::Sz(s) | RegistryValue::ExpandSz(s) => Ok(s),
+			val => Err(invalid_value(&val, "a string")),
+		}
+	}
+
+	fn key(self) -> Result<(&'a dyn RegTree, String), Error> {
+		match self {
+			Self::Value(val) => Err(invalid_value(&val, "a key")),
+			Self::Key(tree, path) => Ok((tree, path)),
+		}
+	}
+}
+
+/// Iterates over the values and then the subkeys of a key, translating the
+/// names to the struct fields, if any.
+struct KeyMapAccess<'a> {
+	items: std::vec::IntoIter<(String, Node<'a>)>,
+	cur_node: Option<Node<'a>>,
+}
+
+impl<'a, 'de> de::MapAccess<'de> for KeyMapAccess<'a> {
+	type Error = Error;
+
+	fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self,
+		seed: K) -> Result<Option<K::Value>, Error>
+	{
+		match self.items.next() {
+			None => Ok(None),
+			Some((name, node)) => {
+				self.cur_node = Some(node);
+				seed.deserialize(IntoDeserializer::<Error>::into_deserializer(name))
+					.map(Some)
+			},
+		}
+	}
+
+	fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self,
+		seed: V) -> Result<V::Value, Error>
+	{
+		seed.deserialize(
+			self.cur_node.take()
+				.ok_or_else(|| Error::Message("value requested before key".to_owned()))?,
+		)
+	}
+}
+
+impl<'a> KeyMapAccess<'a> {
+	fn new(tree: &'a dyn RegTree,
+		path: &str, fields: &'static [&'static str]) -> Result<Self, Error>
+	{
+		let field_name = |name: String| fields.iter()
+			.find(|field| names_eq(field, &name))
+			.map_or(name, |field| field.to_string());
+
+		let mut items = Vec::default();
+		for (name, data) in tree.values(path)? {
+			if !name.is_empty() { // the default value has no name to be matched
+				items.push((field_name(name), Node::Value(data)));
+			}
+		}
+		for name in tree.keys(path)? {
+			let sub_path = join_path(path, &name);
+			items.push((field_name(name), Node::Key(tree, sub_path)));
+		}
+
+		Ok(Self { items: items.into_iter(), cur_node: None })
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// A serialized value, to be written to the tree.
+enum Field {
+	Absent,
+	Value(RegistryValue),
+	Key(Vec<(String, Field)>),
+}
+
+/// A serialized item of a list.
+enum Item {
+	Byte(u8),
+	Text(String),
+}
+
+fn write_key(tree: &mut dyn RegTree,
+	path: &str, entries: Vec<(String, Field)>) -> Result<(), Error>
+{
+	tree.add_key(path)?;
+
+	for (name, field) in entries.into_iter() {
+		match field {
+			Field::Absent => {
+				ignore_not_found(tree.remove_value(path, &name))?;
+				ignore_not_found(tree.remove_key(&join_path(path, &name)))?;
+			},
+			Field::Value(data) => tree.put_value(path, &name, data)?,
+			Field::Key(entries) => write_key(tree, &join_path(path, &name), entries)?,
+		}
+	}
+	Ok(())
+}
+
+fn ignore_not_found(res: WinResult<()>) -> WinResult<()> {
+	match res {
+		Err(co::ERROR::FILE_NOT_FOUND) => Ok(()),
+		res => res,
+	}
+}
+
+fn serialize_key<T: ser::Serialize + ?Sized>(key: &T) -> Result<String, Error> {
+	match key.serialize(ItemSerializer)? {
+		Item::Text(text) if !text.is_empty() => Ok(text),
+		Item::Byte(b) => Ok(b.to_string()),
+		_ => Err(Error::Message("keys must be non-empty strings".to_owned())),
+	}
+}
+
+macro_rules! serialize_unsupported {
+	($msg:expr; $( $method:ident($ty:ty) )*) => {
+		$(
+			fn $method(self, _: $ty) -> Result<Self::Ok, Error> {
+				Err(Error::Message($msg.to_owned()))
+			}
+		)*
+	};
+}
+
+macro_rules! serialize_with {
+	($( $method:ident($ty:ty) )* => |$v:ident| $conv:expr) => {
+		$(
+			fn $method(self, $v: $ty) -> Result<Self::Ok, Error> {
+				Ok($conv)
+			}
+		)*
+	};
+}
+
+const ENUM_ERR: &str = "only unit enum variants are supported";
+
+/// Serializes a value into a `Field`.
+struct FieldSerializer;
+
+impl ser::Serializer for FieldSerializer {
+	type Ok = Field;
+	type Error = Error;
+	type SerializeSeq = ListSerializer;
+	type SerializeTuple = ListSerializer;
+	type SerializeTupleStruct = ListSerializer;
+	type SerializeTupleVariant = ser::Impossible<Field, Error>;
+	type SerializeMap = KeySerializer;
+	type SerializeStruct = KeySerializer;
+	type SerializeStructVariant = ser::Impossible<Field, Error>;
+
+	serialize_with! {
+		serialize_i8(i8) serialize_i16(i16) serialize_i32(i32)
+		serialize_u8(u8) serialize_u16(u16) serialize_u32(u32)
+		=> |v| Field::Value(RegistryValue::Dword(v as _)) // signed as two's complement
+	}
+
+	serialize_with! {
+		serialize_i64(i64) serialize_u64(u64)
+		=> |v| Field::Value(RegistryValue::Qword(v as _))
+	}
+
+	serialize_with! {
+		serialize_f32(f32) serialize_f64(f64) serialize_char(char)
+		=> |v| Field::Value(RegistryValue::Sz(v.to_string()))
+	}
+
+	fn serialize_bool(self, v: bool) -> Result<Field, Error> {
+		Ok(Field::Value(RegistryValue::Dword(v as _)))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<Field, Error> {
+		Ok(Field::Value(RegistryValue::Sz(v.to_owned())))
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Field, Error> {
+		Ok(Field::Value(RegistryValue::Binary(v.to_vec())))
+	}
+
+	fn serialize_none(self) -> Result<Field, Error> {
+		Ok(Field::Absent)
+	}
+
+	fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Field, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Field, Error> {
+		Ok(Field::Value(RegistryValue::None))
+	}
+
+	fn serialize_unit_struct(self, _: &'static str) -> Result<Field, Error> {
+		self.serialize_unit()
+	}
+
+	fn serialize_unit_variant(self,
+		_: &'static str, _: u32, variant: &'static str) -> Result<Field, Error>
+	{
+		self.serialize_str(variant)
+	}
+
+	fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, value: &T) -> Result<Field, Error>
+	{
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, _: u32, _: &'static str, _: &T) -> Result<Field, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<ListSerializer, Error> {
+		Ok(ListSerializer { items: Vec::default() })
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<ListSerializer, Error> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self,
+		_: &'static str, len: usize) -> Result<ListSerializer, Error>
+	{
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeTupleVariant, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<KeySerializer, Error> {
+		Ok(KeySerializer { entries: Vec::default(), cur_key: None })
+	}
+
+	fn serialize_struct(self,
+		_: &'static str, len: usize) -> Result<KeySerializer, Error>
+	{
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeStructVariant, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+}
+
+const ITEM_ERR: &str = "list items and keys must be plain values";
+
+/// Serializes an item of a list, or the key of a map, into an `Item`.
+struct ItemSerializer;
+
+impl ser::Serializer for ItemSerializer {
+	type Ok = Item;
+	type Error = Error;
+	type SerializeSeq = ser::Impossible<Item, Error>;
+	type SerializeTuple = ser::Impossible<Item, Error>;
+	type SerializeTupleStruct = ser::Impossible<Item, Error>;
+	type SerializeTupleVariant = ser::Impossible<Item, Error>;
+	type SerializeMap = ser::Impossible<Item, Error>;
+	type SerializeStruct = ser::Impossible<Item, Error>;
+	type SerializeStructVariant = ser::Impossible<Item, Error>;
+
+	serialize_with! {
+		serialize_bool(bool) serialize_i8(i8) serialize_i16(i16)
+		serialize_i32(i32) serialize_i64(i64) serialize_u16(u16)
+		serialize_u32(u32) serialize_u64(u64) serialize_f32(f32)
+		serialize_f64(f64) serialize_char(char) serialize_str(&str)
+		=> |v| Item::Text(v.to_string())
+	}
+
+	serialize_unsupported! { ITEM_ERR;
+		serialize_bytes(&[u8]) serialize_unit_struct(&'static str)
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<Item, Error> {
+		Ok(Item::Byte(v))
+	}
+
+	fn serialize_none(self) -> Result<Item, Error> {
+		Err(Error::Message(ITEM_ERR.to_owned()))
+	}
+
+	fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Item, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Item, Error> {
+		Err(Error::Message(ITEM_ERR.to_owned()))
+	}
+
+	fn serialize_unit_variant(self,
+		_: &'static str, _: u32, variant: &'static str) -> Result<Item, Error>
+	{
+		Ok(Item::Text(variant.to_owned()))
+	}
+
+	fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, value: &T) -> Result<Item, Error>
+	{
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, _: u32, _: &'static str, _: &T) -> Result<Item, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+		Err(Error::Message("nested lists are not supported".to_owned()))
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self,
+		_: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Error>
+	{
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeTupleVariant, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+		Err(Error::Message(ITEM_ERR.to_owned()))
+	}
+
+	fn serialize_struct(self,
+		_: &'static str, _: usize) -> Result<Self::SerializeStruct, Error>
+	{
+		Err(Error::Message(ITEM_ERR.to_owned()))
+	}
+
+	fn serialize_struct_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeStructVariant, Error>
+	{
+		Err(Error::Message(ENUM_ERR.to_owned()))
+	}
+}
+
+/// Collects the items of a list, which becomes binary data if all items are
+/// bytes, or a sequence of strings otherwise.
+struct ListSerializer {
+	items: Vec<Item>,
+}
+
+impl ser::SerializeSeq for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.items.push(value.serialize(ItemSerializer)?);
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		if !self.items.is_empty() && self.items.iter().all(|item| matches!(item, Item::Byte(_))) {
+			return Ok(Field::Value(RegistryValue::Binary(
+				self.items.into_iter()
+					.map(|item| match item {
+						Item::Byte(b) => b,
+						Item::Text(_) => unreachable!(),
+					})
+					.collect(),
+			)));
+		}
+
+		let mut strs = Vec::with_capacity(self.items.len());
+		for item in self.items.into_iter() {
+			strs.push(match item {
+				Item::Byte(b) => b.to_string(),
+				Item::Text(text) if text.is_empty() => return Err(Error::Message(
+					"list items cannot be empty strings".to_owned())), // would end the REG_MULTI_SZ
+				Item::Text(text) => text,
+			});
+		}
+		Ok(Field::Value(RegistryValue::MultiSz(strs)))
+	}
+}
+
+impl ser::SerializeTuple for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+/// Collects the values and subkeys of a key.
+struct KeySerializer {
+	entries: Vec<(String, Field)>,
+	cur_key: Option<String>,
+}
+
+impl ser::SerializeStruct for KeySerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_field<T: ser::Serialize + ?Sized>(&mut self,
+		key: &'static str, value: &T) -> Result<(), Error>
+	{
+		let field = value.serialize(FieldSerializer)?;
+		self.entries.push((key.to_owned(), field));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		Ok(Field::Key(self.entries))
+	}
+}
+
+impl ser::SerializeMap for KeySerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+		self.cur_key = Some(serialize_key(key)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let key = self.cur_key.take()
+			.ok_or_else(|| Error::Message("value serialized before key".to_owned()))?;
+		let field = value.serialize(FieldSerializer)?;
+		self.entries.push((key, field));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		Ok(Field::Key(self.entries))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use serde::{Deserialize, Serialize};
+	use super::*;
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	struct Ints {
+		tiny: i8,
+		short: i16,
+		long: i32,
+		huge: u64,
+		wide: i64,
+	}
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	struct Profile {
+		name: Option<String>,
+		servers: Vec<String>,
+		proxy: Option<Proxy>,
+	}
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	struct Proxy {
+		host: String,
+		port: u16,
+	}
+
+	fn profile() -> Profile {
+		Profile {
+			name: Some("work".to_owned()),
+			servers: vec!["a.example".to_owned(), "C:\\b c".to_owned()],
+			proxy: Some(Proxy { host: "10.0.0.1".to_owned(), port: 8080 }),
+		}
+	}
+
+	/// Reads a single value into the given type.
+	fn read_as<T: de::DeserializeOwned>(data: RegistryValue) -> Result<T, Error> {
+		let mut tree = MemRegTree::default();
+		tree.put_value("", "v", data).unwrap();
+		let map: BTreeMap<String, T> = from_key(&tree, "")?;
+		Ok(map.into_values().next().unwrap())
+	}
+
+	#[test]
+	fn twos_complement_bounds() {
+		let mins = Ints { tiny: i8::MIN, short: i16::MIN, long: i32::MIN, huge: 0, wide: i64::MIN };
+		let maxs = Ints { tiny: i8::MAX, short: i16::MAX, long: i32::MAX, huge: u64::MAX, wide: i64::MAX };
+		let mut tree = MemRegTree::default();
+		to_key(&mut tree, "Min", &mins).unwrap();
+		to_key(&mut tree, "Max", &maxs).unwrap();
+
+		let key = tree.key("Min").unwrap();
+		assert_eq!(key.value("tiny"), Some(&RegistryValue::Dword(0xffff_ff80)));
+		assert_eq!(key.value("short"), Some(&RegistryValue::Dword(0xffff_8000)));
+		assert_eq!(key.value("long"), Some(&RegistryValue::Dword(0x8000_0000)));
+		assert_eq!(key.value("huge"), Some(&RegistryValue::Qword(0)));
+		assert_eq!(key.value("wide"), Some(&RegistryValue::Qword(0x8000_0000_0000_0000)));
+
+		let key = tree.key("Max").unwrap();
+		assert_eq!(key.value("tiny"), Some(&RegistryValue::Dword(0x7f)));
+		assert_eq!(key.value("short"), Some(&RegistryValue::Dword(0x7fff)));
+		assert_eq!(key.value("long"), Some(&RegistryValue::Dword(0x7fff_ffff)));
+		assert_eq!(key.value("huge"), Some(&RegistryValue::Qword(u64::MAX)));
+		assert_eq!(key.value("wide"), Some(&RegistryValue::Qword(0x7fff_ffff_ffff_ffff)));
+
+		assert_eq!(from_key::<Ints, _>(&tree, "Min").unwrap(), mins);
+		assert_eq!(from_key::<Ints, _>(&tree, "Max").unwrap(), maxs);
+	}
+
+	#[test]
+	fn out_of_range() {
+		assert!(read_as::<i8>(RegistryValue::Dword(0x80)).is_err());
+		assert!(read_as::<i8>(RegistryValue::Dword(0xffff_ff7f)).is_err());
+		assert!(read_as::<i16>(RegistryValue::Dword(0x8000)).is_err());
+		assert!(read_as::<i16>(RegistryValue::Dword(0xffff_7fff)).is_err());
+		assert!(read_as::<u8>(RegistryValue::Dword(0xffff_ffff)).is_err());
+		assert!(read_as::<u32>(RegistryValue::Qword(1 << 32)).is_err());
+
+		// a DWORD is zero-extended into 64-bit fields
+		assert_eq!(read_as::<u64>(RegistryValue::Dword(0xffff_ffff)).unwrap(), 0xffff_ffff);
+		assert_eq!(read_as::<i64>(RegistryValue::Dword(0xffff_ffff)).unwrap(), 0xffff_ffff);
+		assert_eq!(read_as::<i64>(RegistryValue::Qword(u64::MAX)).unwrap(), -1);
+	}
+
+	#[test]
+	fn big_endian_dword() {
+		assert_eq!(read_as::<u32>(RegistryValue::DwordBigEndian(0x1234_5678)).unwrap(), 0x1234_5678);
+		assert_eq!(read_as::<i16>(RegistryValue::DwordBigEndian(0xffff_8000)).unwrap(), i16::MIN);
+		assert!(read_as::<u8>(RegistryValue::DwordBigEndian(0x100)).is_err());
+
+		let mut tree = MemRegTree::default();
+		tree.add_key("Ints").unwrap();
+		for (name, n) in [("tiny", 0xffff_ffffu32), ("short", 2), ("long", 3)] {
+			tree.put_value("Ints", name, RegistryValue::DwordBigEndian(n)).unwrap();
+		}
+		tree.put_value("Ints", "huge", RegistryValue::DwordBigEndian(4)).unwrap();
+		tree.put_value("Ints", "wide", RegistryValue::Qword(5)).unwrap();
+
+		let ints: Ints = from_key(&tree, "Ints").unwrap();
+		assert_eq!(ints, Ints { tiny: -1, short: 2, long: 3, huge: 4, wide: 5 });
+
+		to_key(&mut tree, "Ints", &ints).unwrap(); // written back in native order
+		let key = tree.key("Ints").unwrap();
+		assert_eq!(key.value("tiny"), Some(&RegistryValue::Dword(0xffff_ffff)));
+		assert_eq!(key.value("huge"), Some(&RegistryValue::Qword(4)));
+	}
+
+	#[test]
+	fn none_deletes_subkey() {
+		let mut tree = MemRegTree::default();
+		to_key(&mut tree, "Profile", &profile()).unwrap();
+		assert_eq!(tree.key("Profile").unwrap().value("servers"),
+			Some(&RegistryValue::MultiSz(vec!["a.example".to_owned(), "C:\\b c".to_owned()])));
+
+		tree.add_key("Profile\\Proxy\\Credentials").unwrap();
+		tree.put_value("Profile\\Proxy", "Bypass", RegistryValue::new_sz("local")).unwrap();
+		tree.put_value("Profile", "Theme", RegistryValue::Dword(1)).unwrap();
+		assert_eq!(from_key::<Profile, _>(&tree, "Profile").unwrap(), profile());
+
+		let empty = Profile { name: None, servers: Vec::new(), proxy: None };
+		to_key(&mut tree, "Profile", &empty).unwrap();
+		let key = tree.key("Profile").unwrap();
+		assert_eq!(key.value("name"), None);
+		assert!(key.key("proxy").is_none()); // along with the entries which aren't fields
+		assert_eq!(key.value("theme"), Some(&RegistryValue::Dword(1)));
+		assert_eq!(from_key::<Profile, _>(&tree, "Profile").unwrap(), empty);
+
+		to_key(&mut tree, "Profile", &empty).unwrap(); // nothing left to delete
+	}
+
+	#[test]
+	fn names_and_conversions() {
+		let mut tree = MemRegTree::default();
+		tree.add_key("Profile\\PROXY").unwrap();
+		tree.put_value("Profile", "Servers", RegistryValue::MultiSz(Vec::new())).unwrap();
+		tree.put_value("Profile\\PROXY", "HOST",
+			RegistryValue::ExpandSz("%COMPUTERNAME%".to_owned())).unwrap();
+		tree.put_value("Profile\\PROXY", "Port", RegistryValue::new_sz(" 8080 ")).unwrap();
+
+		let read: Profile = from_key(&tree, "profile").unwrap();
+		assert_eq!(read.name, None);
+		assert_eq!(read.proxy, Some(Proxy { host: "%COMPUTERNAME%".to_owned(), port: 8080 }));
+	}
+
+	#[test]
+	fn type_mismatch() {
+		let mut tree = MemRegTree::default();
+		to_key(&mut tree, "Profile", &profile()).unwrap();
+
+		let check = |path: &str, name: &str, data: RegistryValue, expected: &str| {
+			let mut tree = tree.clone();
+			tree.put_value(path, name, data).unwrap();
+			match from_key::<Profile, _>(&tree, "Profile") {
+				Err(Error::Message(msg)) => assert!(msg.contains(expected), "{}", msg),
+				res => panic!("{}: {:?}", name, res),
+			}
+		};
+		check("Profile\\Proxy", "port", RegistryValue::new_sz("many"), "u16");
+		check("Profile\\Proxy", "port", RegistryValue::Dword(0x1_0000), "u16");
+		check("Profile\\Proxy", "host", RegistryValue::Dword(1), "a string");
+		check("Profile", "servers", RegistryValue::new_sz("x"), "a list");
+		check("Profile", "proxy", RegistryValue::Dword(1), "a key"); // values come before subkeys
+
+		assert_eq!(from_key::<Profile, _>(&tree, "None").unwrap_err(),
+			Error::Registry(co::ERROR::FILE_NOT_FOUND));
+	}
+
+	#[test]
+	fn serialize_errors() {
+		let mut tree = MemRegTree::default();
+		assert!(to_key(&mut tree, "Profile", &5u32).is_err()); // not a struct
+		assert!(to_key(&mut tree, "Profile", &BTreeMap::from([("x", vec!["a", ""])])).is_err());
+		assert!(to_key(&mut tree, "Profile", &BTreeMap::from([("", 1)])).is_err());
+	}
+}
@@ -164,6 +164,19 @@ const_ordinary! { DISPOSITION: u32: "kernel";
 	TRUNCATE_EXISTING 5
 }
 
+const_bitflag! { DUPLICATE: u32: "kernel";
+	/// [`HPROCESS::DuplicateHandle`](crate::prelude::KernelHprocess::DuplicateHandle)
+	/// `options` (`u32`).
+	=>
+	=>
+	NoValue 0
+	/// Closes the source handle, even if an error occurs.
+	CLOSE_SOURCE 0x0000_0001
+	/// Ignores the `desired_access` argument, the duplicate handle has the
+	/// same access as the source handle.
+	SAME_ACCESS 0x0000_0002
+}
+
 const_bitflag! { FILE_ATTRIBUTE: u32: "kernel";
 	/// File attribute
 	/// [flags](https://docs.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants),
@@ -231,6 +244,39 @@ const_bitflag! { FILE_MAP: u32: "kernel";
 	TARGETS_INVALID 0x4000_0000
 }
 
+const_bitflag! { FILE_RIGHT: u32: "kernel";
+	/// File access rights
+	/// [flags](https://docs.microsoft.com/en-us/windows/win32/fileio/file-access-rights-constants)
+	/// (`u32`).
+	=>
+	=>
+	/// Read the file data.
+	READ_DATA 0x0001
+	/// Write anywhere in the file.
+	WRITE_DATA 0x0002
+	/// Append data to the end of the file.
+	APPEND_DATA 0x0004
+	/// Read the extended attributes.
+	READ_EA 0x0008
+	/// Write the extended attributes.
+	WRITE_EA 0x0010
+	/// Execute the file.
+	EXECUTE 0x0020
+	/// Read the file attributes.
+	READ_ATTRIBUTES 0x0080
+	/// Write the file attributes.
+	WRITE_ATTRIBUTES 0x0100
+	/// All possible access rights for a file.
+	ALL_ACCESS STANDARD_RIGHTS::REQUIRED.0 | ACCESS_RIGHTS::SYNCHRONIZE.0 | 0x1ff
+	/// Standard rights to read a file.
+	GENERIC_READ STANDARD_RIGHTS::READ.0 | Self::READ_DATA.0 | Self::READ_ATTRIBUTES.0 | Self::READ_EA.0 | ACCESS_RIGHTS::SYNCHRONIZE.0
+	/// Standard rights to write a file, including
+	/// [`APPEND_DATA`](crate::co::FILE_RIGHT::APPEND_DATA).
+	GENERIC_WRITE STANDARD_RIGHTS::WRITE.0 | Self::WRITE_DATA.0 | Self::WRITE_ATTRIBUTES.0 | Self::WRITE_EA.0 | Self::APPEND_DATA.0 | ACCESS_RIGHTS::SYNCHRONIZE.0
+	/// Standard rights to execute a file.
+	GENERIC_EXECUTE STANDARD_RIGHTS::EXECUTE.0 | Self::READ_ATTRIBUTES.0 | Self::EXECUTE.0 | ACCESS_RIGHTS::SYNCHRONIZE.0
+}
+
 const_bitflag! { FILE_SHARE: u32: "kernel";
 	/// [`HFILE::CreateFile`](crate::prelude::KernelHfile::CreateFile)
 	/// `share_mode` (`u32`).
@@ -279,6 +325,50 @@ const_ordinary! { FILE_TYPE: u32: "kernel";
 	UNKNOWN 0x0000
 }
 
+const_ordinary! { FINDEX_INFO_LEVELS: u32: "kernel";
+	/// [`FINDEX_INFO_LEVELS`](https://docs.microsoft.com/en-us/windows/win32/api/minwinbase/ne-minwinbase-findex_info_levels)
+	/// enumeration (`u32`).
+	=>
+	=>
+	/// The search returns the full
+	/// [`WIN32_FIND_DATA`](crate::WIN32_FIND_DATA), including the 8.3 short
+	/// name.
+	Standard 0
+	/// The search doesn't query the short name, leaving
+	/// `cAlternateFileName` empty, which is faster.
+	Basic 1
+}
+
+const_ordinary! { FINDEX_SEARCH_OPS: u32: "kernel";
+	/// [`FINDEX_SEARCH_OPS`](https://docs.microsoft.com/en-us/windows/win32/api/minwinbase/ne-minwinbase-findex_search_ops)
+	/// enumeration (`u32`).
+	=>
+	=>
+	/// The search for a file that matches a specified file name.
+	NameMatch 0
+	/// A hint to return only directories; file systems which don't support it
+	/// return files too.
+	LimitToDirectories 1
+	/// This filtering type is not available.
+	LimitToDevices 2
+}
+
+const_bitflag! { FIND_FIRST_EX: u32: "kernel";
+	/// [`HFINDFILE::FindFirstFileEx`](crate::prelude::KernelHfindfile::FindFirstFileEx)
+	/// `additional_flags` (`u32`).
+	=>
+	=>
+	NoValue 0
+	/// Searches are case-sensitive.
+	CASE_SENSITIVE 1
+	/// Uses a larger buffer for directory queries, which can increase
+	/// performance of the find operation.
+	LARGE_FETCH 2
+	/// Limits the results to files that are physically on disk. This flag is
+	/// only relevant when a file virtualization filter is present.
+	ON_DISK_ENTRIES_ONLY 4
+}
+
 const_ordinary! { FIRMWARE_TYPE: u32: "kernel";
 	/// [`FIRMWARE_TYPE`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ne-winnt-firmware_type)
 	/// enumeration (`u32`).
@@ -343,6 +433,82 @@ const_bitflag! { GR: u32: "kernel";
 	USEROBJECTS_PEAK 4
 }
 
+const_bitflag! { JOB_OBJECT_CPU_RATE_CONTROL: u32: "kernel";
+	/// [`JOBOBJECT_CPU_RATE_CONTROL_INFORMATION`](crate::JOBOBJECT_CPU_RATE_CONTROL_INFORMATION)
+	/// `ControlFlags` (`u32`).
+	=>
+	=>
+	NoValue 0
+	ENABLE 0x1
+	WEIGHT_BASED 0x2
+	HARD_CAP 0x4
+	NOTIFY 0x8
+	MIN_MAX_RATE 0x10
+}
+
+const_bitflag! { JOB_OBJECT_LIMIT: u32: "kernel";
+	/// [`JOBOBJECT_BASIC_LIMIT_INFORMATION`](crate::JOBOBJECT_BASIC_LIMIT_INFORMATION)
+	/// `LimitFlags` (`u32`).
+	=>
+	=>
+	NoValue 0
+	WORKINGSET 0x0000_0001
+	PROCESS_TIME 0x0000_0002
+	JOB_TIME 0x0000_0004
+	ACTIVE_PROCESS 0x0000_0008
+	AFFINITY 0x0000_0010
+	PRIORITY_CLASS 0x0000_0020
+	PRESERVE_JOB_TIME 0x0000_0040
+	SCHEDULING_CLASS 0x0000_0080
+	PROCESS_MEMORY 0x0000_0100
+	JOB_MEMORY 0x0000_0200
+	DIE_ON_UNHANDLED_EXCEPTION 0x0000_0400
+	BREAKAWAY_OK 0x0000_0800
+	SILENT_BREAKAWAY_OK 0x0000_1000
+	KILL_ON_JOB_CLOSE 0x0000_2000
+	SUBSET_AFFINITY 0x0000_4000
+}
+
+const_bitflag! { JOB_OBJECT_UILIMIT: u32: "kernel";
+	/// [`JOBOBJECT_BASIC_UI_RESTRICTIONS`](crate::JOBOBJECT_BASIC_UI_RESTRICTIONS)
+	/// `UIRestrictionsClass` (`u32`).
+	=>
+	=>
+	NONE 0x0000_0000
+	HANDLES 0x0000_0001
+	READCLIPBOARD 0x0000_0002
+	WRITECLIPBOARD 0x0000_0004
+	SYSTEMPARAMETERS 0x0000_0008
+	DISPLAYSETTINGS 0x0000_0010
+	GLOBALATOMS 0x0000_0020
+	DESKTOP 0x0000_0040
+	EXITWINDOWS 0x0000_0080
+}
+
+const_ordinary! { JOBOBJECTINFOCLASS: u32: "kernel";
+	/// [`HJOB::QueryInformationJobObject`](crate::prelude::KernelHjob::QueryInformationJobObject)
+	/// and
+	/// [`HJOB::SetInformationJobObject`](crate::prelude::KernelHjob::SetInformationJobObject)
+	/// `info_class` (`u32`).
+	///
+	/// Originally has `JobObject` prefix.
+	=>
+	=>
+	BasicAccountingInformation 1
+	BasicLimitInformation 2
+	BasicProcessIdList 3
+	BasicUIRestrictions 4
+	EndOfJobTimeInformation 6
+	AssociateCompletionPortInformation 7
+	BasicAndIoAccountingInformation 8
+	ExtendedLimitInformation 9
+	GroupInformation 11
+	NotificationLimitInformation 12
+	LimitViolationInformation 13
+	GroupInformationEx 14
+	CpuRateControlInformation 15
+}
+
 const_ordinary! { LANG: u16: "kernel";
 	/// Language
 	/// [identifier](https://docs.microsoft.com/en-us/windows/win32/intl/language-identifier-constants-and-strings)
@@ -561,6 +727,51 @@ const_ordinary! { PAGE: u32: "kernel";
 	SEC_WRITECOMBINE 0x4000_0000
 }
 
+const_bitflag! { PIPE: u32: "kernel";
+	/// [`HPIPE::CreateNamedPipe`](crate::prelude::KernelHpipe::CreateNamedPipe)
+	/// `pipe_mode` (`u32`).
+	=>
+	=>
+	/// Data is written to the pipe as a stream of bytes.
+	TYPE_BYTE 0x0000_0000
+	/// Data is written to the pipe as a stream of messages.
+	TYPE_MESSAGE 0x0000_0004
+	/// Data is read from the pipe as a stream of bytes.
+	READMODE_BYTE 0x0000_0000
+	/// Data is read from the pipe as a stream of messages. Requires
+	/// `TYPE_MESSAGE`.
+	READMODE_MESSAGE 0x0000_0002
+	/// Blocking mode is enabled.
+	WAIT 0x0000_0000
+	/// Nonblocking mode is enabled, only for compatibility with LAN Manager.
+	NOWAIT 0x0000_0001
+	/// Connections from remote clients can be accepted.
+	ACCEPT_REMOTE_CLIENTS 0x0000_0000
+	/// Connections from remote clients are automatically rejected.
+	REJECT_REMOTE_CLIENTS 0x0000_0008
+}
+
+const_bitflag! { PIPE_ACCESS: u32: "kernel";
+	/// [`HPIPE::CreateNamedPipe`](crate::prelude::KernelHpipe::CreateNamedPipe)
+	/// `open_mode` (`u32`).
+	=>
+	=>
+	/// The flow of data in the pipe goes from client to server only.
+	INBOUND 0x0000_0001
+	/// The flow of data in the pipe goes from server to client only.
+	OUTBOUND 0x0000_0002
+	/// The pipe is bi-directional.
+	DUPLEX 0x0000_0003
+
+	/// Fails if the pipe already exists, so another process can't create
+	/// the pipe first.
+	FIRST_PIPE_INSTANCE 0x0008_0000
+	/// Write-through mode is enabled, for byte-type pipes.
+	WRITE_THROUGH 0x8000_0000
+	/// Overlapped mode is enabled.
+	OVERLAPPED 0x4000_0000
+}
+
 const_bitflag! { PROCESS: u32: "kernel";
 	/// Process
 	/// [security and access rights](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights)
@@ -774,6 +985,15 @@ const_bitflag! { STARTF: u32: "kernel";
 	USESTDHANDLES 0x0000_0100
 }
 
+const_ordinary! { STD_HANDLE: u32: "kernel";
+	/// [`GetStdHandle`](crate::GetStdHandle) `std_handle` (`u32`).
+	=>
+	=>
+	INPUT 0xffff_fff6
+	OUTPUT 0xffff_fff5
+	ERROR 0xffff_fff4
+}
+
 const_ordinary! { SUBLANG: u16: "kernel";
 	/// Sublanguage
 	/// [identifier](https://docs.microsoft.com/en-us/windows/win32/intl/language-identifier-constants-and-strings)
@@ -1168,6 +1388,8 @@ const_ordinary! { VER_PLATFORM: u32: "kernel";
 
 const_ordinary! { WAIT: u32: "kernel";
 	/// [`HPROCESS::WaitForSingleObject`](crate::prelude::KernelHprocess::WaitForSingleObject)
+	/// and
+	/// [`HEVENT::WaitForSingleObject`](crate::prelude::KernelHevent::WaitForSingleObject)
 	/// return value (`u32`).
 	=>
 	=>
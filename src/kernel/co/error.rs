@@ -10,7 +10,8 @@ const_no_debug_display! { ERROR: u32: "kernel";
 	///
 	/// Implements the standard
 	/// [`Error`](https://doc.rust-lang.org/beta/std/error/trait.Error.html)
-	/// trait.
+	/// trait, and can be converted into a
+	/// [`std::io::Error`](https://doc.rust-lang.org/std/io/struct.Error.html).
 	///
 	/// Implements the
 	/// [`Debug`](https://doc.rust-lang.org/std/fmt/trait.Debug.html) and
@@ -39,6 +40,12 @@ impl std::error::Error for ERROR {
 	}
 }
 
+impl From<ERROR> for std::io::Error {
+	fn from(err: ERROR) -> Self {
+		std::io::Error::from_raw_os_error(err.0 as _)
+	}
+}
+
 impl std::fmt::Debug for ERROR {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		if self.0 > 0xffff {
@@ -249,6 +256,7 @@ const_values! { ERROR;
 	VC_DISCONNECTED 240
 	INVALID_EA_NAME 254
 	EA_LIST_INCONSISTENT 255
+	WAIT_TIMEOUT 258
 	NO_MORE_ITEMS 259
 	CANNOT_COPY 266
 	DIRECTORY 267
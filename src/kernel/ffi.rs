@@ -1,15 +1,24 @@
 use crate::ffi_types::{BOOL, HANDLE, PCSTR, PCVOID, PFUNC, PSTR, PVOID};
 
 extern_sys! { "kernel32";
+	AssignProcessToJobObject(HANDLE, HANDLE) -> BOOL
 	BeginUpdateResourceW(PCSTR, BOOL) -> HANDLE
+	CancelIoEx(HANDLE, PVOID) -> BOOL
 	CloseHandle(HANDLE) -> BOOL
+	ConnectNamedPipe(HANDLE, PVOID) -> BOOL
 	CopyFileW(PCSTR, PCSTR, BOOL) -> BOOL
+	CreateEventW(PVOID, BOOL, BOOL, PCSTR) -> HANDLE
 	CreateFileMappingW(HANDLE, PVOID, u32, u32, u32, PCSTR) -> HANDLE
 	CreateFileW(PCSTR, u32, u32, PVOID, u32, u32, HANDLE) -> HANDLE
+	CreateIoCompletionPort(HANDLE, HANDLE, usize, u32) -> HANDLE
+	CreateJobObjectW(PVOID, PCSTR) -> HANDLE
+	CreateNamedPipeW(PCSTR, u32, u32, u32, u32, u32, u32, PVOID) -> HANDLE
 	CreatePipe(*mut HANDLE, *mut HANDLE, PVOID, u32) -> BOOL
 	CreateProcessW(PCSTR, PSTR, PVOID, PVOID, BOOL, u32, PVOID, PCSTR, PVOID, PVOID) -> BOOL
 	CreateThread(PVOID, u64, PVOID, PVOID, u32, *mut u32) -> HANDLE
 	DeleteFileW(PCSTR) -> BOOL
+	DisconnectNamedPipe(HANDLE) -> BOOL
+	DuplicateHandle(HANDLE, HANDLE, HANDLE, *mut HANDLE, u32, BOOL, u32) -> BOOL
 	EndUpdateResourceW(HANDLE, BOOL) -> BOOL
 	EnumResourceLanguagesW(HANDLE, PCSTR, PCSTR, PFUNC, isize) -> BOOL
 	EnumResourceNamesW(HANDLE, PCSTR, PFUNC, isize) -> BOOL
@@ -19,10 +28,12 @@ extern_sys! { "kernel32";
 	ExpandEnvironmentStringsW(PCSTR, PSTR, u32) -> u32
 	FileTimeToSystemTime(PCVOID, PVOID) -> BOOL
 	FindClose(HANDLE) -> BOOL
+	FindFirstFileExW(PCSTR, u32, PVOID, u32, PVOID, u32) -> HANDLE
 	FindFirstFileW(PCSTR, PVOID) -> HANDLE
 	FindNextFileW(HANDLE, PVOID) -> BOOL
 	FindResourceExW(HANDLE, PCSTR, PCSTR, u16) -> HANDLE
 	FindResourceW(HANDLE, PCSTR, PCSTR) -> HANDLE
+	FlushFileBuffers(HANDLE) -> BOOL
 	FlushInstructionCache(HANDLE, PCVOID, u64) -> BOOL
 	FlushProcessWriteBuffers()
 	FormatMessageW(u32, PCVOID, u32, u32, PSTR, u32, PVOID) -> u32
@@ -47,6 +58,7 @@ extern_sys! { "kernel32";
 	GetFileSizeEx(HANDLE, *mut i64) -> BOOL
 	GetFileType(HANDLE) -> u32
 	GetFirmwareType(*mut u32) -> BOOL
+	GetFullPathNameW(PCSTR, u32, PSTR, *mut PSTR) -> u32
 	GetGuiResources(HANDLE, u32) -> u32
 	GetLargePageMinimum() -> u64
 	GetLastError() -> u32
@@ -54,11 +66,15 @@ extern_sys! { "kernel32";
 	GetModuleFileNameW(HANDLE, PSTR, u32) -> u32
 	GetModuleHandleW(PCSTR) -> HANDLE
 	GetNativeSystemInfo(PVOID)
+	GetOverlappedResult(HANDLE, PVOID, *mut u32, BOOL) -> BOOL
 	GetProcAddress(HANDLE, *const u8) -> PCVOID
 	GetProcessId(HANDLE) -> u32
 	GetProcessIdOfThread(HANDLE) -> u32
 	GetProcessTimes(HANDLE, PVOID, PVOID, PVOID, PVOID) -> BOOL
+	GetQueuedCompletionStatus(HANDLE, *mut u32, *mut usize, *mut PVOID, u32) -> BOOL
+	GetQueuedCompletionStatusEx(HANDLE, PVOID, u32, *mut u32, u32, BOOL) -> BOOL
 	GetStartupInfoW(PVOID)
+	GetStdHandle(u32) -> HANDLE
 	GetSystemDirectoryW(PSTR, u32) -> u32
 	GetSystemInfo(PVOID)
 	GetSystemTime(PVOID)
@@ -78,6 +94,7 @@ extern_sys! { "kernel32";
 	GlobalSize(HANDLE) -> u64
 	GlobalUnlock(HANDLE) -> BOOL
 	IsNativeVhdBoot(*mut BOOL) -> BOOL
+	IsProcessInJob(HANDLE, HANDLE, *mut BOOL) -> BOOL
 	IsWow64Process(HANDLE, *mut BOOL) -> BOOL
 	LoadLibraryW(PCSTR) -> HANDLE
 	LoadResource(HANDLE, HANDLE) -> HANDLE
@@ -92,25 +109,38 @@ extern_sys! { "kernel32";
 	MultiByteToWideChar(u32, u32, *const u8, i32, PSTR, i32) -> i32
 	OpenProcess(u32, BOOL, u32) -> HANDLE
 	OutputDebugStringW(PCSTR)
+	PeekNamedPipe(HANDLE, PVOID, u32, *mut u32, *mut u32, *mut u32) -> BOOL
+	PostQueuedCompletionStatus(HANDLE, u32, usize, PVOID) -> BOOL
 	QueryFullProcessImageNameW(HANDLE, u32, PSTR, *mut u32) -> BOOL
+	QueryInformationJobObject(HANDLE, u32, PVOID, u32, *mut u32) -> BOOL
 	QueryPerformanceCounter(*mut i64) -> BOOL
 	QueryPerformanceFrequency(*mut i64) -> BOOL
 	ReadFile(HANDLE, PVOID, u32, *mut u32, PVOID) -> BOOL
 	ReplaceFileW(PCSTR, PCSTR, PCSTR, u32, PVOID, PVOID) -> BOOL
+	ResetEvent(HANDLE) -> BOOL
+	ResumeThread(HANDLE) -> u32
 	SetCurrentDirectoryW(PCSTR) -> BOOL
 	SetEndOfFile(HANDLE) -> BOOL
+	SetEvent(HANDLE) -> BOOL
 	SetFilePointerEx(HANDLE, i64, *mut i64, u32) -> BOOL
+	SetInformationJobObject(HANDLE, u32, PVOID, u32) -> BOOL
 	SetLastError(u32)
+	SetNamedPipeHandleState(HANDLE, *mut u32, *mut u32, *mut u32) -> BOOL
 	SizeofResource(HANDLE, HANDLE) -> u32
 	Sleep(u32)
+	SuspendThread(HANDLE) -> u32
 	SystemTimeToFileTime(PCVOID, PVOID) -> BOOL
 	SystemTimeToTzSpecificLocalTime(PCVOID, PCVOID, PVOID) -> BOOL
+	TerminateJobObject(HANDLE, u32) -> BOOL
+	TerminateProcess(HANDLE, u32) -> BOOL
+	TransactNamedPipe(HANDLE, PCVOID, u32, PVOID, u32, *mut u32, PVOID) -> BOOL
 	UnlockFile(HANDLE, u32, u32, u32, u32) -> BOOL
 	UnmapViewOfFile(PCVOID) -> BOOL
 	UpdateResourceW(HANDLE, PCSTR, PCSTR, u16, PVOID, u32) -> BOOL
 	VerifyVersionInfoW(PVOID, u32, u64) -> BOOL
 	VerSetConditionMask(u64, u32, u8) -> u64
 	WaitForSingleObject(HANDLE, u32) -> u32
+	WaitNamedPipeW(PCSTR, u32) -> BOOL
 	WideCharToMultiByte(u32, u32, PCSTR, i32, PSTR, i32, *const u8, *mut BOOL) -> i32
 	WriteFile(HANDLE, PCVOID, u32, *mut u32, PVOID) -> BOOL
 }
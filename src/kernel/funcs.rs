@@ -4,9 +4,10 @@ use std::collections::HashMap;
 
 use crate::{co, kernel};
 use crate::ffi_types::BOOL;
-use crate::kernel::decl::{MEMORYSTATUSEX, OSVERSIONINFOEX, STARTUPINFO,
-	SYSTEM_INFO, TIME_ZONE_INFORMATION, WinResult, WString};
+use crate::kernel::decl::{HFILE, MEMORYSTATUSEX, OSVERSIONINFOEX, path, STARTUPINFO,
+	SYSTEM_INFO, TIME_ZONE_INFORMATION, ToWStr, WinResult, WString};
 use crate::kernel::privs::{bool_to_winresult, INVALID_FILE_ATTRIBUTES,
+	INVALID_HANDLE_VALUE,
 	MAX_COMPUTERNAME_LENGTH, MAX_PATH, parse_multi_z_str};
 use crate::kernel::structs::{FILETIME, SYSTEMTIME};
 
@@ -20,8 +21,8 @@ pub fn CopyFile(
 	bool_to_winresult(
 		unsafe {
 			kernel::ffi::CopyFileW(
-				WString::from_str(existing_file).as_ptr(),
-				WString::from_str(new_file).as_ptr(),
+				WString::from_str(&path::to_win32_path(existing_file)).as_ptr(),
+				WString::from_str(&path::to_win32_path(new_file)).as_ptr(),
 				fail_if_exists as _,
 			)
 		},
@@ -34,7 +35,9 @@ pub fn CopyFile(
 pub fn DeleteFile(file_name: &str) -> WinResult<()> {
 	bool_to_winresult(
 		unsafe {
-			kernel::ffi::DeleteFileW(WString::from_str(file_name).as_ptr())
+			kernel::ffi::DeleteFileW(
+				WString::from_str(&path::to_win32_path(file_name)).as_ptr(),
+			)
 		},
 	)
 }
@@ -56,8 +59,8 @@ pub fn DeleteFile(file_name: &str) -> WinResult<()> {
 /// # Ok::<_, winsafe::co::ERROR>(())
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
-pub fn ExpandEnvironmentStrings(src: &str) -> WinResult<String> {
-	let wsrc = WString::from_str(src);
+pub fn ExpandEnvironmentStrings(src: &(impl ToWStr + ?Sized)) -> WinResult<String> {
+	let wsrc = src.to_wstr();
 	let len = unsafe {
 		kernel::ffi::ExpandEnvironmentStringsW(
 			wsrc.as_ptr(),
@@ -192,8 +195,12 @@ pub fn GetEnvironmentStrings() -> WinResult<HashMap<String, String>> {
 
 			let mut map = HashMap::with_capacity(vec_env_strs.len());
 			for env_str in vec_env_strs {
-				let pair: Vec<&str> = env_str.split("=").collect();
-				map.insert(pair[0].to_owned(), pair[1].to_owned());
+				let sep = env_str.char_indices()
+					.skip(1) // hidden variables, like "=C:", start with "="
+					.find(|(_, ch)| *ch == '=')
+					.map_or(env_str.len(), |(idx, _)| idx);
+				let (name, value) = env_str.split_at(sep);
+				map.insert(name.to_owned(), value.get(1..).unwrap_or("").to_owned());
 			}
 			map
 		})
@@ -209,6 +216,28 @@ pub fn GetFirmwareType() -> WinResult<co::FIRMWARE_TYPE> {
 		.map(|_| co::FIRMWARE_TYPE(ft))
 }
 
+/// [`GetFullPathName`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfullpathnamew)
+/// function.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn GetFullPathName(file_name: &str) -> WinResult<String> {
+	let wfile_name = WString::from_str(file_name);
+	let mut buf = WString::new_alloc_buffer(MAX_PATH + 1);
+	loop {
+		match unsafe {
+			kernel::ffi::GetFullPathNameW(
+				wfile_name.as_ptr(),
+				buf.buffer_size() as _,
+				buf.as_mut_ptr(),
+				std::ptr::null_mut(),
+			)
+		} {
+			0 => return Err(GetLastError()),
+			len if len as usize >= buf.buffer_size() => buf.realloc_buffer(len as _), // buffer too small, len includes terminating null
+			_ => return Ok(buf.to_string()),
+		}
+	}
+}
+
 /// [`GetLargePageMinimum`](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-getlargepageminimum)
 /// function.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
@@ -281,7 +310,9 @@ pub fn GetLogicalDriveStrings() -> WinResult<Vec<String>> {
 pub fn GetFileAttributes(file_name: &str) -> WinResult<co::FILE_ATTRIBUTE> {
 	const INVALID: u32 = INVALID_FILE_ATTRIBUTES as u32;
 	match unsafe {
-		kernel::ffi::GetFileAttributesW(WString::from_str(file_name).as_ptr())
+		kernel::ffi::GetFileAttributesW(
+			WString::from_str(&path::to_win32_path(file_name)).as_ptr(),
+		)
 	} {
 		INVALID => Err(GetLastError()),
 		flags => Ok(co::FILE_ATTRIBUTE(flags)),
@@ -302,6 +333,21 @@ pub fn GetStartupInfo(si: &mut STARTUPINFO) {
 	unsafe { kernel::ffi::GetStartupInfoW(si as *mut _ as _) }
 }
 
+/// [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
+/// function.
+///
+/// The returned handle can be null if the process has no such handle, like a
+/// GUI application without a console.
+///
+/// **Note:** The handle must not be closed, unless it's being replaced.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn GetStdHandle(std_handle: co::STD_HANDLE) -> WinResult<HFILE> {
+	match unsafe { kernel::ffi::GetStdHandle(std_handle.0) } {
+		ptr if ptr as isize == INVALID_HANDLE_VALUE => Err(GetLastError()),
+		ptr => Ok(HFILE(ptr)),
+	}
+}
+
 /// [`GetSystemTime`](https://docs.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getsystemtime)
 /// function.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
@@ -567,8 +613,8 @@ pub fn MoveFile(existing_file: &str, new_file: &str) -> WinResult<()> {
 	bool_to_winresult(
 		unsafe {
 			kernel::ffi::MoveFileW(
-				WString::from_str(existing_file).as_ptr(),
-				WString::from_str(new_file).as_ptr(),
+				WString::from_str(&path::to_win32_path(existing_file)).as_ptr(),
+				WString::from_str(&path::to_win32_path(new_file)).as_ptr(),
 			)
 		},
 	)
@@ -628,9 +674,9 @@ pub fn MultiByteToWideChar(
 /// [`OutputDebugString`](https://docs.microsoft.com/en-us/windows/win32/api/debugapi/nf-debugapi-outputdebugstringw)
 /// function.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
-pub fn OutputDebugString(output_string: &str) {
+pub fn OutputDebugString(output_string: &(impl ToWStr + ?Sized)) {
 	unsafe {
-		kernel::ffi::OutputDebugStringW(WString::from_str(output_string).as_ptr())
+		kernel::ffi::OutputDebugStringW(output_string.to_wstr().as_ptr())
 	}
 }
 
@@ -682,9 +728,9 @@ pub fn ReplaceFile(
 	bool_to_winresult(
 		unsafe {
 			kernel::ffi::ReplaceFileW(
-				WString::from_str(replaced).as_ptr(),
-				WString::from_str(replacement).as_ptr(),
-				WString::from_opt_str(backup).as_ptr(),
+				WString::from_str(&path::to_win32_path(replaced)).as_ptr(),
+				WString::from_str(&path::to_win32_path(replacement)).as_ptr(),
+				WString::from_opt_str(backup.map(path::to_win32_path).as_deref()).as_ptr(),
 				flags.0,
 				std::ptr::null_mut(),
 				std::ptr::null_mut(),
@@ -696,10 +742,10 @@ pub fn ReplaceFile(
 /// [`SetCurrentDirectory`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-setcurrentdirectory)
 /// function.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
-pub fn SetCurrentDirectory(path_name: &str) -> WinResult<()> {
+pub fn SetCurrentDirectory(path_name: &(impl ToWStr + ?Sized)) -> WinResult<()> {
 	bool_to_winresult(
 		unsafe {
-			kernel::ffi::SetCurrentDirectoryW(WString::from_str(path_name).as_ptr())
+			kernel::ffi::SetCurrentDirectoryW(path_name.to_wstr().as_ptr())
 		},
 	)
 }
@@ -1,4 +1,10 @@
-use crate::prelude::HandleClose;
+#![allow(non_snake_case)]
+
+use crate::{co, kernel};
+use crate::kernel::decl::{GetLastError, SECURITY_ATTRIBUTES, WinResult,
+	WString};
+use crate::kernel::privs::{bool_to_winresult, INFINITE};
+use crate::prelude::{Handle, HandleClose};
 
 impl_handle! { HEVENT: "kernel";
 	/// Handle to an
@@ -7,3 +13,60 @@ impl_handle! { HEVENT: "kernel";
 }
 
 impl HandleClose for HEVENT {}
+impl KernelHevent for HEVENT {}
+
+/// [`HEVENT`](crate::HEVENT) methods from `kernel` feature.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub trait KernelHevent: Handle {
+	/// [`CreateEvent`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createeventw)
+	/// static method.
+	///
+	/// **Note:** Must be paired with an
+	/// [`HEVENT::CloseHandle`](crate::prelude::HandleClose::CloseHandle) call.
+	fn CreateEvent(
+		security_attrs: Option<&mut SECURITY_ATTRIBUTES>,
+		manual_reset: bool,
+		initial_state: bool,
+		name: Option<&str>) -> WinResult<HEVENT>
+	{
+		unsafe {
+			kernel::ffi::CreateEventW(
+				security_attrs.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				manual_reset as _,
+				initial_state as _,
+				WString::from_opt_str(name).as_ptr(),
+			).as_mut()
+		}.map(|ptr| HEVENT(ptr))
+			.ok_or_else(GetLastError)
+	}
+
+	/// [`ResetEvent`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-resetevent)
+	/// method.
+	fn ResetEvent(self) -> WinResult<()> {
+		bool_to_winresult(unsafe { kernel::ffi::ResetEvent(self.as_ptr()) })
+	}
+
+	/// [`SetEvent`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-setevent)
+	/// method.
+	fn SetEvent(self) -> WinResult<()> {
+		bool_to_winresult(unsafe { kernel::ffi::SetEvent(self.as_ptr()) })
+	}
+
+	/// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject)
+	/// method.
+	fn WaitForSingleObject(self,
+		milliseconds: Option<u32>) -> WinResult<co::WAIT>
+	{
+		match unsafe {
+			co::WAIT(
+				kernel::ffi::WaitForSingleObject(
+					self.as_ptr(),
+					milliseconds.unwrap_or(INFINITE),
+				),
+			)
+		} {
+			co::WAIT::FAILED => Err(GetLastError()),
+			wait => Ok(wait),
+		}
+	}
+}
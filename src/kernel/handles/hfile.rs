@@ -2,7 +2,8 @@
 
 use crate::{co, kernel};
 use crate::kernel::decl::{BY_HANDLE_FILE_INFORMATION, GetLastError, HFILEMAP,
-	HIDWORD, LODWORD, OVERLAPPED, SECURITY_ATTRIBUTES, WinResult, WString};
+	HIDWORD, LODWORD, OVERLAPPED, path, SECURITY_ATTRIBUTES, WinResult,
+	WString};
 use crate::kernel::privs::{bool_to_winresult, INVALID_HANDLE_VALUE};
 use crate::prelude::{Handle, HandleClose};
 
@@ -18,6 +19,22 @@ impl KernelHfile for HFILE {}
 /// [`HFILE`](crate::HFILE) methods from `kernel` feature.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub trait KernelHfile: Handle {
+	/// [`CancelIoEx`](https://docs.microsoft.com/en-us/windows/win32/fileio/cancelioex-func)
+	/// method.
+	///
+	/// If `overlapped` is `None`, all pending I/O operations issued by the
+	/// calling process for the handle are canceled.
+	fn CancelIoEx(self, overlapped: Option<&mut OVERLAPPED>) -> WinResult<()> {
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::CancelIoEx(
+					self.as_ptr(),
+					overlapped.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				)
+			},
+		)
+	}
+
 	/// [`CreateFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew)
 	/// static method.
 	///
@@ -76,7 +93,7 @@ pub trait KernelHfile: Handle {
 	{
 		match unsafe {
 			kernel::ffi::CreateFileW(
-				WString::from_str(file_name).as_ptr(),
+				WString::from_str(&path::to_win32_path(file_name)).as_ptr(),
 				desired_access.0,
 				share_mode.0,
 				security_attrs.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
@@ -115,6 +132,12 @@ pub trait KernelHfile: Handle {
 			.ok_or_else(|| GetLastError())
 	}
 
+	/// [`FlushFileBuffers`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-flushfilebuffers)
+	/// method.
+	fn FlushFileBuffers(self) -> WinResult<()> {
+		bool_to_winresult(unsafe { kernel::ffi::FlushFileBuffers(self.as_ptr()) })
+	}
+
 	/// [`GetFileInformationByHandle`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfileinformationbyhandle)
 	/// method.
 	fn GetFileInformationByHandle(self,
@@ -151,6 +174,26 @@ pub trait KernelHfile: Handle {
 		}
 	}
 
+	/// [`GetOverlappedResult`](https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getoverlappedresult)
+	/// method.
+	///
+	/// Returns the number of bytes transferred.
+	fn GetOverlappedResult(self,
+		overlapped: &OVERLAPPED, wait: bool) -> WinResult<u32>
+	{
+		let mut bytes_transferred = u32::default();
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::GetOverlappedResult(
+					self.as_ptr(),
+					overlapped as *const _ as _,
+					&mut bytes_transferred,
+					wait as _,
+				)
+			},
+		).map(|_| bytes_transferred)
+	}
+
 	/// [`LockFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-lockfile)
 	/// method.
 	///
@@ -1,8 +1,8 @@
 #![allow(non_snake_case)]
 
 use crate::{co, kernel};
-use crate::kernel::decl::{GetLastError, path, WIN32_FIND_DATA, WinResult,
-	WString};
+use crate::kernel::decl::{DirWalk, DirWalkOptions, GetLastError, path,
+	WIN32_FIND_DATA, WinResult, WString};
 use crate::kernel::privs::bool_to_winresult;
 use crate::prelude::Handle;
 
@@ -56,6 +56,60 @@ pub trait KernelHfindfile: Handle {
 		Box::new(HfindfileIter::new(path_and_pattern))
 	}
 
+	/// Returns an iterator which recursively walks the directory, with the
+	/// default [`DirWalkOptions`](crate::DirWalkOptions). The directory itself
+	/// is not returned.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::HFINDFILE;
+	///
+	/// for entry in HFINDFILE::walk("C:\\Temp") {
+	///     let entry = entry?;
+	///     println!("{} - {} bytes", entry.path, entry.size);
+	/// }
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	fn walk(dir: &str) -> DirWalk {
+		DirWalk::new(dir, DirWalkOptions::default())
+	}
+
+	/// Returns an iterator which recursively walks the directory, with the
+	/// given options. The directory itself is not returned.
+	///
+	/// # Examples
+	///
+	/// Listing the visible Rust files up to two levels deep, sorted by name,
+	/// and skipping any `target` directory:
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{DirSort, DirWalkOptions, HFINDFILE, path};
+	///
+	/// let options = DirWalkOptions {
+	///     max_depth: Some(2),
+	///     skip_hidden: true,
+	///     sort: DirSort::Name,
+	///     ..Default::default()
+	/// };
+	///
+	/// let walk = HFINDFILE::walk_with("C:\\Projects", options)
+	///     .filter_entry(|entry| entry.file_name() != "target");
+	///
+	/// for entry in walk {
+	///     let entry = entry?;
+	///     if !entry.is_dir() && path::has_extension(&entry.path, &[".rs"]) {
+	///         println!("{}", entry.path);
+	///     }
+	/// }
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	fn walk_with(dir: &str, options: DirWalkOptions) -> DirWalk {
+		DirWalk::new(dir, options)
+	}
+
 	/// [`FindClose`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findclose)
 	/// method.
 	fn FindClose(self) -> WinResult<()> {
@@ -77,8 +131,42 @@ pub trait KernelHfindfile: Handle {
 	{
 		match unsafe {
 			kernel::ffi::FindFirstFileW(
-				WString::from_str(file_name).as_ptr(),
+				WString::from_str(&path::to_win32_path(file_name)).as_ptr(),
+				wfd as *mut _ as _,
+			).as_mut()
+		} {
+			Some(ptr) => Ok((HFINDFILE(ptr), true)), // first file found
+			None => match GetLastError() {
+				co::ERROR::FILE_NOT_FOUND => Ok((HFINDFILE::NULL, false)), // not an error, first file not found
+				err => Err(err),
+			},
+		}
+	}
+
+	/// [`FindFirstFileEx`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findfirstfileexw)
+	/// static method.
+	///
+	/// **Note:** Must be paired with an
+	/// [`HFINDFILE::FindClose`](crate::prelude::KernelHfindfile::FindClose)
+	/// call.
+	///
+	/// To list directories recursively, consider using
+	/// [`HFINDFILE::walk`](crate::prelude::KernelHfindfile::walk).
+	fn FindFirstFileEx(
+		file_name: &str,
+		info_level: co::FINDEX_INFO_LEVELS,
+		wfd: &mut WIN32_FIND_DATA,
+		search_op: co::FINDEX_SEARCH_OPS,
+		additional_flags: co::FIND_FIRST_EX) -> WinResult<(HFINDFILE, bool)>
+	{
+		match unsafe {
+			kernel::ffi::FindFirstFileExW(
+				WString::from_str(&path::to_win32_path(file_name)).as_ptr(),
+				info_level.0,
 				wfd as *mut _ as _,
+				search_op.0,
+				std::ptr::null_mut(),
+				additional_flags.0,
 			).as_mut()
 		} {
 			Some(ptr) => Ok((HFINDFILE(ptr), true)), // first file found
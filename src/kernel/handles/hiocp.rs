@@ -0,0 +1,135 @@
+#![allow(non_snake_case)]
+
+use crate::kernel;
+use crate::kernel::decl::{GetLastError, OVERLAPPED, OVERLAPPED_ENTRY,
+	WinResult};
+use crate::kernel::privs::{bool_to_winresult, INFINITE, INVALID_HANDLE_VALUE};
+use crate::prelude::{Handle, HandleClose};
+
+impl_handle! { HIOCP: "kernel";
+	/// Handle to an
+	/// [I/O completion port](https://docs.microsoft.com/en-us/windows/win32/fileio/i-o-completion-ports).
+	/// Originally just a `HANDLE`.
+	///
+	/// For a safe way to run asynchronous I/O, see
+	/// [`CompletionPort`](crate::CompletionPort).
+}
+
+impl HandleClose for HIOCP {}
+impl KernelHiocp for HIOCP {}
+
+/// [`HIOCP`](crate::HIOCP) methods from `kernel` feature.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub trait KernelHiocp: Handle {
+	/// [`CreateIoCompletionPort`](https://docs.microsoft.com/en-us/windows/win32/fileio/createiocompletionport)
+	/// method, which associates a file or pipe handle to this port.
+	///
+	/// The handle must have been opened with
+	/// [`FILE_ATTRIBUTE::FLAG_OVERLAPPED`](crate::co::FILE_ATTRIBUTE::FLAG_OVERLAPPED).
+	/// The completion key is returned along with each completed operation of
+	/// the handle.
+	fn AssociateHandle(self,
+		handle: impl Handle, completion_key: usize) -> WinResult<()>
+	{
+		match unsafe {
+			kernel::ffi::CreateIoCompletionPort(
+				handle.as_ptr(), self.as_ptr(), completion_key, 0).as_mut()
+		} {
+			None => Err(GetLastError()),
+			Some(_) => Ok(()), // same port handle
+		}
+	}
+
+	/// [`CreateIoCompletionPort`](https://docs.microsoft.com/en-us/windows/win32/fileio/createiocompletionport)
+	/// static method, which creates a new port.
+	///
+	/// If `num_concurrent_threads` is `None`, the system allows as many
+	/// threads as processors.
+	///
+	/// **Note:** Must be paired with an
+	/// [`HIOCP::CloseHandle`](crate::prelude::HandleClose::CloseHandle) call.
+	fn CreateIoCompletionPort(
+		num_concurrent_threads: Option<u32>) -> WinResult<HIOCP>
+	{
+		unsafe {
+			kernel::ffi::CreateIoCompletionPort(
+				INVALID_HANDLE_VALUE as _,
+				std::ptr::null_mut(),
+				0,
+				num_concurrent_threads.unwrap_or_default(),
+			).as_mut()
+		}.map(|ptr| HIOCP(ptr))
+			.ok_or_else(GetLastError)
+	}
+
+	/// [`GetQueuedCompletionStatus`](https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getqueuedcompletionstatus)
+	/// method.
+	///
+	/// The `entry` is filled even if the function fails: if
+	/// `entry.lpOverlapped` is not null, an I/O operation was dequeued, and the
+	/// error is the error of the operation; otherwise the wait itself failed,
+	/// like with [`ERROR::WAIT_TIMEOUT`](crate::co::ERROR::WAIT_TIMEOUT).
+	fn GetQueuedCompletionStatus(self,
+		entry: &mut OVERLAPPED_ENTRY, milliseconds: Option<u32>) -> WinResult<()>
+	{
+		let mut overlapped = std::ptr::null_mut();
+		let res = bool_to_winresult(
+			unsafe {
+				kernel::ffi::GetQueuedCompletionStatus(
+					self.as_ptr(),
+					&mut entry.dwNumberOfBytesTransferred,
+					&mut entry.lpCompletionKey,
+					&mut overlapped,
+					milliseconds.unwrap_or(INFINITE),
+				)
+			},
+		);
+		entry.lpOverlapped = overlapped as _;
+		res
+	}
+
+	/// [`GetQueuedCompletionStatusEx`](https://docs.microsoft.com/en-us/windows/win32/fileio/getqueuedcompletionstatusex-func)
+	/// method.
+	///
+	/// Returns the number of entries which were filled. The result of each
+	/// operation can be retrieved with
+	/// [`HFILE::GetOverlappedResult`](crate::prelude::KernelHfile::GetOverlappedResult).
+	fn GetQueuedCompletionStatusEx(self,
+		entries: &mut [OVERLAPPED_ENTRY],
+		milliseconds: Option<u32>,
+		alertable: bool) -> WinResult<usize>
+	{
+		let mut num_removed = u32::default();
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::GetQueuedCompletionStatusEx(
+					self.as_ptr(),
+					entries.as_mut_ptr() as _,
+					entries.len() as _,
+					&mut num_removed,
+					milliseconds.unwrap_or(INFINITE),
+					alertable as _,
+				)
+			},
+		).map(|_| num_removed as _)
+	}
+
+	/// [`PostQueuedCompletionStatus`](https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-postqueuedcompletionstatus)
+	/// method.
+	fn PostQueuedCompletionStatus(self,
+		num_bytes_transferred: u32,
+		completion_key: usize,
+		overlapped: Option<&mut OVERLAPPED>) -> WinResult<()>
+	{
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::PostQueuedCompletionStatus(
+					self.as_ptr(),
+					num_bytes_transferred,
+					completion_key,
+					overlapped.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				)
+			},
+		)
+	}
+}
@@ -0,0 +1,113 @@
+#![allow(non_snake_case)]
+
+use crate::{co, kernel};
+use crate::kernel::decl::{GetLastError, HPROCESS, SECURITY_ATTRIBUTES,
+	WinResult, WString};
+use crate::kernel::privs::bool_to_winresult;
+use crate::prelude::{Handle, HandleClose};
+
+impl_handle! { HJOB: "kernel";
+	/// Handle to a
+	/// [job object](https://docs.microsoft.com/en-us/windows/win32/procthread/job-objects).
+	/// Originally just a `HANDLE`.
+	///
+	/// For a safe way to manage a job, see [`Job`](crate::Job).
+}
+
+impl HandleClose for HJOB {}
+impl KernelHjob for HJOB {}
+
+/// [`HJOB`](crate::HJOB) methods from `kernel` feature.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub trait KernelHjob: Handle {
+	/// [`AssignProcessToJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-assignprocesstojobobject)
+	/// method.
+	///
+	/// Processes created afterwards by the assigned process are associated to
+	/// the job too.
+	fn AssignProcessToJobObject(self, hprocess: HPROCESS) -> WinResult<()> {
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::AssignProcessToJobObject(
+					self.as_ptr(), hprocess.as_ptr(),
+				)
+			},
+		)
+	}
+
+	/// [`CreateJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-createjobobjectw)
+	/// static method.
+	///
+	/// **Note:** Must be paired with an
+	/// [`HJOB::CloseHandle`](crate::prelude::HandleClose::CloseHandle) call.
+	fn CreateJobObject(
+		security_attrs: Option<&mut SECURITY_ATTRIBUTES>,
+		name: Option<&str>) -> WinResult<HJOB>
+	{
+		unsafe {
+			kernel::ffi::CreateJobObjectW(
+				security_attrs.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				WString::from_opt_str(name).as_ptr(),
+			).as_mut()
+		}.map(|ptr| HJOB(ptr))
+			.ok_or_else(GetLastError)
+	}
+
+	/// [`QueryInformationJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-queryinformationjobobject)
+	/// method.
+	///
+	/// Returns the number of bytes written to `info`.
+	///
+	/// # Safety
+	///
+	/// The `info` type must be the struct expected by `info_class`, like
+	/// [`JOBOBJECT_EXTENDED_LIMIT_INFORMATION`](crate::JOBOBJECT_EXTENDED_LIMIT_INFORMATION)
+	/// for
+	/// [`JOBOBJECTINFOCLASS::ExtendedLimitInformation`](crate::co::JOBOBJECTINFOCLASS::ExtendedLimitInformation).
+	unsafe fn QueryInformationJobObject<T>(self,
+		info_class: co::JOBOBJECTINFOCLASS, info: &mut T) -> WinResult<u32>
+	{
+		let mut ret_len = u32::default();
+		bool_to_winresult(
+			kernel::ffi::QueryInformationJobObject(
+				self.as_ptr(),
+				info_class.0,
+				info as *mut _ as _,
+				std::mem::size_of::<T>() as _,
+				&mut ret_len,
+			),
+		).map(|_| ret_len)
+	}
+
+	/// [`SetInformationJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-setinformationjobobject)
+	/// method.
+	///
+	/// # Safety
+	///
+	/// The `info` type must be the struct expected by `info_class`, like
+	/// [`JOBOBJECT_EXTENDED_LIMIT_INFORMATION`](crate::JOBOBJECT_EXTENDED_LIMIT_INFORMATION)
+	/// for
+	/// [`JOBOBJECTINFOCLASS::ExtendedLimitInformation`](crate::co::JOBOBJECTINFOCLASS::ExtendedLimitInformation).
+	unsafe fn SetInformationJobObject<T>(self,
+		info_class: co::JOBOBJECTINFOCLASS, info: &T) -> WinResult<()>
+	{
+		bool_to_winresult(
+			kernel::ffi::SetInformationJobObject(
+				self.as_ptr(),
+				info_class.0,
+				info as *const _ as _,
+				std::mem::size_of::<T>() as _,
+			),
+		)
+	}
+
+	/// [`TerminateJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-terminatejobobject)
+	/// method.
+	///
+	/// Terminates all processes associated to the job.
+	fn TerminateJobObject(self, exit_code: u32) -> WinResult<()> {
+		bool_to_winresult(
+			unsafe { kernel::ffi::TerminateJobObject(self.as_ptr(), exit_code) },
+		)
+	}
+}
@@ -1,14 +1,22 @@
 #![allow(non_snake_case)]
 
-use crate::kernel;
-use crate::kernel::decl::{HFILE, OVERLAPPED, SECURITY_ATTRIBUTES, WinResult};
-use crate::kernel::privs::bool_to_winresult;
+use crate::{co, kernel};
+use crate::kernel::decl::{GetLastError, HFILE, OVERLAPPED, SECURITY_ATTRIBUTES,
+	WinResult, WString};
+use crate::kernel::privs::{bool_to_winresult, INFINITE, INVALID_HANDLE_VALUE,
+	PIPE_UNLIMITED_INSTANCES};
 use crate::prelude::{Handle, HandleClose, KernelHfile};
 
 impl_handle! { HPIPE: "kernel";
 	/// Handle to an
-	/// [anonymous pipe](https://docs.microsoft.com/en-us/windows/win32/ipc/anonymous-pipes).
+	/// [anonymous pipe](https://docs.microsoft.com/en-us/windows/win32/ipc/anonymous-pipes)
+	/// or a
+	/// [named pipe](https://docs.microsoft.com/en-us/windows/win32/ipc/named-pipes).
 	/// Originally just a `HANDLE`.
+	///
+	/// For a higher-level API to named pipes, see
+	/// [`NamedPipeServer`](crate::NamedPipeServer) and
+	/// [`NamedPipeClient`](crate::NamedPipeClient).
 }
 
 impl HandleClose for HPIPE {}
@@ -17,6 +25,73 @@ impl KernelHpipe for HPIPE {}
 /// [`HPIPE`](crate::HPIPE) methods from `kernel` feature.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub trait KernelHpipe: Handle {
+	/// [`CancelIoEx`](https://docs.microsoft.com/en-us/windows/win32/fileio/cancelioex-func)
+	/// method.
+	fn CancelIoEx(self, overlapped: Option<&mut OVERLAPPED>) -> WinResult<()> {
+		HFILE(unsafe { self.as_ptr() }).CancelIoEx(overlapped)
+	}
+
+	/// [`ConnectNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-connectnamedpipe)
+	/// method.
+	///
+	/// If a client connected between
+	/// [`HPIPE::CreateNamedPipe`](crate::prelude::KernelHpipe::CreateNamedPipe)
+	/// and this call, which fails with
+	/// [`ERROR::PIPE_CONNECTED`](crate::co::ERROR::PIPE_CONNECTED), `Ok` is
+	/// returned.
+	fn ConnectNamedPipe(self, overlapped: Option<&mut OVERLAPPED>) -> WinResult<()> {
+		match bool_to_winresult(
+			unsafe {
+				kernel::ffi::ConnectNamedPipe(
+					self.as_ptr(),
+					overlapped.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				)
+			},
+		) {
+			Err(co::ERROR::PIPE_CONNECTED) => Ok(()),
+			res => res,
+		}
+	}
+
+	/// [`CreateNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-createnamedpipew)
+	/// static method.
+	///
+	/// The name must have the form `\\.\pipe\pipename`. If `max_instances`
+	/// is `None`, the number of instances is limited only by the system
+	/// resources. If `default_timeout` is `None`, the default of 50
+	/// milliseconds is used by
+	/// [`HPIPE::WaitNamedPipe`](crate::prelude::KernelHpipe::WaitNamedPipe).
+	///
+	/// **Note:** Must be paired with an
+	/// [`HPIPE::CloseHandle`](crate::prelude::HandleClose::CloseHandle) call.
+	#[allow(clippy::too_many_arguments)]
+	fn CreateNamedPipe(
+		name: &str,
+		open_mode: co::PIPE_ACCESS,
+		pipe_mode: co::PIPE,
+		max_instances: Option<u32>,
+		out_buffer_size: u32,
+		in_buffer_size: u32,
+		default_timeout: Option<u32>,
+		security_attrs: Option<&mut SECURITY_ATTRIBUTES>) -> WinResult<HPIPE>
+	{
+		match unsafe {
+			kernel::ffi::CreateNamedPipeW(
+				WString::from_str(name).as_ptr(),
+				open_mode.0,
+				pipe_mode.0,
+				max_instances.unwrap_or(PIPE_UNLIMITED_INSTANCES),
+				out_buffer_size,
+				in_buffer_size,
+				default_timeout.unwrap_or_default(),
+				security_attrs.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+			) as _
+		} {
+			INVALID_HANDLE_VALUE => Err(GetLastError()),
+			ptr => Ok(HPIPE(ptr as _)),
+		}
+	}
+
 	/// [`CreatePipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-createpipe)
 	/// static method.
 	///
@@ -41,6 +116,60 @@ pub trait KernelHpipe: Handle {
 		).map(|_| (hread, hwrite))
 	}
 
+	/// [`DisconnectNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-disconnectnamedpipe)
+	/// method.
+	fn DisconnectNamedPipe(self) -> WinResult<()> {
+		bool_to_winresult(unsafe { kernel::ffi::DisconnectNamedPipe(self.as_ptr()) })
+	}
+
+	/// [`FlushFileBuffers`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-flushfilebuffers)
+	/// method.
+	///
+	/// Blocks until the client has read all data written to the pipe.
+	fn FlushFileBuffers(self) -> WinResult<()> {
+		HFILE(unsafe { self.as_ptr() }).FlushFileBuffers()
+	}
+
+	/// [`GetOverlappedResult`](https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-getoverlappedresult)
+	/// method.
+	///
+	/// Returns the number of bytes transferred.
+	fn GetOverlappedResult(self,
+		overlapped: &OVERLAPPED, wait: bool) -> WinResult<u32>
+	{
+		HFILE(unsafe { self.as_ptr() }).GetOverlappedResult(overlapped, wait)
+	}
+
+	/// [`PeekNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-peeknamedpipe)
+	/// method.
+	///
+	/// Copies data into the buffer, if any, without removing it from the pipe.
+	/// Returns the number of bytes copied, the total number of bytes available
+	/// to be read, and the number of bytes remaining in the current message –
+	/// which is zero for byte-type pipes.
+	fn PeekNamedPipe(self,
+		buffer: Option<&mut [u8]>) -> WinResult<(u32, u32, u32)>
+	{
+		let (mut bytes_read, mut total_avail, mut left_this_msg) = (0, 0, 0);
+		let (buf_ptr, buf_len) = buffer.map_or(
+			(std::ptr::null_mut(), 0),
+			|buf| (buf.as_mut_ptr(), buf.len()),
+		);
+
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::PeekNamedPipe(
+					self.as_ptr(),
+					buf_ptr as _,
+					buf_len as _,
+					&mut bytes_read,
+					&mut total_avail,
+					&mut left_this_msg,
+				)
+			},
+		).map(|_| (bytes_read, total_avail, left_this_msg))
+	}
+
 	/// [`ReadFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-readfile)
 	/// method.
 	///
@@ -52,6 +181,78 @@ pub trait KernelHpipe: Handle {
 		HFILE(unsafe { self.as_ptr() }).ReadFile(buffer, overlapped)
 	}
 
+	/// [`SetNamedPipeHandleState`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-setnamedpipehandlestate)
+	/// method.
+	///
+	/// Only the `Some` values are changed. The `mode` accepts only the
+	/// `READMODE` and `WAIT` flags of [`co::PIPE`](crate::co::PIPE).
+	fn SetNamedPipeHandleState(self,
+		mode: Option<co::PIPE>,
+		max_collection_count: Option<u32>,
+		collect_data_timeout: Option<u32>) -> WinResult<()>
+	{
+		let mut mode = mode.map(|m| m.0);
+		let mut max_collection_count = max_collection_count;
+		let mut collect_data_timeout = collect_data_timeout;
+
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::SetNamedPipeHandleState(
+					self.as_ptr(),
+					mode.as_mut().map_or(std::ptr::null_mut(), |m| m),
+					max_collection_count.as_mut().map_or(std::ptr::null_mut(), |n| n),
+					collect_data_timeout.as_mut().map_or(std::ptr::null_mut(), |t| t),
+				)
+			},
+		)
+	}
+
+	/// [`TransactNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-transactnamedpipe)
+	/// method.
+	///
+	/// Writes a message and reads the reply into `output`, returning the number
+	/// of bytes read. If the reply doesn't fit, fails with
+	/// [`ERROR::MORE_DATA`](crate::co::ERROR::MORE_DATA), and the rest can be
+	/// read with [`HPIPE::ReadFile`](crate::prelude::KernelHpipe::ReadFile).
+	fn TransactNamedPipe(self,
+		input: &[u8],
+		output: &mut [u8],
+		overlapped: Option<&mut OVERLAPPED>) -> WinResult<u32>
+	{
+		let mut bytes_read = u32::default();
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::TransactNamedPipe(
+					self.as_ptr(),
+					input.as_ptr() as _,
+					input.len() as _,
+					output.as_mut_ptr() as _,
+					output.len() as _,
+					&mut bytes_read,
+					overlapped.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
+				)
+			},
+		).map(|_| bytes_read)
+	}
+
+	/// [`WaitNamedPipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-waitnamedpipew)
+	/// static method.
+	///
+	/// Waits until an instance of the pipe is available for connection. If
+	/// `milliseconds` is `None`, waits forever; zero means the default timeout
+	/// given to
+	/// [`HPIPE::CreateNamedPipe`](crate::prelude::KernelHpipe::CreateNamedPipe).
+	fn WaitNamedPipe(name: &str, milliseconds: Option<u32>) -> WinResult<()> {
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::WaitNamedPipeW(
+					WString::from_str(name).as_ptr(),
+					milliseconds.unwrap_or(INFINITE), // NMPWAIT_WAIT_FOREVER
+				)
+			},
+		)
+	}
+
 	/// [`WriteFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-writefile)
 	/// method.
 	fn WriteFile(self,
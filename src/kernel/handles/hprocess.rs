@@ -2,7 +2,7 @@
 
 use crate::{co, kernel};
 use crate::ffi_types::BOOL;
-use crate::kernel::decl::{FILETIME, GetLastError, PROCESS_INFORMATION,
+use crate::kernel::decl::{FILETIME, GetLastError, HJOB, PROCESS_INFORMATION,
 	SECURITY_ATTRIBUTES, STARTUPINFO, WinResult, WString};
 use crate::kernel::privs::{bool_to_winresult, INFINITE, MAX_PATH};
 use crate::prelude::{Handle, HandleClose};
@@ -28,6 +28,12 @@ pub trait KernelHprocess: Handle {
 	/// [`HPROCESS::CloseHandle`](crate::prelude::HandleClose::CloseHandle) and
 	/// [`HTHREAD::CloseHandle`](crate::prelude::HandleClose::CloseHandle)
 	/// calls.
+	///
+	/// The `environment` strings have the form `name=value`, and require
+	/// [`CREATE::UNICODE_ENVIRONMENT`](crate::co::CREATE::UNICODE_ENVIRONMENT).
+	///
+	/// For a higher-level way to run a process, see
+	/// [`Command`](crate::Command).
 	fn CreateProcess(
 		application_name: Option<&str>,
 		command_line: Option<&str>,
@@ -40,6 +46,7 @@ pub trait KernelHprocess: Handle {
 		si: &mut STARTUPINFO) -> WinResult<PROCESS_INFORMATION>
 	{
 		let mut buf_cmd_line = command_line.map_or(WString::default(), |lp| WString::from_str(lp));
+		let buf_env = environment.as_ref().map(|env| WString::from_str_vec(env));
 		let mut pi = PROCESS_INFORMATION::default();
 
 		bool_to_winresult(
@@ -51,8 +58,8 @@ pub trait KernelHprocess: Handle {
 					thread_attrs.map_or(std::ptr::null_mut(), |lp| lp as *mut _ as _),
 					inherit_handles as _,
 					creation_flags.0,
-					environment.as_ref()
-						.map_or(std::ptr::null_mut(), |lp| WString::from_str_vec(lp).as_ptr() as _),
+					buf_env.as_ref()
+						.map_or(std::ptr::null_mut(), |buf| buf.as_ptr() as _),
 					WString::from_opt_str(current_dir).as_ptr(),
 					si as *mut _ as _,
 					&mut pi as *mut _ as _,
@@ -61,6 +68,40 @@ pub trait KernelHprocess: Handle {
 		).map(|_| pi)
 	}
 
+	/// [`DuplicateHandle`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle)
+	/// method.
+	///
+	/// Duplicates a handle of this process into `target_process`. To
+	/// duplicate within the current process, call it on
+	/// [`HPROCESS::GetCurrentProcess`](crate::prelude::KernelHprocess::GetCurrentProcess)
+	/// and pass it as the target too.
+	///
+	/// **Note:** The returned handle belongs to the target process; if it's
+	/// the current one, it must be paired with a
+	/// [`CloseHandle`](crate::prelude::HandleClose::CloseHandle) call.
+	fn DuplicateHandle<H: Handle>(self,
+		source_handle: H,
+		target_process: HPROCESS,
+		desired_access: u32,
+		inherit_handle: bool,
+		options: co::DUPLICATE) -> WinResult<H>
+	{
+		let mut target_handle = std::ptr::null_mut();
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::DuplicateHandle(
+					self.as_ptr(),
+					source_handle.as_ptr(),
+					target_process.as_ptr(),
+					&mut target_handle,
+					desired_access,
+					inherit_handle as _,
+					options.0,
+				)
+			},
+		).map(|_| unsafe { H::from_ptr(target_handle) })
+	}
+
 	/// [`ExitProcess`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-exitprocess)
 	/// static method.
 	fn ExitProcess(exit_code: u32) {
@@ -143,6 +184,23 @@ pub trait KernelHprocess: Handle {
 		)
 	}
 
+	/// [`IsProcessInJob`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi/nf-jobapi-isprocessinjob)
+	/// method.
+	///
+	/// If `job` is `None`, tells whether the process is associated to any job.
+	fn IsProcessInJob(self, job: Option<HJOB>) -> WinResult<bool> {
+		let mut result: BOOL = 0;
+		bool_to_winresult(
+			unsafe {
+				kernel::ffi::IsProcessInJob(
+					self.as_ptr(),
+					job.map_or(std::ptr::null_mut(), |h| h.0),
+					&mut result,
+				)
+			},
+		).map(|_| result != 0)
+	}
+
 	/// [`IsWow64Process`](https://docs.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process)
 	/// method.
 	fn IsWow64Process(self) -> WinResult<bool> {
@@ -193,6 +251,14 @@ pub trait KernelHprocess: Handle {
 		).map(|_| buf.to_string())
 	}
 
+	/// [`TerminateProcess`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-terminateprocess)
+	/// method.
+	fn TerminateProcess(self, exit_code: u32) -> WinResult<()> {
+		bool_to_winresult(
+			unsafe { kernel::ffi::TerminateProcess(self.as_ptr(), exit_code) },
+		)
+	}
+
 	/// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject)
 	/// method.
 	fn WaitForSingleObject(self,
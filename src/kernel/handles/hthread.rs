@@ -107,4 +107,27 @@ pub trait KernelHthread: Handle {
 			},
 		)
 	}
+
+	/// [`ResumeThread`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-resumethread)
+	/// method.
+	///
+	/// Returns the previous suspend count; the thread runs when it reaches
+	/// zero.
+	fn ResumeThread(self) -> WinResult<u32> {
+		match unsafe { kernel::ffi::ResumeThread(self.as_ptr()) } {
+			u32::MAX => Err(GetLastError()),
+			count => Ok(count),
+		}
+	}
+
+	/// [`SuspendThread`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-suspendthread)
+	/// method.
+	///
+	/// Returns the previous suspend count.
+	fn SuspendThread(self) -> WinResult<u32> {
+		match unsafe { kernel::ffi::SuspendThread(self.as_ptr()) } {
+			u32::MAX => Err(GetLastError()),
+			count => Ok(count),
+		}
+	}
 }
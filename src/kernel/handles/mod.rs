@@ -6,6 +6,8 @@ mod hfilemapview;
 mod hfindfile;
 mod hglobal;
 mod hinstance;
+mod hiocp;
+mod hjob;
 mod hlocal;
 mod hpipe;
 mod hprocess;
@@ -21,6 +23,8 @@ pub mod decl {
 	pub use super::hfindfile::HFINDFILE;
 	pub use super::hglobal::HGLOBAL;
 	pub use super::hinstance::HINSTANCE;
+	pub use super::hiocp::HIOCP;
+	pub use super::hjob::HJOB;
 	pub use super::hlocal::HLOCAL;
 	pub use super::hpipe::HPIPE;
 	pub use super::hprocess::HPROCESS;
@@ -49,12 +53,15 @@ pub mod decl {
 
 pub mod traits {
 	pub use super::handle::{Handle, HandleClose};
+	pub use super::hevent::KernelHevent;
 	pub use super::hfile::KernelHfile;
 	pub use super::hfilemap::KernelHfilemap;
 	pub use super::hfilemapview::KernelHfilemapview;
 	pub use super::hfindfile::KernelHfindfile;
 	pub use super::hglobal::KernelHglobal;
 	pub use super::hinstance::KernelHinstance;
+	pub use super::hiocp::KernelHiocp;
+	pub use super::hjob::KernelHjob;
 	pub use super::hlocal::KernelHlocal;
 	pub use super::hpipe::KernelHpipe;
 	pub use super::hprocess::KernelHprocess;
@@ -9,6 +9,7 @@ pub(crate) const INVALID_FILE_ATTRIBUTES: i32 = -1;
 pub(crate) const INVALID_HANDLE_VALUE: isize = -1;
 pub(crate) const MAX_COMPUTERNAME_LENGTH: usize = 15;
 pub(crate) const MAX_PATH: usize = 260;
+pub(crate) const PIPE_UNLIMITED_INSTANCES: u32 = 255;
 
 /// [`IS_INTRESOURCE`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-is_intresource)
 /// macro.
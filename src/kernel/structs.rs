@@ -3,7 +3,8 @@
 use std::marker::PhantomData;
 
 use crate::co;
-use crate::kernel::decl::{HEVENT, HPIPE, HPROCESS, HTHREAD, MAKEQWORD};
+use crate::kernel::decl::{HEVENT, HPIPE, HPROCESS, HIDWORD, HTHREAD, LODWORD,
+	MAKEQWORD};
 use crate::kernel::privs::MAX_PATH;
 
 /// [`ACL`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-acl)
@@ -41,12 +42,146 @@ pub struct BY_HANDLE_FILE_INFORMATION {
 /// struct.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 #[repr(C)]
-#[derive(Default, Clone, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
 pub struct FILETIME {
 	pub dwLowDateTime: u32,
 	pub dwHighDateTime: u32,
 }
 
+/// [`IO_COUNTERS`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-io_counters)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct IO_COUNTERS {
+	pub ReadOperationCount: u64,
+	pub WriteOperationCount: u64,
+	pub OtherOperationCount: u64,
+	pub ReadTransferCount: u64,
+	pub WriteTransferCount: u64,
+	pub OtherTransferCount: u64,
+}
+
+/// [`JOBOBJECT_BASIC_ACCOUNTING_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_accounting_information)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_BASIC_ACCOUNTING_INFORMATION {
+	pub TotalUserTime: i64,
+	pub TotalKernelTime: i64,
+	pub ThisPeriodTotalUserTime: i64,
+	pub ThisPeriodTotalKernelTime: i64,
+	pub TotalPageFaultCount: u32,
+	pub TotalProcesses: u32,
+	pub ActiveProcesses: u32,
+	pub TotalTerminatedProcesses: u32,
+}
+
+/// [`JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_and_io_accounting_information)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION {
+	pub BasicInfo: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+	pub IoInfo: IO_COUNTERS,
+}
+
+/// [`JOBOBJECT_BASIC_LIMIT_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_limit_information)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_BASIC_LIMIT_INFORMATION {
+	pub PerProcessUserTimeLimit: i64,
+	pub PerJobUserTimeLimit: i64,
+	pub LimitFlags: co::JOB_OBJECT_LIMIT,
+	pub MinimumWorkingSetSize: usize,
+	pub MaximumWorkingSetSize: usize,
+	pub ActiveProcessLimit: u32,
+	pub Affinity: usize,
+	pub PriorityClass: u32,
+	pub SchedulingClass: u32,
+}
+
+/// [`JOBOBJECT_BASIC_UI_RESTRICTIONS`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_ui_restrictions)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_BASIC_UI_RESTRICTIONS {
+	pub UIRestrictionsClass: co::JOB_OBJECT_UILIMIT,
+}
+
+/// [`JOBOBJECT_CPU_RATE_CONTROL_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_cpu_rate_control_information)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_CPU_RATE_CONTROL_INFORMATION {
+	pub ControlFlags: co::JOB_OBJECT_CPU_RATE_CONTROL,
+	Value: u32, // union of CpuRate, Weight, and MinRate/MaxRate
+}
+
+impl JOBOBJECT_CPU_RATE_CONTROL_INFORMATION {
+	/// Returns the `CpuRate` field, the portion of the processor cycles, in
+	/// hundredths of percent.
+	pub const fn CpuRate(&self) -> u32 {
+		self.Value
+	}
+
+	/// Sets the `CpuRate` field, the portion of the processor cycles, in
+	/// hundredths of percent.
+	pub fn set_CpuRate(&mut self, val: u32) {
+		self.Value = val;
+	}
+
+	/// Returns the `Weight` field.
+	pub const fn Weight(&self) -> u32 {
+		self.Value
+	}
+
+	/// Sets the `Weight` field, from 1 to 9.
+	pub fn set_Weight(&mut self, val: u32) {
+		self.Value = val;
+	}
+
+	/// Returns the `MinRate` field.
+	pub const fn MinRate(&self) -> u16 {
+		(self.Value & 0xffff) as _
+	}
+
+	/// Sets the `MinRate` field.
+	pub fn set_MinRate(&mut self, val: u16) {
+		self.Value = (self.Value & 0xffff_0000) | val as u32;
+	}
+
+	/// Returns the `MaxRate` field.
+	pub const fn MaxRate(&self) -> u16 {
+		(self.Value >> 16) as _
+	}
+
+	/// Sets the `MaxRate` field.
+	pub fn set_MaxRate(&mut self, val: u16) {
+		self.Value = (self.Value & 0x0000_ffff) | ((val as u32) << 16);
+	}
+}
+
+/// [`JOBOBJECT_EXTENDED_LIMIT_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_extended_limit_information)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct JOBOBJECT_EXTENDED_LIMIT_INFORMATION {
+	pub BasicLimitInformation: JOBOBJECT_BASIC_LIMIT_INFORMATION,
+	pub IoInfo: IO_COUNTERS,
+	pub ProcessMemoryLimit: usize,
+	pub JobMemoryLimit: usize,
+	pub PeakProcessMemoryUsed: usize,
+	pub PeakJobMemoryUsed: usize,
+}
+
 /// [`LANGID`](https://docs.microsoft.com/en-us/windows/win32/intl/language-identifiers)
 /// language identifier.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
@@ -182,12 +317,39 @@ impl OSVERSIONINFOEX {
 pub struct OVERLAPPED {
 	pub Internal: usize,
 	pub InternalHigh: usize,
-	pub Pointer: usize,
+	Offset: u32,
+	OffsetHigh: u32,
 	pub hEvent: HEVENT,
 }
 
 impl_default!(OVERLAPPED);
 
+impl OVERLAPPED {
+	/// Returns the Offset and OffsetHigh fields.
+	pub const fn Offset(&self) -> u64 {
+		MAKEQWORD(self.Offset, self.OffsetHigh)
+	}
+
+	/// Sets the Offset and OffsetHigh fields.
+	pub fn set_Offset(&mut self, offset: u64) {
+		self.Offset = LODWORD(offset);
+		self.OffsetHigh = HIDWORD(offset);
+	}
+}
+
+/// [`OVERLAPPED_ENTRY`](https://docs.microsoft.com/en-us/windows/win32/api/minwinbase/ns-minwinbase-overlapped_entry)
+/// struct.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[repr(C)]
+pub struct OVERLAPPED_ENTRY {
+	pub lpCompletionKey: usize,
+	pub lpOverlapped: *mut OVERLAPPED,
+	pub Internal: usize,
+	pub dwNumberOfBytesTransferred: u32,
+}
+
+impl_default!(OVERLAPPED_ENTRY);
+
 /// [`PROCESS_INFORMATION`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/ns-processthreadsapi-process_information)
 /// struct.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
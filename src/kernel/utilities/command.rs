@@ -0,0 +1,796 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+
+use crate::co;
+use crate::kernel::decl::{File, GetEnvironmentStrings, GetStdHandle, HFILE,
+	HPIPE, HPROCESS, HTHREAD, Job, STARTUPINFO, WinResult};
+use crate::prelude::{Handle, HandleClose, KernelHfile, KernelHpipe,
+	KernelHprocess, KernelHthread, NativeBitflag};
+
+/// Serializes the creation of inheritable handles, so a process being spawned
+/// doesn't inherit the handles meant to another one.
+static SPAWN_LOCK: Mutex<()> = Mutex::new(());
+
+/// What a child process, spawned by a [`Command`](crate::Command), uses as
+/// its standard input, output or error.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub enum Stdio {
+	/// The child uses the same handle of the current process.
+	Inherit,
+	/// The child reads nothing and its output is discarded, by using the `NUL`
+	/// device.
+	Null,
+	/// A new anonymous pipe connects the child to the current process, which
+	/// gets the other end in [`Child`](crate::Child).
+	Piped,
+	/// The child reads from or writes to the given file.
+	File(File),
+}
+
+/// Result of [`Command::output`](crate::Command::output) and
+/// [`Child::wait_with_output`](crate::Child::wait_with_output).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Output {
+	/// Exit code of the process.
+	pub exit_code: u32,
+	/// Everything the process wrote to its standard output, if it was piped.
+	pub stdout: Vec<u8>,
+	/// Everything the process wrote to its standard error, if it was piped.
+	pub stderr: Vec<u8>,
+}
+
+//------------------------------------------------------------------------------
+
+/// Builds a child process, which is spawned with
+/// [`HPROCESS::CreateProcess`](crate::prelude::KernelHprocess::CreateProcess).
+///
+/// The arguments are quoted according to the rules of
+/// [`CommandLineToArgv`](crate::CommandLineToArgv), which are the same of the
+/// C runtime. Note that programs like `cmd.exe`, and therefore `.bat` and
+/// `.cmd` files, parse their command line differently, so their arguments
+/// must not come from untrusted sources.
+///
+/// # Examples
+///
+/// Capturing the output of a program:
+///
+/// ```rust,no_run
+/// use winsafe::Command;
+///
+/// let output = Command::new("git")
+///     .args(["log", "--oneline", "-5"])
+///     .current_dir("C:\\Projects\\foo")
+///     .output()?;
+///
+/// if output.exit_code == 0 {
+///     println!("{}", String::from_utf8_lossy(&output.stdout));
+/// }
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+///
+/// Writing to the standard input of a child:
+///
+/// ```rust,no_run
+/// use std::io::Write;
+/// use winsafe::{Command, Stdio};
+///
+/// let mut child = Command::new("sort")
+///     .stdin(Stdio::Piped)
+///     .stdout(Stdio::Piped)
+///     .spawn()?;
+///
+/// child.stdin.as_mut().unwrap().write_all(b"b\r\na\r\n").unwrap();
+/// let output = child.wait_with_output()?;
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct Command {
+	program: String,
+	args: Vec<String>,
+	env_clear: bool,
+	env: BTreeMap<String, (String, Option<String>)>, // uppercase name => (name, value); None means removed
+	current_dir: Option<String>,
+	flags: co::CREATE,
+	stdin: Option<Stdio>, // None means not set
+	stdout: Option<Stdio>,
+	stderr: Option<Stdio>,
+}
+
+impl Command {
+	/// Creates a new command to run the given program, which is searched the
+	/// same way of
+	/// [`HPROCESS::CreateProcess`](crate::prelude::KernelHprocess::CreateProcess):
+	/// in the directory of the current executable, in the current directory,
+	/// in the system directories and in the `PATH`. The `.exe` extension can
+	/// be omitted.
+	///
+	/// By default, the child inherits the environment, the current directory
+	/// and the standard handles of the current process.
+	pub fn new(program: &str) -> Command {
+		Self {
+			program: program.to_owned(),
+			args: Vec::default(),
+			env_clear: false,
+			env: BTreeMap::default(),
+			current_dir: None,
+			flags: co::CREATE::NoValue,
+			stdin: None,
+			stdout: None,
+			stderr: None,
+		}
+	}
+
+	/// Adds an argument, which is quoted if needed.
+	pub fn arg(&mut self, arg: &str) -> &mut Command {
+		self.args.push(arg.to_owned());
+		self
+	}
+
+	/// Adds many arguments, which are quoted if needed.
+	pub fn args<I, S>(&mut self, args: I) -> &mut Command
+		where I: IntoIterator<Item = S>,
+			S: AsRef<str>,
+	{
+		self.args.extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+		self
+	}
+
+	/// Sets the creation flags passed to
+	/// [`HPROCESS::CreateProcess`](crate::prelude::KernelHprocess::CreateProcess),
+	/// like [`CREATE::NO_WINDOW`](crate::co::CREATE::NO_WINDOW).
+	pub fn creation_flags(&mut self, flags: co::CREATE) -> &mut Command {
+		self.flags = flags;
+		self
+	}
+
+	/// Sets the current directory of the child.
+	pub fn current_dir(&mut self, dir: &str) -> &mut Command {
+		self.current_dir = Some(dir.to_owned());
+		self
+	}
+
+	/// Sets an environment variable of the child. Names are case-insensitive.
+	pub fn env(&mut self, name: &str, value: &str) -> &mut Command {
+		self.env.insert(name.to_uppercase(),
+			(name.to_owned(), Some(value.to_owned())));
+		self
+	}
+
+	/// Removes all environment variables, so the child gets only the ones set
+	/// afterwards with [`env`](crate::Command::env).
+	pub fn env_clear(&mut self) -> &mut Command {
+		self.env_clear = true;
+		self.env.clear();
+		self
+	}
+
+	/// Removes an environment variable from the child.
+	pub fn env_remove(&mut self, name: &str) -> &mut Command {
+		self.env.insert(name.to_uppercase(), (name.to_owned(), None));
+		self
+	}
+
+	/// Sets many environment variables of the child.
+	pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Command
+		where I: IntoIterator<Item = (K, V)>,
+			K: AsRef<str>,
+			V: AsRef<str>,
+	{
+		vars.into_iter()
+			.for_each(|(name, value)| { self.env(name.as_ref(), value.as_ref()); });
+		self
+	}
+
+	/// Sets the standard error of the child. Default is
+	/// [`Stdio::Inherit`](crate::Stdio::Inherit).
+	pub fn stderr(&mut self, stderr: Stdio) -> &mut Command {
+		self.stderr = Some(stderr);
+		self
+	}
+
+	/// Sets the standard input of the child. Default is
+	/// [`Stdio::Inherit`](crate::Stdio::Inherit).
+	pub fn stdin(&mut self, stdin: Stdio) -> &mut Command {
+		self.stdin = Some(stdin);
+		self
+	}
+
+	/// Sets the standard output of the child. Default is
+	/// [`Stdio::Inherit`](crate::Stdio::Inherit).
+	pub fn stdout(&mut self, stdout: Stdio) -> &mut Command {
+		self.stdout = Some(stdout);
+		self
+	}
+
+	/// Returns the command line which is passed to the child: the program
+	/// followed by the arguments, quoted if needed.
+	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the program name contains a quote, which can't be represented.
+	pub fn command_line(&self) -> WinResult<String> {
+		if self.program.contains('"') {
+			return Err(co::ERROR::INVALID_PARAMETER);
+		}
+
+		let mut cmd_line = if self.program.is_empty()
+			|| self.program.contains([' ', '\t'])
+		{
+			format!("\"{}\"", self.program) // program name is not escaped, just quoted
+		} else {
+			self.program.clone()
+		};
+
+		for arg in self.args.iter() {
+			cmd_line.push(' ');
+			cmd_line.push_str(&Self::quote_arg(arg));
+		}
+		Ok(cmd_line)
+	}
+
+	/// Spawns the child, waits for it to finish, and collects its standard
+	/// output and error.
+	///
+	/// Unless set otherwise, standard input is
+	/// [`Stdio::Null`](crate::Stdio::Null), while standard output and error
+	/// are [`Stdio::Piped`](crate::Stdio::Piped).
+	pub fn output(&mut self) -> WinResult<Output> {
+		let mut child = self.spawn_with(Stdio::Null, Stdio::Piped, Stdio::Piped, self.flags)?;
+		child.stdin = None;
+		child.wait_with_output()
+	}
+
+	/// Spawns the child and waits for it to finish, returning its exit code.
+	pub fn status(&mut self) -> WinResult<u32> {
+		self.spawn()?.wait()
+	}
+
+	/// Spawns the child, returning immediately.
+	///
+	/// If [`CREATE::SUSPENDED`](crate::co::CREATE::SUSPENDED) is set, the
+	/// child runs only after [`Child::resume`](crate::Child::resume).
+	pub fn spawn(&mut self) -> WinResult<Child> {
+		self.spawn_with(Stdio::Inherit, Stdio::Inherit, Stdio::Inherit, self.flags)
+	}
+
+	/// Spawns the child already associated to the job, so all processes it
+	/// creates also belong to the job. Returns immediately.
+	///
+	/// The child is created suspended, assigned to the job, then resumed –
+	/// unless [`CREATE::SUSPENDED`](crate::co::CREATE::SUSPENDED) is set. If
+	/// it can't be assigned or resumed, it's terminated.
+	///
+	/// For an example, see [`Job`](crate::Job).
+	pub fn spawn_in_job(&mut self, job: &Job) -> WinResult<Child> {
+		let child = self.spawn_with(Stdio::Inherit, Stdio::Inherit, Stdio::Inherit,
+			self.flags | co::CREATE::SUSPENDED)?;
+
+		if let Err(e) = job.assign(child.hprocess) {
+			child.hprocess.TerminateProcess(1).ok(); // never ran
+			return Err(e);
+		}
+		if !self.flags.has(co::CREATE::SUSPENDED) {
+			if let Err(e) = child.resume() {
+				child.hprocess.TerminateProcess(1).ok(); // would stay suspended forever
+				return Err(e);
+			}
+		}
+		Ok(child)
+	}
+
+	/// Spawns the child with the given defaults for the standard handles which
+	/// were not set.
+	fn spawn_with(&self,
+		def_stdin: Stdio, def_stdout: Stdio, def_stderr: Stdio,
+		mut flags: co::CREATE) -> WinResult<Child>
+	{
+		let cmd_line = self.command_line()?;
+		let env = self.env_block()?;
+
+		if env.is_some() {
+			flags |= co::CREATE::UNICODE_ENVIRONMENT;
+		}
+
+		let (child_stdin, parent_stdin) = prepare_stdio(
+			self.stdin.as_ref().unwrap_or(&def_stdin), co::STD_HANDLE::INPUT)?;
+		let (child_stdout, parent_stdout) = prepare_stdio(
+			self.stdout.as_ref().unwrap_or(&def_stdout), co::STD_HANDLE::OUTPUT)?;
+		let (child_stderr, parent_stderr) = prepare_stdio(
+			self.stderr.as_ref().unwrap_or(&def_stderr), co::STD_HANDLE::ERROR)?;
+
+		let pi = {
+			let _lock = SPAWN_LOCK.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner());
+
+			let inh_stdin = child_stdin.inheritable()?;
+			let inh_stdout = child_stdout.inheritable()?;
+			let inh_stderr = child_stderr.inheritable()?;
+
+			let mut si = STARTUPINFO::default();
+			si.dwFlags = co::STARTF::USESTDHANDLES;
+			si.hStdInput = HPIPE(inh_stdin.handle.0);
+			si.hStdOutput = HPIPE(inh_stdout.handle.0);
+			si.hStdError = HPIPE(inh_stderr.handle.0);
+
+			HPROCESS::CreateProcess(None, Some(&cmd_line), None, None, true,
+				flags, env, self.current_dir.as_deref(), &mut si)?
+		}; // inheritable duplicates are closed here
+
+		Ok(Child {
+			hprocess: pi.hProcess,
+			hthread: pi.hThread,
+			process_id: pi.dwProcessId,
+			stdin: parent_stdin,
+			stdout: parent_stdout,
+			stderr: parent_stderr,
+		})
+	}
+
+	/// Builds the environment strings for the child, or `None` if it simply
+	/// inherits the current ones.
+	fn env_block(&self) -> WinResult<Option<Vec<String>>> {
+		if !self.env_clear && self.env.is_empty() {
+			return Ok(None);
+		}
+
+		let mut vars = BTreeMap::default(); // sorted case-insensitively, as required
+		if !self.env_clear {
+			for (name, value) in GetEnvironmentStrings()?.into_iter() {
+				vars.insert(name.to_uppercase(), (name, value));
+			}
+		}
+		for (upper_name, (name, value)) in self.env.iter() {
+			match value {
+				Some(value) => vars.insert(upper_name.clone(), (name.clone(), value.clone())),
+				None => vars.remove(upper_name),
+			};
+		}
+
+		Ok(Some(
+			vars.into_values()
+				.map(|(name, value)| format!("{}={}", name, value))
+				.collect(),
+		))
+	}
+
+	/// Quotes an argument so it's parsed back unchanged by
+	/// [`CommandLineToArgv`](crate::CommandLineToArgv) and the C runtime.
+	///
+	/// Arguments without spaces, tabs or quotes are returned unchanged.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use winsafe::Command;
+	///
+	/// assert_eq!(Command::quote_arg("foo"), "foo");
+	/// assert_eq!(Command::quote_arg("foo bar"), "\"foo bar\"");
+	/// assert_eq!(Command::quote_arg("C:\\some dir\\"), "\"C:\\some dir\\\\\"");
+	/// assert_eq!(Command::quote_arg("say \"hi\""), "\"say \\\"hi\\\"\"");
+	/// ```
+	pub fn quote_arg(arg: &str) -> String {
+		if !arg.is_empty()
+			&& !arg.contains([' ', '\t', '\n', '\x0b', '"'])
+		{
+			return arg.to_owned();
+		}
+
+		let mut quoted = String::with_capacity(arg.len() + 2);
+		quoted.push('"');
+		let mut backslashes = 0;
+		for ch in arg.chars() {
+			match ch {
+				'\\' => backslashes += 1,
+				'"' => {
+					quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1)); // escape them all, and the quote
+					quoted.push('"');
+					backslashes = 0;
+				},
+				ch => {
+					quoted.extend(std::iter::repeat_n('\\', backslashes)); // literal, not followed by a quote
+					quoted.push(ch);
+					backslashes = 0;
+				},
+			}
+		}
+		quoted.extend(std::iter::repeat_n('\\', backslashes * 2)); // they'd escape the closing quote
+		quoted.push('"');
+		quoted
+	}
+
+	/// Splits a command line into the program name and the arguments, with the
+	/// same rules of [`CommandLineToArgv`](crate::CommandLineToArgv), but
+	/// without calling it, so it's also available without the `shell`
+	/// feature.
+	///
+	/// Unlike `CommandLineToArgv`, an empty command line returns an empty
+	/// vector, instead of the current executable path.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use winsafe::Command;
+	///
+	/// assert_eq!(
+	///     Command::split_command_line("\"C:\\Program Files\\a.exe\" x \"y z\""),
+	///     ["C:\\Program Files\\a.exe", "x", "y z"],
+	/// );
+	/// ```
+	pub fn split_command_line(cmd_line: &str) -> Vec<String> {
+		let mut args = Vec::default();
+		let mut chars = cmd_line.chars().peekable();
+		if chars.peek().is_none() {
+			return args;
+		}
+
+		// The program name has no escapes: it's either everything up to the
+		// next quote, or up to the next whitespace.
+		let mut program = String::default();
+		if chars.next_if_eq(&'"').is_some() {
+			for ch in chars.by_ref() {
+				if ch == '"' { break; }
+				program.push(ch);
+			}
+		} else {
+			while let Some(ch) = chars.next_if(|ch| *ch != ' ' && *ch != '\t') {
+				program.push(ch);
+			}
+		}
+		args.push(program);
+
+		loop {
+			while chars.next_if(|ch| *ch == ' ' || *ch == '\t').is_some() {}
+			if chars.peek().is_none() {
+				return args;
+			}
+
+			let mut arg = String::default();
+			let mut quotes = 0; // 1 if inside quotes
+			let mut backslashes = 0;
+			while let Some(ch) = chars.next() {
+				match ch {
+					' ' | '\t' if quotes == 0 => break,
+					'\\' => {
+						arg.push('\\');
+						backslashes += 1;
+						continue;
+					},
+					'"' => {
+						arg.truncate(arg.len() - backslashes / 2 - backslashes % 2);
+						if backslashes % 2 == 0 {
+							quotes += 1; // opens or closes quotes
+						} else {
+							arg.push('"'); // escaped quote
+						}
+
+						// Each third consecutive quote, counting the one which
+						// opened the quotes, is a literal quote.
+						while chars.next_if_eq(&'"').is_some() {
+							quotes += 1;
+							if quotes == 3 {
+								arg.push('"');
+								quotes = 0;
+							}
+						}
+						if quotes == 2 {
+							quotes = 0;
+						}
+					},
+					ch => arg.push(ch),
+				}
+				backslashes = 0;
+			}
+			args.push(arg);
+		}
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// A child process spawned by a [`Command`](crate::Command), whose
+/// [`HPROCESS`](crate::HPROCESS) and [`HTHREAD`](crate::HTHREAD) handles are
+/// closed automatically when the object goes out of scope.
+///
+/// Dropping the object doesn't wait for the process, nor terminates it.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct Child {
+	hprocess: HPROCESS,
+	hthread: HTHREAD,
+	process_id: u32,
+	/// Writes to the standard input of the child, if it was
+	/// [`Stdio::Piped`](crate::Stdio::Piped). Set it to `None` to close it, so
+	/// the child reaches the end of its input.
+	pub stdin: Option<ChildPipe>,
+	/// Reads from the standard output of the child, if it was
+	/// [`Stdio::Piped`](crate::Stdio::Piped).
+	pub stdout: Option<ChildPipe>,
+	/// Reads from the standard error of the child, if it was
+	/// [`Stdio::Piped`](crate::Stdio::Piped).
+	pub stderr: Option<ChildPipe>,
+}
+
+impl Drop for Child {
+	fn drop(&mut self) {
+		self.hthread.CloseHandle().ok(); // ignore errors
+		self.hprocess.CloseHandle().ok();
+	}
+}
+
+impl Child {
+	/// Returns the exit code of the child, or `None` if it's still running.
+	pub fn exit_code(&self) -> WinResult<Option<u32>> {
+		self.wait_timeout(0)
+	}
+
+	/// Returns the underlying process handle.
+	pub const fn hprocess(&self) -> HPROCESS {
+		self.hprocess
+	}
+
+	/// Returns the underlying handle of the main thread of the child.
+	pub const fn hthread(&self) -> HTHREAD {
+		self.hthread
+	}
+
+	/// Returns the process ID of the child.
+	pub const fn id(&self) -> u32 {
+		self.process_id
+	}
+
+	/// Terminates the child with
+	/// [`HPROCESS::TerminateProcess`](crate::prelude::KernelHprocess::TerminateProcess),
+	/// using 1 as its exit code. Does nothing if the child already finished.
+	pub fn kill(&self) -> WinResult<()> {
+		match self.hprocess.TerminateProcess(1) {
+			Err(co::ERROR::ACCESS_DENIED) if self.exit_code()?.is_some() => Ok(()),
+			res => res,
+		}
+	}
+
+	/// Resumes the main thread of the child, if it was created with
+	/// [`CREATE::SUSPENDED`](crate::co::CREATE::SUSPENDED).
+	pub fn resume(&self) -> WinResult<()> {
+		self.hthread.ResumeThread().map(|_| ())
+	}
+
+	/// Closes the standard input, if piped, then waits until the child
+	/// finishes, returning its exit code.
+	pub fn wait(&mut self) -> WinResult<u32> {
+		self.stdin = None;
+		self.hprocess.WaitForSingleObject(None)?;
+		self.hprocess.GetExitCodeProcess()
+	}
+
+	/// Waits until the child finishes, or the timeout expires. Returns the
+	/// exit code, or `None` if the child is still running.
+	///
+	/// Unlike [`wait`](crate::Child::wait), doesn't close the standard input.
+	pub fn wait_timeout(&self, milliseconds: u32) -> WinResult<Option<u32>> {
+		match self.hprocess.WaitForSingleObject(Some(milliseconds))? {
+			co::WAIT::TIMEOUT => Ok(None),
+			_ => self.hprocess.GetExitCodeProcess().map(Some),
+		}
+	}
+
+	/// Closes the standard input, if piped, then reads the whole standard
+	/// output and error, if piped, until the child finishes.
+	///
+	/// Both are read at the same time – the standard error in another thread –
+	/// so the child never blocks because one of the pipes is full.
+	pub fn wait_with_output(mut self) -> WinResult<Output> {
+		self.stdin = None;
+
+		let (stdout, stderr) = match (self.stdout.take(), self.stderr.take()) {
+			(None, None) => (Vec::default(), Vec::default()),
+			(Some(out), None) => (out.read_all()?, Vec::default()),
+			(None, Some(err)) => (Vec::default(), err.read_all()?),
+			(Some(out), Some(err)) => {
+				let err_thread = std::thread::spawn(move || err.read_all());
+				let stdout = out.read_all();
+				let stderr = err_thread.join()
+					.unwrap_or(Err(co::ERROR::OPERATION_ABORTED));
+				(stdout?, stderr?)
+			},
+		};
+
+		Ok(Output { exit_code: self.wait()?, stdout, stderr })
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// The end of an anonymous pipe connected to a standard handle of a
+/// [`Child`](crate::Child), whose [`HPIPE`](crate::HPIPE) handle is closed
+/// automatically when the object goes out of scope.
+///
+/// Implements the standard
+/// [`Read`](https://doc.rust-lang.org/std/io/trait.Read.html) and
+/// [`Write`](https://doc.rust-lang.org/std/io/trait.Write.html) traits, also
+/// for `&ChildPipe`. Reading returns zero bytes when the child closes the
+/// pipe.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct ChildPipe {
+	hpipe: HPIPE,
+}
+
+impl Drop for ChildPipe {
+	fn drop(&mut self) {
+		self.hpipe.CloseHandle().ok(); // ignore errors
+	}
+}
+
+impl io::Read for ChildPipe {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		io::Read::read(&mut &*self, buf)
+	}
+}
+
+impl io::Read for &ChildPipe {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let len = buf.len().min(u32::MAX as _);
+		match self.hpipe.ReadFile(&mut buf[..len], None) {
+			Ok(bytes_read) => Ok(bytes_read as _),
+			Err(co::ERROR::BROKEN_PIPE) => Ok(0), // the child closed the pipe
+			Err(e) => Err(e.into()),
+		}
+	}
+}
+
+impl io::Write for ChildPipe {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		io::Write::write(&mut &*self, buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		io::Write::flush(&mut &*self)
+	}
+}
+
+impl io::Write for &ChildPipe {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = buf.len().min(u32::MAX as _);
+		self.hpipe.WriteFile(&buf[..len], None)
+			.map(|bytes_written| bytes_written as _)
+			.map_err(|e| e.into())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(()) // writes are not buffered by us
+	}
+}
+
+impl ChildPipe {
+	/// Returns the underlying pipe handle.
+	pub const fn hpipe(&self) -> HPIPE {
+		self.hpipe
+	}
+
+	/// Reads everything until the child closes the pipe.
+	pub fn read_all(&self) -> WinResult<Vec<u8>> {
+		let mut data = Vec::default();
+		let mut chunk = vec![0; 4096];
+		loop {
+			match self.hpipe.ReadFile(&mut chunk, None) {
+				Ok(0) | Err(co::ERROR::BROKEN_PIPE) => return Ok(data),
+				Ok(bytes_read) => data.extend_from_slice(&chunk[..bytes_read as usize]),
+				Err(e) => return Err(e),
+			}
+		}
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// A handle to be passed to the child, closed when dropped if owned.
+struct ChildHandle {
+	handle: HFILE,
+	owned: bool,
+}
+
+impl Drop for ChildHandle {
+	fn drop(&mut self) {
+		if self.owned && !self.handle.is_null() {
+			self.handle.CloseHandle().ok(); // ignore errors
+		}
+	}
+}
+
+impl ChildHandle {
+	/// Returns an inheritable duplicate of the handle. Must be called with
+	/// the spawn lock held.
+	fn inheritable(&self) -> WinResult<ChildHandle> {
+		if self.handle.is_null() {
+			return Ok(Self { handle: HFILE::NULL, owned: false });
+		}
+		let hproc = HPROCESS::GetCurrentProcess();
+		hproc.DuplicateHandle(self.handle, hproc, 0, true, co::DUPLICATE::SAME_ACCESS)
+			.map(|handle| Self { handle, owned: true })
+	}
+}
+
+/// Returns the handle to be given to the child, and the other end of the pipe,
+/// if any.
+fn prepare_stdio(
+	stdio: &Stdio, std_handle: co::STD_HANDLE,
+) -> WinResult<(ChildHandle, Option<ChildPipe>)>
+{
+	let is_input = std_handle == co::STD_HANDLE::INPUT;
+	Ok(match stdio {
+		Stdio::Inherit => (
+			ChildHandle { handle: GetStdHandle(std_handle)?, owned: false },
+			None,
+		),
+		Stdio::Null => {
+			let (hfile, _) = HFILE::CreateFile("\\\\.\\NUL",
+				if is_input { co::GENERIC::READ } else { co::GENERIC::WRITE },
+				co::FILE_SHARE::READ | co::FILE_SHARE::WRITE, None,
+				co::DISPOSITION::OPEN_EXISTING, co::FILE_ATTRIBUTE::NORMAL, None)?;
+			(ChildHandle { handle: hfile, owned: true }, None)
+		},
+		Stdio::Piped => {
+			let (hread, hwrite) = HPIPE::CreatePipe(None, 0)?;
+			let (child_end, parent_end) = if is_input { (hread, hwrite) } else { (hwrite, hread) };
+			(
+				ChildHandle { handle: HFILE(child_end.0), owned: true },
+				Some(ChildPipe { hpipe: parent_end }),
+			)
+		},
+		Stdio::File(file) => (
+			ChildHandle { handle: file.hfile(), owned: false },
+			None,
+		),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn round_trip(args: &[&str]) {
+		let cmd_line = std::iter::once("prog.exe".to_owned())
+			.chain(args.iter().map(|arg| Command::quote_arg(arg)))
+			.collect::<Vec<_>>()
+			.join(" ");
+		let split = Command::split_command_line(&cmd_line);
+		assert_eq!(split[0], "prog.exe");
+		assert_eq!(&split[1..], args, "{}", cmd_line);
+	}
+
+	#[test]
+	fn quote_round_trip() {
+		round_trip(&["plain", "with space", "with\ttab", "a\nb"]);
+		round_trip(&["say \"hi\"", "\"", "\"\"", "\"\"\"", "a\"b c"]);
+		round_trip(&["C:\\dir\\", "C:\\some dir\\", "\\\\", "a b\\\\", "\\"]);
+		round_trip(&["a\\\"b", "a\\\\\"b", "\\\"", "x \\\" y"]);
+		round_trip(&["", "x", "", " ", "\t"]);
+		round_trip(&["ação", "🦀 crab"]);
+	}
+
+	#[test]
+	fn quote_unchanged() {
+		assert_eq!(Command::quote_arg("C:\\dir\\"), "C:\\dir\\");
+		assert_eq!(Command::quote_arg("a\\b"), "a\\b");
+		assert_eq!(Command::quote_arg(""), "\"\"");
+	}
+
+	#[test]
+	fn split_rules() {
+		let cases: &[(&str, &[&str])] = &[
+			("", &[]),
+			("p", &["p"]),
+			("\"C:\\a b\\p.exe\"x y", &["C:\\a b\\p.exe", "x", "y"]),
+			("C:\\a\\p.exe  \t a", &["C:\\a\\p.exe", "a"]),
+			("p a\\\\b", &["p", "a\\\\b"]), // backslashes not before a quote are literal
+			("p a\\\\\\\"b", &["p", "a\\\"b"]), // 2n+1 backslashes: n, and a literal quote
+			("p \"a\\\\\" b", &["p", "a\\", "b"]), // 2n backslashes: n, and a closing quote
+			("p \"\" x", &["p", "", "x"]),
+			("p \"a b", &["p", "a b"]), // unterminated quotes
+			("p a\"b c\"d", &["p", "ab cd"]),
+		];
+		for (cmd_line, expected) in cases {
+			assert_eq!(Command::split_command_line(cmd_line), *expected, "{}", cmd_line);
+		}
+	}
+}
@@ -0,0 +1,617 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::co;
+use crate::kernel::decl::{HFILE, HIOCP, OVERLAPPED, OVERLAPPED_ENTRY,
+	WinResult};
+use crate::prelude::{Handle, HandleClose, KernelHfile, KernelHiocp};
+
+/// A finished asynchronous operation, returned by
+/// [`CompletionPort::wait`](crate::CompletionPort::wait) or by an
+/// [`IoFuture`](crate::IoFuture).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Debug)]
+pub struct IoCompletion {
+	/// Identifier of the operation, returned when it was started.
+	pub id: u64,
+	/// Completion key given when the handle was associated to the port.
+	pub key: usize,
+	/// Number of bytes read or written.
+	pub bytes_transferred: usize,
+	/// The buffer given when the operation was started, now returned to the
+	/// caller. For reads, it's truncated to the bytes actually read.
+	pub buffer: Vec<u8>,
+	/// Result of the operation. Reading past the end of a file fails with
+	/// [`ERROR::HANDLE_EOF`](crate::co::ERROR::HANDLE_EOF), reading a pipe
+	/// whose other end was closed fails with
+	/// [`ERROR::BROKEN_PIPE`](crate::co::ERROR::BROKEN_PIPE), and a canceled
+	/// operation fails with
+	/// [`ERROR::OPERATION_ABORTED`](crate::co::ERROR::OPERATION_ABORTED).
+	pub result: WinResult<()>,
+}
+
+/// Manages an [`HIOCP`](crate::HIOCP) handle, running asynchronous reads and
+/// writes on files and pipes. The handle is closed automatically when the
+/// object goes out of scope.
+///
+/// The port owns the buffer of each operation until the operation completes,
+/// when the buffer is given back in an
+/// [`IoCompletion`](crate::IoCompletion), so the memory is never freed while
+/// the system is still writing to it. When the port is dropped, any pending
+/// operation is canceled, and the port waits for them to finish.
+///
+/// Completions are retrieved either by a blocking loop, with
+/// [`wait`](crate::CompletionPort::wait) or
+/// [`run`](crate::CompletionPort::run), or by awaiting the
+/// [`IoFuture`](crate::IoFuture) returned by
+/// [`read_async`](crate::CompletionPort::read_async) and
+/// [`write_async`](crate::CompletionPort::write_async). The futures don't poll
+/// the port themselves: some thread must be running the loop, which delivers
+/// their completions and wakes them.
+///
+/// The port can be shared among threads with an
+/// [`Arc`](https://doc.rust-lang.org/std/sync/struct.Arc.html).
+///
+/// # Examples
+///
+/// Reading several pipes with a single thread:
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::{CompletionPort, HPIPE};
+///
+/// let pipes: Vec<HPIPE> = vec![]; // opened with FILE_ATTRIBUTE::FLAG_OVERLAPPED
+///
+/// let port = CompletionPort::new(None)?;
+/// for (idx, hpipe) in pipes.iter().enumerate() {
+///     port.associate(*hpipe, idx)?;
+///     port.read(*hpipe, 0, vec![0; 4096])?;
+/// }
+///
+/// while let Some(done) = port.wait(None)? {
+///     match done.result {
+///         Ok(()) => {
+///             println!("Pipe {}: {} bytes", done.key, done.bytes_transferred);
+///             port.read(pipes[done.key], 0, done.buffer)?; // read again
+///         },
+///         Err(e) => println!("Pipe {} closed: {}", done.key, e),
+///     }
+/// }
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct CompletionPort {
+	hiocp: HIOCP,
+	state: Mutex<PortState>,
+}
+
+// HIOCP is not Sync only because it wraps a raw pointer, but a completion port
+// is meant to be used by many threads at once; everything else is behind the
+// mutex, which is Sync by itself.
+unsafe impl Sync for CompletionPort {}
+
+impl Drop for CompletionPort {
+	fn drop(&mut self) {
+		let state = self.state.get_mut().unwrap();
+		for op in state.pending.values_mut() {
+			op.hfile.CancelIoEx(Some(&mut op.overlapped)).ok(); // may have already finished
+		}
+
+		// The system still owns the buffers until each completion is dequeued.
+		while !self.state.get_mut().unwrap().pending.is_empty() {
+			let mut entry = OVERLAPPED_ENTRY::default();
+			let res = self.hiocp.GetQueuedCompletionStatus(&mut entry, None);
+			if entry.lpOverlapped.is_null() && res.is_err() {
+				break; // the port itself failed, nothing else can be done
+			}
+			self.state.get_mut().unwrap().pending.remove(&(entry.lpOverlapped as usize));
+		}
+
+		self.hiocp.CloseHandle().ok(); // ignore errors
+	}
+}
+
+impl CompletionPort {
+	/// Creates a new port by calling
+	/// [`HIOCP::CreateIoCompletionPort`](crate::prelude::KernelHiocp::CreateIoCompletionPort).
+	///
+	/// If `num_concurrent_threads` is `None`, the system allows as many
+	/// threads as processors to process completions concurrently.
+	pub fn new(num_concurrent_threads: Option<u32>) -> WinResult<CompletionPort> {
+		Ok(Self {
+			hiocp: HIOCP::CreateIoCompletionPort(num_concurrent_threads)?,
+			state: Mutex::new(PortState::default()),
+		})
+	}
+
+	/// Associates a file or pipe handle to the port, so operations can be
+	/// started on it. The handle must have been opened with
+	/// [`FILE_ATTRIBUTE::FLAG_OVERLAPPED`](crate::co::FILE_ATTRIBUTE::FLAG_OVERLAPPED).
+	///
+	/// The completion key is returned in each
+	/// [`IoCompletion`](crate::IoCompletion) of the handle. A handle can be
+	/// associated to only one port, and must be kept open while there are
+	/// pending operations.
+	pub fn associate(&self, handle: impl Handle, key: usize) -> WinResult<()> {
+		let ptr = unsafe { handle.as_ptr() } as usize;
+		self.hiocp.AssociateHandle(handle, key)?;
+		self.state.lock().unwrap().associated.insert(ptr);
+		Ok(())
+	}
+
+	/// Requests the cancellation of a pending operation, by calling
+	/// [`HFILE::CancelIoEx`](crate::prelude::KernelHfile::CancelIoEx).
+	///
+	/// The operation will still be completed, usually with
+	/// [`ERROR::OPERATION_ABORTED`](crate::co::ERROR::OPERATION_ABORTED). Fails
+	/// with [`ERROR::NOT_FOUND`](crate::co::ERROR::NOT_FOUND) if the operation
+	/// already finished.
+	pub fn cancel(&self, id: u64) -> WinResult<()> {
+		let mut state = self.state.lock().unwrap();
+		match state.pending.values_mut().find(|op| op.id == id) {
+			Some(op) => op.hfile.CancelIoEx(Some(&mut op.overlapped)),
+			None => Err(co::ERROR::NOT_FOUND),
+		}
+	}
+
+	/// Returns the underlying port handle.
+	pub const fn hiocp(&self) -> HIOCP {
+		self.hiocp
+	}
+
+	/// Returns the number of operations which were started, but whose
+	/// completion was not dequeued yet.
+	pub fn num_pending(&self) -> usize {
+		self.state.lock().unwrap().pending.len()
+	}
+
+	/// Starts reading from the handle into the buffer, returning the
+	/// identifier of the operation. The whole buffer length is requested.
+	///
+	/// For files, `offset` is the position where the reading starts; for pipes
+	/// it's ignored.
+	pub fn read(&self,
+		handle: impl Handle, offset: u64, buffer: Vec<u8>) -> WinResult<u64>
+	{
+		self.start(handle, offset, buffer, IoKind::Read, None)
+	}
+
+	/// Starts reading from the handle into the buffer, returning a future
+	/// which resolves when the operation completes. See
+	/// [`read`](crate::CompletionPort::read).
+	pub fn read_async(&self,
+		handle: impl Handle, offset: u64, buffer: Vec<u8>) -> WinResult<IoFuture>
+	{
+		let slot = Arc::new(Mutex::new(FutureSlot::default()));
+		let id = self.start(handle, offset, buffer, IoKind::Read, Some(slot.clone()))?;
+		Ok(IoFuture { id, slot })
+	}
+
+	/// Dequeues completions and returns each one to the callback, until the
+	/// callback returns `false` or the port is woken up by
+	/// [`wake`](crate::CompletionPort::wake).
+	///
+	/// This is the loop a thread runs to drive the
+	/// [`IoFuture`](crate::IoFuture) objects; if only futures are used, the
+	/// callback is never called.
+	pub fn run<F>(&self, mut on_completion: F) -> WinResult<()>
+		where F: FnMut(IoCompletion) -> bool,
+	{
+		while let Some(completion) = self.wait(None)? {
+			if !on_completion(completion) {
+				break;
+			}
+		}
+		Ok(())
+	}
+
+	/// Blocks until an operation completes, returning its
+	/// [`IoCompletion`](crate::IoCompletion).
+	///
+	/// Completions of operations started with
+	/// [`read_async`](crate::CompletionPort::read_async) or
+	/// [`write_async`](crate::CompletionPort::write_async) are delivered to
+	/// their futures, and the waiting goes on.
+	///
+	/// Returns `None` if the timeout expires, or if the port is woken up by
+	/// [`wake`](crate::CompletionPort::wake).
+	pub fn wait(&self, milliseconds: Option<u32>) -> WinResult<Option<IoCompletion>> {
+		let deadline = milliseconds
+			.map(|ms| Instant::now() + Duration::from_millis(ms as _));
+
+		loop {
+			let remaining = deadline.map(|deadline|
+				deadline.saturating_duration_since(Instant::now())
+					.as_millis()
+					.min(u32::MAX as u128 - 1) as u32); // u32::MAX is INFINITE
+
+			let mut entry = OVERLAPPED_ENTRY::default();
+			let res = self.hiocp.GetQueuedCompletionStatus(&mut entry, remaining);
+			if entry.lpOverlapped.is_null() {
+				return match res {
+					Ok(_) => Ok(None), // posted by wake()
+					Err(co::ERROR::WAIT_TIMEOUT) => Ok(None),
+					Err(e) => Err(e),
+				};
+			}
+
+			if let Some(completion) = self.complete(&entry, res) {
+				return Ok(Some(completion));
+			}
+		}
+	}
+
+	/// Wakes up one thread blocked in [`wait`](crate::CompletionPort::wait)
+	/// or [`run`](crate::CompletionPort::run), which then returns.
+	pub fn wake(&self) -> WinResult<()> {
+		self.hiocp.PostQueuedCompletionStatus(0, 0, None)
+	}
+
+	/// Starts writing the buffer to the handle, returning the identifier of
+	/// the operation.
+	///
+	/// For files, `offset` is the position where the writing starts, and
+	/// `u64::MAX` means the end of the file; for pipes it's ignored.
+	pub fn write(&self,
+		handle: impl Handle, offset: u64, buffer: Vec<u8>) -> WinResult<u64>
+	{
+		self.start(handle, offset, buffer, IoKind::Write, None)
+	}
+
+	/// Starts writing the buffer to the handle, returning a future which
+	/// resolves when the operation completes. See
+	/// [`write`](crate::CompletionPort::write).
+	pub fn write_async(&self,
+		handle: impl Handle, offset: u64, buffer: Vec<u8>) -> WinResult<IoFuture>
+	{
+		let slot = Arc::new(Mutex::new(FutureSlot::default()));
+		let id = self.start(handle, offset, buffer, IoKind::Write, Some(slot.clone()))?;
+		Ok(IoFuture { id, slot })
+	}
+
+	/// Takes the finished operation out of the pending ones. If it belongs to a
+	/// future, the completion is delivered to it, and `None` is returned.
+	///
+	/// The `result` is the one of the dequeuing, which is the result of the
+	/// operation itself, so the handle is not touched – it may be closed by
+	/// now.
+	fn complete(&self,
+		entry: &OVERLAPPED_ENTRY, result: WinResult<()>) -> Option<IoCompletion>
+	{
+		let (completion, future) = self.state.lock().unwrap()
+			.finish(entry, result)?; // not started by us
+
+		match future {
+			None => Some(completion),
+			Some(slot) => {
+				FutureSlot::deliver(&slot, completion); // port is unlocked, the waker may use it
+				None
+			},
+		}
+	}
+
+	fn start(&self,
+		handle: impl Handle,
+		offset: u64,
+		buffer: Vec<u8>,
+		kind: IoKind,
+		future: Option<Arc<Mutex<FutureSlot>>>) -> WinResult<u64>
+	{
+		let hfile = HFILE(unsafe { handle.as_ptr() });
+		let mut state = self.state.lock().unwrap();
+		let mut op = state.new_op(hfile, offset, buffer, kind, future)?;
+
+		let len = op.buffer.len().min(u32::MAX as _);
+		let res = match kind {
+			IoKind::Read => {
+				let IoOp { overlapped, buffer, .. } = &mut *op;
+				hfile.ReadFile(&mut buffer[..len], Some(overlapped))
+			},
+			IoKind::Write => {
+				let IoOp { overlapped, buffer, .. } = &mut *op;
+				hfile.WriteFile(&buffer[..len], Some(overlapped))
+			},
+		};
+
+		match res {
+			Ok(_) | Err(co::ERROR::IO_PENDING) => { // a completion will be queued either way
+				let id = op.id;
+				state.pending.insert(op.overlapped_addr(), op);
+				Ok(id)
+			},
+			Err(e) => Err(e), // nothing was queued, the buffer is dropped
+		}
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// Future returned by
+/// [`CompletionPort::read_async`](crate::CompletionPort::read_async) and
+/// [`CompletionPort::write_async`](crate::CompletionPort::write_async), which
+/// resolves to the [`IoCompletion`](crate::IoCompletion) of the operation.
+///
+/// The future is woken by the thread running
+/// [`CompletionPort::wait`](crate::CompletionPort::wait) or
+/// [`CompletionPort::run`](crate::CompletionPort::run). Dropping the future
+/// doesn't cancel the operation; for that, call
+/// [`CompletionPort::cancel`](crate::CompletionPort::cancel) with its
+/// [`id`](crate::IoFuture::id).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct IoFuture {
+	id: u64,
+	slot: Arc<Mutex<FutureSlot>>,
+}
+
+impl Future for IoFuture {
+	type Output = IoCompletion;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let mut slot = self.slot.lock().unwrap();
+		match slot.completion.take() {
+			Some(completion) => Poll::Ready(completion),
+			None => {
+				slot.waker = Some(cx.waker().clone());
+				Poll::Pending
+			},
+		}
+	}
+}
+
+impl IoFuture {
+	/// Returns the identifier of the operation.
+	pub const fn id(&self) -> u64 {
+		self.id
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// Bookkeeping of the operations, which doesn't touch the port itself.
+struct PortState {
+	next_id: u64,
+	associated: HashSet<usize>, // handles which can be used
+	pending: HashMap<usize, Box<IoOp>>, // keyed by the OVERLAPPED address
+}
+
+impl Default for PortState {
+	fn default() -> Self {
+		Self {
+			next_id: 1,
+			associated: HashSet::default(),
+			pending: HashMap::default(),
+		}
+	}
+}
+
+impl PortState {
+	/// Creates a new operation with the next identifier, if the handle was
+	/// associated to the port. The operation is not pending yet.
+	fn new_op(&mut self,
+		hfile: HFILE,
+		offset: u64,
+		buffer: Vec<u8>,
+		kind: IoKind,
+		future: Option<Arc<Mutex<FutureSlot>>>) -> WinResult<Box<IoOp>>
+	{
+		if !self.associated.contains(&(hfile.0 as usize)) {
+			return Err(co::ERROR::INVALID_HANDLE); // its completion would never be dequeued
+		}
+
+		let id = self.next_id;
+		self.next_id += 1;
+
+		let mut op = Box::new(IoOp {
+			overlapped: OVERLAPPED::default(),
+			id,
+			hfile,
+			kind,
+			buffer,
+			future,
+		});
+		op.overlapped.set_Offset(offset);
+		Ok(op)
+	}
+
+	/// Takes the dequeued operation out of the pending ones, returning its
+	/// completion and its future, if any. Returns `None` if the operation was
+	/// not started by the port.
+	fn finish(&mut self,
+		entry: &OVERLAPPED_ENTRY,
+		result: WinResult<()>) -> Option<(IoCompletion, Option<Arc<Mutex<FutureSlot>>>)>
+	{
+		let mut op = self.pending.remove(&(entry.lpOverlapped as usize))?;
+
+		let bytes_transferred = entry.dwNumberOfBytesTransferred as usize;
+		if op.kind == IoKind::Read {
+			op.buffer.truncate(bytes_transferred);
+		}
+		let completion = IoCompletion {
+			id: op.id,
+			key: entry.lpCompletionKey,
+			bytes_transferred,
+			buffer: std::mem::take(&mut op.buffer),
+			result,
+		};
+		Some((completion, op.future.take()))
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IoKind { Read, Write }
+
+/// An operation started on the port. Boxed, so the `OVERLAPPED` and the buffer
+/// don't move while the system uses them.
+#[repr(C)]
+struct IoOp {
+	overlapped: OVERLAPPED,
+	id: u64,
+	hfile: HFILE,
+	kind: IoKind,
+	buffer: Vec<u8>,
+	future: Option<Arc<Mutex<FutureSlot>>>,
+}
+
+impl IoOp {
+	/// The address the system returns in
+	/// [`OVERLAPPED_ENTRY`](crate::OVERLAPPED_ENTRY) when the operation
+	/// completes.
+	fn overlapped_addr(&self) -> usize {
+		&self.overlapped as *const _ as usize
+	}
+}
+
+#[derive(Default)]
+struct FutureSlot {
+	completion: Option<IoCompletion>,
+	waker: Option<Waker>,
+}
+
+impl FutureSlot {
+	/// Stores the completion and wakes the future, if it was already polled.
+	fn deliver(slot: &Mutex<FutureSlot>, completion: IoCompletion) {
+		let mut slot = slot.lock().unwrap();
+		slot.completion = Some(completion);
+		if let Some(waker) = slot.waker.take() {
+			waker.wake();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::task::Wake;
+	use super::*;
+
+	struct CountingWaker(AtomicUsize);
+
+	impl Wake for CountingWaker {
+		fn wake(self: Arc<Self>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	fn fake_hfile() -> HFILE {
+		HFILE(0x1000 as _)
+	}
+
+	fn associated_state() -> PortState {
+		let mut state = PortState::default();
+		state.associated.insert(fake_hfile().0 as usize);
+		state
+	}
+
+	/// Makes the operation pending, then simulates its dequeuing.
+	fn dequeue(state: &mut PortState, op: Box<IoOp>,
+		bytes_transferred: u32,
+		result: WinResult<()>) -> Option<(IoCompletion, Option<Arc<Mutex<FutureSlot>>>)>
+	{
+		let addr = op.overlapped_addr();
+		state.pending.insert(addr, op);
+
+		let entry = OVERLAPPED_ENTRY {
+			lpCompletionKey: 42,
+			lpOverlapped: addr as _,
+			dwNumberOfBytesTransferred: bytes_transferred,
+			..Default::default()
+		};
+		state.finish(&entry, result)
+	}
+
+	#[test]
+	fn new_op_checks_handle() {
+		let mut state = associated_state();
+		assert_eq!(state.new_op(HFILE(0x2000 as _), 0, vec![], IoKind::Read, None).err(),
+			Some(co::ERROR::INVALID_HANDLE));
+
+		let op1 = state.new_op(fake_hfile(), 0x1_0000_0002, vec![], IoKind::Read, None).unwrap();
+		let op2 = state.new_op(fake_hfile(), 0, vec![], IoKind::Write, None).unwrap();
+		assert_eq!((op1.id, op2.id), (1, 2));
+		assert_eq!(op1.overlapped.Offset(), 0x1_0000_0002);
+		assert!(state.pending.is_empty()); // not started yet
+	}
+
+	#[test]
+	fn finish_truncates_reads() {
+		let mut state = associated_state();
+
+		let op = state.new_op(fake_hfile(), 0, vec![0xaa; 16], IoKind::Read, None).unwrap();
+		let (done, future) = dequeue(&mut state, op, 5, Ok(())).unwrap();
+		assert!(future.is_none());
+		assert_eq!((done.id, done.key, done.bytes_transferred), (1, 42, 5));
+		assert_eq!(done.buffer, [0xaa; 5]);
+		assert_eq!(done.result, Ok(()));
+
+		let op = state.new_op(fake_hfile(), 0, vec![0xbb; 16], IoKind::Write, None).unwrap();
+		let (done, _) = dequeue(&mut state, op, 5, Ok(())).unwrap();
+		assert_eq!((done.bytes_transferred, done.buffer.len()), (5, 16)); // writes keep the buffer
+
+		let op = state.new_op(fake_hfile(), 0, vec![0xcc; 16], IoKind::Read, None).unwrap();
+		let (done, _) = dequeue(&mut state, op, 0, Err(co::ERROR::HANDLE_EOF)).unwrap();
+		assert!(done.buffer.is_empty());
+		assert_eq!(done.result, Err(co::ERROR::HANDLE_EOF));
+
+		assert!(state.pending.is_empty());
+	}
+
+	#[test]
+	fn finish_ignores_unknown() {
+		let mut state = associated_state();
+		let op = state.new_op(fake_hfile(), 0, vec![0; 4], IoKind::Read, None).unwrap();
+		state.pending.insert(op.overlapped_addr(), op);
+
+		let entry = OVERLAPPED_ENTRY {
+			lpOverlapped: 0x3000 as _, // posted by someone else
+			..Default::default()
+		};
+		assert!(state.finish(&entry, Ok(())).is_none());
+		assert_eq!(state.pending.len(), 1);
+	}
+
+	#[test]
+	fn future_woken_on_completion() {
+		let mut state = associated_state();
+		let slot = Arc::new(Mutex::new(FutureSlot::default()));
+		let op = state.new_op(fake_hfile(), 0, vec![0; 8], IoKind::Read, Some(slot.clone())).unwrap();
+		let mut fut = IoFuture { id: op.id, slot };
+
+		let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+		let waker = Waker::from(counter.clone());
+		let mut cx = Context::from_waker(&waker);
+		assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+		assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+		let (done, future) = dequeue(&mut state, op, 3, Ok(())).unwrap();
+		FutureSlot::deliver(&future.unwrap(), done);
+		assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+		match Pin::new(&mut fut).poll(&mut cx) {
+			Poll::Ready(done) => assert_eq!((done.id, done.buffer.len()), (fut.id(), 3)),
+			Poll::Pending => panic!("completion not delivered"),
+		}
+	}
+
+	#[test]
+	fn future_completed_before_poll() {
+		let mut state = associated_state();
+		let slot = Arc::new(Mutex::new(FutureSlot::default()));
+		let op = state.new_op(fake_hfile(), 0, vec![1, 2], IoKind::Write, Some(slot.clone())).unwrap();
+		let mut fut = IoFuture { id: op.id, slot };
+
+		let (done, future) = dequeue(&mut state, op, 2, Ok(())).unwrap();
+		FutureSlot::deliver(&future.unwrap(), done); // nobody to wake yet
+
+		let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+		let waker = Waker::from(counter.clone());
+		match Pin::new(&mut fut).poll(&mut Context::from_waker(&waker)) {
+			Poll::Ready(done) => assert_eq!(done.buffer, [1, 2]),
+			Poll::Pending => panic!("completion not delivered"),
+		}
+		assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+	}
+}
@@ -0,0 +1,428 @@
+use std::cmp::Ordering;
+
+use crate::co;
+use crate::kernel::decl::{FILETIME, HFINDFILE, MAKEQWORD, path,
+	WIN32_FIND_DATA, WinResult};
+use crate::prelude::{Handle, KernelHfindfile, NativeBitflag};
+
+/// An entry found by [`DirWalk`](crate::DirWalk), with the information
+/// returned by the search itself, so no further file system calls are needed
+/// to inspect it.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+	/// Full path of the entry: the walked directory joined with the names of
+	/// the subdirectories and the entry itself.
+	pub path: String,
+	/// How deep the entry is; the entries right in the walked directory have
+	/// depth 1.
+	pub depth: usize,
+	/// Attributes of the entry.
+	pub attributes: co::FILE_ATTRIBUTE,
+	/// Size of the file in bytes, zero for directories.
+	pub size: u64,
+	/// When the entry was created.
+	pub creation_time: FILETIME,
+	/// When the entry was last read from, written to, or run.
+	pub last_access_time: FILETIME,
+	/// When the entry was last written to.
+	pub last_write_time: FILETIME,
+}
+
+impl std::fmt::Display for DirEntry {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.path)
+	}
+}
+
+impl DirEntry {
+	/// Returns the name of the entry, which is the last part of its path.
+	pub fn file_name(&self) -> &str {
+		path::get_file_name(&self.path).unwrap_or(&self.path)
+	}
+
+	/// Tells whether the entry is a directory.
+	pub fn is_dir(&self) -> bool {
+		self.attributes.has(co::FILE_ATTRIBUTE::DIRECTORY)
+	}
+
+	/// Tells whether the entry is hidden.
+	pub fn is_hidden(&self) -> bool {
+		self.attributes.has(co::FILE_ATTRIBUTE::HIDDEN)
+	}
+
+	/// Tells whether the entry is a reparse point, like a symbolic link or a
+	/// junction.
+	pub fn is_reparse_point(&self) -> bool {
+		self.attributes.has(co::FILE_ATTRIBUTE::REPARSE_POINT)
+	}
+
+	/// Tells whether the entry is used by the operating system.
+	pub fn is_system(&self) -> bool {
+		self.attributes.has(co::FILE_ATTRIBUTE::SYSTEM)
+	}
+
+	fn new(dir: &str, depth: usize, wfd: &WIN32_FIND_DATA) -> Self {
+		Self {
+			path: path::WinPath::parse(dir).join(&wfd.cFileName()),
+			depth,
+			attributes: wfd.dwFileAttributes,
+			size: wfd.nFileSize(),
+			creation_time: wfd.ftCreationTime.clone(),
+			last_access_time: wfd.ftLastAccessTime.clone(),
+			last_write_time: wfd.tLastWriteTime.clone(),
+		}
+	}
+}
+
+/// Order of the entries within each directory, in a
+/// [`DirWalkOptions`](crate::DirWalkOptions).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DirSort {
+	/// The order returned by the file system, which is alphabetical on NTFS,
+	/// but arbitrary on FAT.
+	None,
+	/// Case-insensitive order of the names.
+	Name,
+	/// Directories before files, each one sorted by name.
+	DirsFirst,
+	/// Smallest files first, then by name.
+	Size,
+	/// Oldest last write times first, then by name.
+	LastWriteTime,
+}
+
+/// Options to
+/// [`HFINDFILE::walk_with`](crate::prelude::KernelHfindfile::walk_with).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirWalkOptions {
+	/// Maximum depth of the returned entries; the entries right in the walked
+	/// directory have depth 1. Deeper directories are not even read.
+	///
+	/// Default is `None`, no limit.
+	pub max_depth: Option<usize>,
+	/// If `true`, directories which are reparse points – symbolic links and
+	/// junctions – are walked into. Note that they can form cycles, which
+	/// should be limited with `max_depth`.
+	///
+	/// Default is `false`: the reparse points are returned, but not walked.
+	pub follow_reparse_points: bool,
+	/// If `true`, hidden entries are skipped, and hidden directories are not
+	/// walked.
+	///
+	/// Default is `false`.
+	pub skip_hidden: bool,
+	/// If `true`, system entries are skipped, and system directories are not
+	/// walked.
+	///
+	/// Default is `false`.
+	pub skip_system: bool,
+	/// If `true`, each directory is returned after its contents (post-order),
+	/// otherwise before (pre-order).
+	///
+	/// Default is `false`.
+	pub contents_first: bool,
+	/// Order of the entries within each directory.
+	///
+	/// Default is [`DirSort::None`](crate::DirSort::None).
+	pub sort: DirSort,
+}
+
+impl Default for DirWalkOptions {
+	fn default() -> Self {
+		Self {
+			max_depth: None,
+			follow_reparse_points: false,
+			skip_hidden: false,
+			skip_system: false,
+			contents_first: false,
+			sort: DirSort::None,
+		}
+	}
+}
+
+/// Iterator which recursively walks a directory, returned by
+/// [`HFINDFILE::walk`](crate::prelude::KernelHfindfile::walk) and
+/// [`HFINDFILE::walk_with`](crate::prelude::KernelHfindfile::walk_with).
+///
+/// Each directory is read at once with
+/// [`HFINDFILE::FindFirstFileEx`](crate::prelude::KernelHfindfile::FindFirstFileEx),
+/// using [`FIND_FIRST_EX::LARGE_FETCH`](crate::co::FIND_FIRST_EX::LARGE_FETCH),
+/// and without querying the 8.3 short names. The `.` and `..` entries are
+/// never returned.
+///
+/// If a directory can't be read, the error is returned in place of its
+/// contents, and the walk goes on.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct DirWalk {
+	options: DirWalkOptions,
+	filter: Option<DirFilter>,
+	list_dir: ListDir,
+	root: Option<String>, // not read yet
+	stack: Vec<DirFrame>,
+}
+
+type DirFilter = Box<dyn FnMut(&DirEntry) -> bool>;
+type ListDir = fn(&str, usize) -> WinResult<Vec<DirEntry>>;
+
+/// A directory being walked.
+struct DirFrame {
+	entries: std::vec::IntoIter<WinResult<DirEntry>>,
+	owner: Option<DirEntry>, // returned after the entries, if contents first
+}
+
+impl Iterator for DirWalk {
+	type Item = WinResult<DirEntry>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if let Some(root) = self.root.take() {
+			if self.options.max_depth == Some(0) {
+				return None;
+			}
+			let entries = self.read_dir(&root, 1);
+			self.stack.push(DirFrame { entries, owner: None });
+		}
+
+		loop {
+			let frame = self.stack.last_mut()?;
+			let entry = match frame.entries.next() {
+				Some(Ok(entry)) => entry,
+				Some(Err(e)) => return Some(Err(e)),
+				None => match self.stack.pop().and_then(|frame| frame.owner) {
+					Some(owner) => return Some(Ok(owner)), // contents first
+					None => continue,
+				},
+			};
+
+			if !self.should_descend(&entry) {
+				return Some(Ok(entry));
+			}
+
+			let entries = self.read_dir(&entry.path, entry.depth + 1);
+			if self.options.contents_first {
+				self.stack.push(DirFrame { entries, owner: Some(entry) });
+			} else {
+				self.stack.push(DirFrame { entries, owner: None });
+				return Some(Ok(entry));
+			}
+		}
+	}
+}
+
+impl DirWalk {
+	pub(in crate::kernel) fn new(dir: &str, options: DirWalkOptions) -> Self {
+		Self {
+			options,
+			filter: None,
+			list_dir: find_all,
+			root: Some(dir.to_owned()),
+			stack: Vec::default(),
+		}
+	}
+
+	/// Sets a predicate which is called for each entry, before it's returned.
+	/// If the predicate returns `false`, the entry is skipped and, if it's a
+	/// directory, its whole subtree is not walked.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::HFINDFILE;
+	///
+	/// let walk = HFINDFILE::walk("C:\\Projects")
+	///     .filter_entry(|entry| !entry.file_name().starts_with('.'));
+	/// ```
+	pub fn filter_entry<F>(mut self, predicate: F) -> Self
+		where F: FnMut(&DirEntry) -> bool + 'static,
+	{
+		self.filter = Some(Box::new(predicate));
+		self
+	}
+
+	fn should_descend(&self, entry: &DirEntry) -> bool {
+		entry.is_dir()
+			&& (self.options.follow_reparse_points || !entry.is_reparse_point())
+			&& self.options.max_depth.is_none_or(|max| entry.depth < max)
+	}
+
+	/// Reads, filters and sorts the entries of a directory. An error is
+	/// returned as the only entry.
+	fn read_dir(&mut self, dir: &str, depth: usize,
+	) -> std::vec::IntoIter<WinResult<DirEntry>>
+	{
+		let mut entries = match (self.list_dir)(dir, depth) {
+			Ok(entries) => entries,
+			Err(e) => return vec![Err(e)].into_iter(),
+		};
+
+		let (skip_hidden, skip_system) = (self.options.skip_hidden, self.options.skip_system);
+		entries.retain(|entry| !((skip_hidden && entry.is_hidden())
+			|| (skip_system && entry.is_system())));
+		if let Some(filter) = self.filter.as_mut() {
+			entries.retain(|entry| filter(entry));
+		}
+
+		match self.options.sort {
+			DirSort::None => {},
+			DirSort::Name => entries.sort_by(cmp_names),
+			DirSort::DirsFirst => entries.sort_by(|a, b|
+				b.is_dir().cmp(&a.is_dir()).then_with(|| cmp_names(a, b))),
+			DirSort::Size => entries.sort_by(|a, b|
+				a.size.cmp(&b.size).then_with(|| cmp_names(a, b))),
+			DirSort::LastWriteTime => entries.sort_by(|a, b|
+				filetime_u64(&a.last_write_time).cmp(&filetime_u64(&b.last_write_time))
+					.then_with(|| cmp_names(a, b))),
+		}
+
+		entries.into_iter()
+			.map(Ok)
+			.collect::<Vec<_>>()
+			.into_iter()
+	}
+}
+
+/// Lists all entries of the directory, skipping `.` and `..`.
+fn find_all(dir: &str, depth: usize) -> WinResult<Vec<DirEntry>> {
+	let mut wfd = WIN32_FIND_DATA::default();
+	let (hfind, mut found) = HFINDFILE::FindFirstFileEx(
+		&path::WinPath::parse(dir).join("*"),
+		co::FINDEX_INFO_LEVELS::Basic,
+		&mut wfd,
+		co::FINDEX_SEARCH_OPS::NameMatch,
+		co::FIND_FIRST_EX::LARGE_FETCH,
+	)?;
+
+	let mut entries = Vec::default();
+	while found {
+		let name = wfd.cFileName();
+		if name != "." && name != ".." {
+			entries.push(DirEntry::new(dir, depth, &wfd));
+		}
+		found = match hfind.FindNextFile(&mut wfd) {
+			Err(e) => {
+				hfind.FindClose().ok(); // ignore error
+				return Err(e);
+			},
+			Ok(found) => found,
+		};
+	}
+
+	if !hfind.is_null() {
+		hfind.FindClose()?;
+	}
+	Ok(entries)
+}
+
+fn cmp_names(a: &DirEntry, b: &DirEntry) -> Ordering {
+	a.file_name().to_uppercase().cmp(&b.file_name().to_uppercase())
+}
+
+fn filetime_u64(ft: &FILETIME) -> u64 {
+	MAKEQWORD(ft.dwLowDateTime, ft.dwHighDateTime)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A fake tree under `C:\r`, where `a` and `.h` are directories, and `.h`
+	/// is hidden.
+	fn fake_tree(dir: &str, depth: usize) -> WinResult<Vec<DirEntry>> {
+		let names: &[&str] = match dir {
+			"C:\\r" => &["b.txt", "a", ".h"],
+			"C:\\r\\a" => &["c", "d.txt"],
+			"C:\\r\\a\\c" => &["e.txt"],
+			"C:\\r\\.h" => &["f.txt"],
+			_ => return Err(co::ERROR::PATH_NOT_FOUND),
+		};
+		Ok(names.iter()
+			.map(|name| {
+				let mut attributes = co::FILE_ATTRIBUTE::NORMAL;
+				if !name.contains(".txt") {
+					attributes = co::FILE_ATTRIBUTE::DIRECTORY;
+				}
+				if name.starts_with(".h") {
+					attributes |= co::FILE_ATTRIBUTE::HIDDEN;
+				}
+				DirEntry {
+					path: format!("{}\\{}", dir, name),
+					depth,
+					attributes,
+					size: 0,
+					creation_time: FILETIME::default(),
+					last_access_time: FILETIME::default(),
+					last_write_time: FILETIME::default(),
+				}
+			})
+			.collect())
+	}
+
+	fn walk(options: DirWalkOptions) -> DirWalk {
+		let mut walk = DirWalk::new("C:\\r", options);
+		walk.list_dir = fake_tree;
+		walk
+	}
+
+	fn paths(walk: DirWalk) -> Vec<String> {
+		walk.map(|entry| {
+			let entry = entry.unwrap();
+			format!("{}:{}", entry.depth, &entry.path[5..]) // skip C:\r\
+		}).collect()
+	}
+
+	#[test]
+	fn pre_order() {
+		let opts = DirWalkOptions { sort: DirSort::Name, ..Default::default() };
+		assert_eq!(paths(walk(opts)),
+			["1:.h", "2:.h\\f.txt", "1:a", "2:a\\c", "3:a\\c\\e.txt", "2:a\\d.txt", "1:b.txt"]);
+	}
+
+	#[test]
+	fn contents_first() {
+		let opts = DirWalkOptions {
+			contents_first: true,
+			skip_hidden: true,
+			sort: DirSort::DirsFirst,
+			..Default::default()
+		};
+		assert_eq!(paths(walk(opts)),
+			["3:a\\c\\e.txt", "2:a\\c", "2:a\\d.txt", "1:a", "1:b.txt"]);
+	}
+
+	#[test]
+	fn max_depth() {
+		let opts = DirWalkOptions {
+			max_depth: Some(2),
+			skip_hidden: true,
+			sort: DirSort::Name,
+			..Default::default()
+		};
+		assert_eq!(paths(walk(opts)), ["1:a", "2:a\\c", "2:a\\d.txt", "1:b.txt"]);
+
+		let opts = DirWalkOptions { max_depth: Some(1), ..Default::default() };
+		assert_eq!(paths(walk(opts)), ["1:b.txt", "1:a", "1:.h"]);
+
+		let opts = DirWalkOptions { max_depth: Some(0), ..Default::default() };
+		assert!(paths(walk(opts)).is_empty());
+	}
+
+	#[test]
+	fn filter_prunes_subtree() {
+		let opts = DirWalkOptions { sort: DirSort::Name, ..Default::default() };
+		let filtered = walk(opts)
+			.filter_entry(|entry| entry.file_name() != "c" && !entry.is_hidden());
+		assert_eq!(paths(filtered), ["1:a", "2:a\\d.txt", "1:b.txt"]);
+	}
+
+	#[test]
+	fn read_error() {
+		let mut w = DirWalk::new("C:\\none", DirWalkOptions::default());
+		w.list_dir = fake_tree;
+		let res = w.collect::<Vec<_>>();
+		assert_eq!(res.len(), 1);
+		assert_eq!(res[0].as_ref().unwrap_err(), &co::ERROR::PATH_NOT_FOUND);
+	}
+}
@@ -1,3 +1,5 @@
+use std::io;
+
 use crate::co;
 use crate::kernel::decl::{HFILE, WinResult};
 use crate::prelude::{HandleClose, KernelHfile};
@@ -5,7 +7,7 @@ use crate::prelude::{HandleClose, KernelHfile};
 /// Access types for [`File::open`](crate::File::open) and
 /// [`FileMapped::open`](crate::FileMapped::open).
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum FileAccess {
 	/// Opens the file as read-only. Fails if the file doesn't exist.
 	ExistingReadOnly,
@@ -14,6 +16,56 @@ pub enum FileAccess {
 	/// Opens the file as read/write. If the file doesn't exist, it will be
 	/// created.
 	OpenOrCreateReadWrite,
+	/// Creates a new file as read/write. Fails if the file already exists.
+	CreateNewReadWrite,
+	/// Opens the file as read/write, truncating it to zero bytes. If the file
+	/// doesn't exist, it will be created.
+	CreateOrTruncateReadWrite,
+	/// Opens the file as read/write, truncating it to zero bytes. Fails if the
+	/// file doesn't exist.
+	TruncateExistingReadWrite,
+	/// Opens the file for reading and appending. If the file doesn't exist, it
+	/// will be created.
+	///
+	/// All writes go to the end of the file, regardless of the internal file
+	/// pointer, and the existing content can't be changed, so
+	/// [`File::resize`](crate::File::resize) fails. Can't be used with
+	/// [`FileMapped`](crate::FileMapped).
+	Append,
+}
+
+impl FileAccess {
+	/// Tells whether the access allows writing to the file.
+	pub const fn is_writable(&self) -> bool {
+		!matches!(self, Self::ExistingReadOnly)
+	}
+
+	/// The share mode used by [`File::open`](crate::File::open): read-only
+	/// files can be read by others, other files are exclusive.
+	const fn default_share(&self) -> co::FILE_SHARE {
+		match self {
+			Self::ExistingReadOnly => co::FILE_SHARE::READ,
+			_ => co::FILE_SHARE::NoValue,
+		}
+	}
+
+	/// The access rights and the creation disposition passed to
+	/// [`HFILE::CreateFile`](crate::prelude::KernelHfile::CreateFile).
+	fn rights_and_disposition(&self) -> (co::GENERIC, co::DISPOSITION) {
+		let read_write = co::GENERIC::READ | co::GENERIC::WRITE;
+		match self {
+			Self::ExistingReadOnly => (co::GENERIC::READ, co::DISPOSITION::OPEN_EXISTING),
+			Self::ExistingReadWrite => (read_write, co::DISPOSITION::OPEN_EXISTING),
+			Self::OpenOrCreateReadWrite => (read_write, co::DISPOSITION::OPEN_ALWAYS),
+			Self::CreateNewReadWrite => (read_write, co::DISPOSITION::CREATE_NEW),
+			Self::CreateOrTruncateReadWrite => (read_write, co::DISPOSITION::CREATE_ALWAYS),
+			Self::TruncateExistingReadWrite => (read_write, co::DISPOSITION::TRUNCATE_EXISTING),
+			Self::Append => (
+				co::GENERIC::READ | co::GENERIC((co::FILE_RIGHT::GENERIC_WRITE & !co::FILE_RIGHT::WRITE_DATA).0), // appending only
+				co::DISPOSITION::OPEN_ALWAYS,
+			),
+		}
+	}
 }
 
 //------------------------------------------------------------------------------
@@ -24,6 +76,32 @@ pub enum FileAccess {
 /// This is an alternative to the standard
 /// [`std::fs::File`](https://doc.rust-lang.org/std/fs/struct.File.html), with a
 /// possibly faster implementation since it's Windows-only.
+///
+/// Implements the standard
+/// [`Read`](https://doc.rust-lang.org/std/io/trait.Read.html),
+/// [`Write`](https://doc.rust-lang.org/std/io/trait.Write.html) and
+/// [`Seek`](https://doc.rust-lang.org/std/io/trait.Seek.html) traits, also for
+/// `&File`, so it can be wrapped in a
+/// [`BufReader`](https://doc.rust-lang.org/std/io/struct.BufReader.html) or a
+/// [`BufWriter`](https://doc.rust-lang.org/std/io/struct.BufWriter.html). Note
+/// that the inherent [`read`](crate::File::read) and
+/// [`write`](crate::File::write) methods take precedence over the trait ones
+/// when called directly.
+///
+/// # Examples
+///
+/// Reading a text file line by line:
+///
+/// ```rust,no_run
+/// use std::io::BufRead;
+/// use winsafe::{File, FileAccess};
+///
+/// let file = File::open("C:\\Temp\\foo.txt", FileAccess::ExistingReadOnly)?;
+/// for line in std::io::BufReader::new(file).lines() {
+///     println!("{}", line?);
+/// }
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub struct File {
 	hfile: HFILE,
@@ -35,27 +113,111 @@ impl Drop for File {
 	}
 }
 
+impl io::Read for File {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		io::Read::read(&mut &*self, buf)
+	}
+}
+
+impl io::Read for &File {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let len = buf.len().min(u32::MAX as _);
+		match self.hfile.ReadFile(&mut buf[..len], None) {
+			Ok(bytes_read) => Ok(bytes_read as _),
+			Err(co::ERROR::BROKEN_PIPE) => Ok(0), // the other end of a pipe was closed
+			Err(e) => Err(e.into()),
+		}
+	}
+}
+
+impl io::Write for File {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		io::Write::write(&mut &*self, buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		io::Write::flush(&mut &*self)
+	}
+}
+
+impl io::Write for &File {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = buf.len().min(u32::MAX as _);
+		self.hfile.WriteFile(&buf[..len], None)
+			.map(|bytes_written| bytes_written as _)
+			.map_err(|e| e.into())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(()) // there's no user-mode buffer; to flush the OS buffers, use sync_all
+	}
+}
+
+impl io::Seek for File {
+	fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+		io::Seek::seek(&mut &*self, pos)
+	}
+}
+
+impl io::Seek for &File {
+	fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+		let (distance, move_method) = seek_args(pos)?;
+		self.hfile.SetFilePointerEx(distance, move_method)
+			.map(|new_offset| new_offset as _)
+			.map_err(|e| e.into())
+	}
+}
+
+/// Converts a `SeekFrom` into the arguments of
+/// [`HFILE::SetFilePointerEx`](crate::prelude::KernelHfile::SetFilePointerEx).
+/// An offset from the start which doesn't fit an `i64` fails with
+/// [`ERROR::NEGATIVE_SEEK`](crate::co::ERROR::NEGATIVE_SEEK), instead of
+/// wrapping around.
+fn seek_args(pos: io::SeekFrom) -> WinResult<(i64, co::FILE_STARTING_POINT)> {
+	Ok(match pos {
+		io::SeekFrom::Start(offset) => (
+			i64::try_from(offset).map_err(|_| co::ERROR::NEGATIVE_SEEK)?,
+			co::FILE_STARTING_POINT::BEGIN,
+		),
+		io::SeekFrom::Current(offset) => (offset, co::FILE_STARTING_POINT::CURRENT),
+		io::SeekFrom::End(offset) => (offset, co::FILE_STARTING_POINT::END),
+	})
+}
+
 impl File {
 	/// Opens a file with the desired access.
+	///
+	/// Read-only files are opened with
+	/// [`FILE_SHARE::READ`](crate::co::FILE_SHARE::READ), other files are
+	/// opened exclusively. To choose the share mode, use
+	/// [`File::open_shared`](crate::File::open_shared).
 	pub fn open(file_path: &str, access: FileAccess) -> WinResult<File> {
-		let (acc, share, disp) = match access {
-			FileAccess::ExistingReadOnly =>  (
-				co::GENERIC::READ,
-				co::FILE_SHARE::READ,
-				co::DISPOSITION::OPEN_EXISTING,
-			),
-			FileAccess::ExistingReadWrite => (
-				co::GENERIC::READ | co::GENERIC::WRITE,
-				co::FILE_SHARE::NoValue,
-				co::DISPOSITION::OPEN_EXISTING,
-			),
-			FileAccess::OpenOrCreateReadWrite => (
-				co::GENERIC::READ | co::GENERIC::WRITE,
-				co::FILE_SHARE::NoValue,
-				co::DISPOSITION::OPEN_ALWAYS,
-			),
-		};
+		Self::open_shared(file_path, access, access.default_share())
+	}
 
+	/// Opens a file with the desired access, allowing other processes to
+	/// read, write or delete the file while it's open, according to `share`.
+	///
+	/// # Examples
+	///
+	/// Opening a log file which is being written by another process:
+	///
+	/// ```rust,no_run
+	/// use winsafe::{co, File, FileAccess};
+	///
+	/// let file = File::open_shared(
+	///     "C:\\Temp\\app.log",
+	///     FileAccess::ExistingReadOnly,
+	///     co::FILE_SHARE::READ | co::FILE_SHARE::WRITE,
+	/// )?;
+	/// # Ok::<_, co::ERROR>(())
+	/// ```
+	pub fn open_shared(
+		file_path: &str,
+		access: FileAccess,
+		share: co::FILE_SHARE) -> WinResult<File>
+	{
+		let (acc, disp) = access.rights_and_disposition();
 		let (hfile, _) = HFILE::CreateFile(
 			file_path, acc, share, None, disp, co::FILE_ATTRIBUTE::NORMAL, None)?;
 		Ok(Self { hfile })
@@ -121,6 +283,13 @@ impl File {
 		self.hfile.GetFileSizeEx()
 	}
 
+	/// Flushes the data buffered by the operating system to the disk, by
+	/// calling
+	/// [`HFILE::FlushFileBuffers`](crate::prelude::KernelHfile::FlushFileBuffers).
+	pub fn sync_all(&self) -> WinResult<()> {
+		self.hfile.FlushFileBuffers()
+	}
+
 	/// Writes the given bytes. The content will be written at the position
 	/// currently pointed by the internal file pointer.
 	pub fn write(&self, data: &[u8]) -> WinResult<()> {
@@ -128,3 +297,41 @@ impl File {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::NativeBitflag;
+	use super::*;
+
+	#[test]
+	fn seek_args_mapping() {
+		assert_eq!(seek_args(io::SeekFrom::Start(10)), Ok((10, co::FILE_STARTING_POINT::BEGIN)));
+		assert_eq!(seek_args(io::SeekFrom::Start(i64::MAX as _)),
+			Ok((i64::MAX, co::FILE_STARTING_POINT::BEGIN)));
+		assert_eq!(seek_args(io::SeekFrom::Current(-3)), Ok((-3, co::FILE_STARTING_POINT::CURRENT)));
+		assert_eq!(seek_args(io::SeekFrom::End(-1)), Ok((-1, co::FILE_STARTING_POINT::END)));
+		assert_eq!(seek_args(io::SeekFrom::End(i64::MIN)), Ok((i64::MIN, co::FILE_STARTING_POINT::END)));
+	}
+
+	#[test]
+	fn seek_args_start_overflow() {
+		assert_eq!(seek_args(io::SeekFrom::Start(i64::MAX as u64 + 1)), Err(co::ERROR::NEGATIVE_SEEK));
+		assert_eq!(seek_args(io::SeekFrom::Start(u64::MAX)), Err(co::ERROR::NEGATIVE_SEEK));
+	}
+
+	#[test]
+	fn append_rights() {
+		let (acc, disp) = FileAccess::Append.rights_and_disposition();
+		let rights = co::FILE_RIGHT(acc.0);
+		assert!(acc.has(co::GENERIC::READ));
+		assert!(!acc.has(co::GENERIC::WRITE));
+		assert!(rights.has(co::FILE_RIGHT::APPEND_DATA));
+		assert!(!rights.has(co::FILE_RIGHT::WRITE_DATA));
+		assert!(rights.has(co::FILE_RIGHT::WRITE_ATTRIBUTES));
+		assert_eq!(disp, co::DISPOSITION::OPEN_ALWAYS);
+
+		let (acc, _) = FileAccess::OpenOrCreateReadWrite.rights_and_disposition();
+		assert!(acc.has(co::GENERIC::READ | co::GENERIC::WRITE));
+		assert!(FileAccess::Append.is_writable());
+	}
+}
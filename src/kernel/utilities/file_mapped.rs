@@ -23,9 +23,16 @@ impl Drop for FileMapped {
 
 impl FileMapped {
 	/// Opens a file with the desired access, then map its contents in memory.
+	///
+	/// [`FileAccess::Append`](crate::FileAccess::Append) is not supported, and
+	/// fails with [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER).
 	pub fn open(
 		file_path: &str, access: FileAccess) -> WinResult<FileMapped>
 	{
+		if access == FileAccess::Append {
+			return Err(co::ERROR::INVALID_PARAMETER); // the mapping would need to write anywhere
+		}
+
 		let mut new_self = Self {
 			access,
 			file: File::open(file_path, access)?,
@@ -41,20 +48,16 @@ impl FileMapped {
 	fn map_in_memory(&mut self) -> WinResult<()> {
 		self.hmap = self.file.hfile().CreateFileMapping(
 			None,
-			match self.access {
-				FileAccess::ExistingReadOnly => co::PAGE::READONLY,
-				FileAccess::ExistingReadWrite
-					| FileAccess::OpenOrCreateReadWrite => co::PAGE::READWRITE,
-			},
+			if self.access.is_writable() { co::PAGE::READWRITE } else { co::PAGE::READONLY },
 			None,
 			None,
 		)?;
 
 		self.hview = self.hmap.MapViewOfFile(
-			match self.access {
-				FileAccess::ExistingReadOnly => co::FILE_MAP::READ,
-				FileAccess::ExistingReadWrite
-					| FileAccess::OpenOrCreateReadWrite => co::FILE_MAP::READ | co::FILE_MAP::WRITE,
+			if self.access.is_writable() {
+				co::FILE_MAP::READ | co::FILE_MAP::WRITE
+			} else {
+				co::FILE_MAP::READ
 			},
 			0,
 			None,
@@ -95,3 +98,14 @@ impl FileMapped {
 		self.size
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reject_append() {
+		assert_eq!(FileMapped::open("C:\\Temp\\foo.txt", FileAccess::Append).err(),
+			Some(co::ERROR::INVALID_PARAMETER));
+	}
+}
@@ -1,10 +1,21 @@
 use crate::co;
-use crate::kernel::decl::{FileAccess, FileMapped, HFILE, WinResult, WString};
+use crate::kernel::decl::{Encoding, ErrResult, FileAccess, FileMapped, HFILE,
+	WinResult, WString};
+use super::w_string::encode_str;
 use crate::prelude::{HandleClose, KernelHfile};
 
 /// Keeps sections and key/value pairs of a `.ini` file, also doing parsing and
 /// serialization of the data.
 ///
+/// The parsing is lossless: comments, blank lines, unrecognized lines,
+/// whitespace around `=` and the original line breaks are all kept, so
+/// serializing an unmodified `Ini` yields exactly the same text that was
+/// parsed. Changing a value will only change the line where it lives.
+///
+/// When parsed from bytes or from a file, the [`Encoding`](crate::Encoding)
+/// and the BOM are also kept, so the data is written back the same way. See
+/// [`set_encoding`](crate::Ini::set_encoding).
+///
 /// # Examples
 ///
 /// ```rust,no_run
@@ -22,74 +33,392 @@ use crate::prelude::{HandleClose, KernelHfile};
 /// # Ok::<_, winsafe::co::ERROR>(())
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug)]
 pub struct Ini {
 	/// All the sections of the file. The values can be modified at will.
+	///
+	/// Key/value pairs which appear before the first section header are kept
+	/// in a section with an empty name, which has no header when serialized.
 	pub sections: Vec<IniSection>,
+	trailing: String,
+	line_break: String,
+	encoding: Encoding,
+	with_bom: bool,
 }
 
 /// A single section of an [`Ini`](crate::Ini).
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug)]
 pub struct IniSection {
 	/// The name of this section.
 	pub name: String,
 	/// All key/value pairs of this section.
 	pub entries: Vec<IniEntry>,
+	headerless: bool,
+	leading: String,
+	layout: Option<SectionLayout>,
 }
 
 /// A single key/value pair of an [`IniSection`](crate::IniSection) of an
 /// [`Ini`](crate::Ini).
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug)]
 pub struct IniEntry {
 	/// Key of this entry.
 	pub key: String,
 	/// Value of this entry.
 	pub val: String,
+	leading: String,
+	layout: Option<EntryLayout>,
+}
+
+/// Original formatting of a section header line.
+#[derive(Clone, Debug)]
+struct SectionLayout {
+	indent: String,
+	pad_left: String,
+	pad_right: String,
+	trailing: String,
+	eol: String,
+}
+
+/// Original formatting of a key/value line.
+#[derive(Clone, Debug)]
+struct EntryLayout {
+	indent: String,
+	sep: String,
+	trailing: String,
+	eol: String,
+}
+
+/// Options to [`Ini::parse_str_with`](crate::Ini::parse_str_with).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IniParseOptions {
+	/// If `true`, malformed lines – like an unterminated section header, or a
+	/// line which is neither a comment nor has a `=` – will fail the parsing.
+	/// If `false`, these lines are kept, but ignored.
+	///
+	/// Default is `false`.
+	pub strict: bool,
+	/// What to do with duplicated sections and keys.
+	///
+	/// Default is [`IniDuplicates::KeepAll`](crate::IniDuplicates::KeepAll).
+	pub duplicates: IniDuplicates,
+	/// If `true`, a `;` or `#` preceded by whitespace starts a comment at the
+	/// end of a value, which is kept on serialization but not in the value.
+	/// If `false`, like
+	/// [`GetPrivateProfileString`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getprivateprofilestringw),
+	/// the value goes up to the end of the line.
+	///
+	/// Default is `false`.
+	pub inline_comments: bool,
+}
+
+impl Default for IniParseOptions {
+	fn default() -> Self {
+		Self {
+			strict: false,
+			duplicates: IniDuplicates::KeepAll,
+			inline_comments: false,
+		}
+	}
+}
+
+/// How [`Ini::parse_str_with`](crate::Ini::parse_str_with) handles sections
+/// and keys which appear more than once. Names are compared
+/// case-insensitively.
+///
+/// Except for `KeepAll`, the duplicated lines are dropped, so the serialized
+/// text will differ from the parsed one.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IniDuplicates {
+	/// Everything is kept as parsed. Lookups like
+	/// [`Ini::value`](crate::Ini::value) will find the first occurrence.
+	KeepAll,
+	/// Duplicated sections are merged into the first one. Within a section,
+	/// the first value of a key is kept, and the others are dropped.
+	FirstWins,
+	/// Duplicated sections are merged into the first one. Within a section,
+	/// the last value of a key replaces the previous ones, which keep their
+	/// original position.
+	LastWins,
+	/// Duplicated sections are merged into the first one. Duplicated keys are
+	/// kept, and all their values can be retrieved with
+	/// [`Ini::values`](crate::Ini::values).
+	Merge,
+	/// A duplicated section or key fails the parsing.
+	Reject,
+}
+
+/// Error returned by [`Ini::parse_str_with`](crate::Ini::parse_str_with).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IniError {
+	/// The line where the error occurred, starting at 1.
+	pub line: usize,
+	/// The column where the error occurred, in chars, starting at 1.
+	pub column: usize,
+	/// The kind of the error.
+	pub kind: IniErrorKind,
+}
+
+/// The kind of an [`IniError`](crate::IniError).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IniErrorKind {
+	/// A section header without the closing `]`.
+	UnterminatedSection,
+	/// A section header followed by something which is not a comment.
+	TextAfterSection,
+	/// A line which is neither a section header, a comment nor a key/value
+	/// pair.
+	MissingEquals,
+	/// A key/value pair with an empty key.
+	EmptyKey,
+	/// A section which already exists, with
+	/// [`IniDuplicates::Reject`](crate::IniDuplicates::Reject).
+	DuplicateSection(String),
+	/// A key which already exists in its section, with
+	/// [`IniDuplicates::Reject`](crate::IniDuplicates::Reject).
+	DuplicateKey(String),
+}
+
+impl std::error::Error for IniError {}
+
+impl std::fmt::Display for IniError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Line {}, column {}: {}", self.line, self.column, self.kind)
+	}
+}
+
+impl std::fmt::Display for IniErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::UnterminatedSection => write!(f, "section header without closing bracket"),
+			Self::TextAfterSection => write!(f, "unexpected text after section header"),
+			Self::MissingEquals => write!(f, "line is not a key/value pair"),
+			Self::EmptyKey => write!(f, "empty key"),
+			Self::DuplicateSection(name) => write!(f, "duplicated section \"{}\"", name),
+			Self::DuplicateKey(key) => write!(f, "duplicated key \"{}\"", key),
+		}
+	}
+}
+
+impl IniError {
+	fn new(line_no: usize, line: &str, byte_idx: usize, kind: IniErrorKind) -> Self {
+		Self {
+			line: line_no,
+			column: line[..byte_idx].chars().count() + 1,
+			kind,
+		}
+	}
+}
+
+impl Default for Ini {
+	fn default() -> Self {
+		Self {
+			sections: Vec::default(),
+			trailing: String::default(),
+			line_break: "\r\n".to_owned(),
+			encoding: Encoding::Utf8,
+			with_bom: false,
+		}
+	}
+}
+
+impl IniSection {
+	/// Creates a new, empty section.
+	pub fn new(name: &str) -> IniSection {
+		Self {
+			name: name.to_owned(),
+			entries: Vec::default(),
+			headerless: false,
+			leading: String::default(),
+			layout: None,
+		}
+	}
+
+	/// Tells whether this section holds the entries before the first section
+	/// header, thus having no header itself.
+	pub(super) const fn is_headerless(&self) -> bool {
+		self.headerless
+	}
+
+	fn entry_idx(&self, key: &str) -> Option<usize> {
+		self.entries.iter()
+			.position(|e| names_eq(&e.key, key))
+	}
+}
+
+impl IniEntry {
+	/// Creates a new key/value pair.
+	pub fn new(key: &str, val: &str) -> IniEntry {
+		Self {
+			key: key.to_owned(),
+			val: val.to_owned(),
+			leading: String::default(),
+			layout: None,
+		}
+	}
 }
 
 impl Ini {
 	/// Parses an `Ini` from a string.
+	///
+	/// This is a lenient parsing which never fails: malformed lines are kept
+	/// as they are, but ignored, and duplicated sections and keys are kept. To
+	/// customize this behavior, use
+	/// [`parse_str_with`](crate::Ini::parse_str_with).
 	pub fn parse_str(contents: &str) -> Ini {
-		let mut sections = Vec::default();
-		let mut cur_section = IniSection {
-			name: "".to_owned(),
-			entries: Vec::default(),
-		};
+		Self::parse_str_with(contents, &IniParseOptions::default())
+			.unwrap() // lenient parsing never fails
+	}
+
+	/// Parses an `Ini` from a string, with the given options.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::{Ini, IniDuplicates, IniParseOptions};
+	///
+	/// let ini = Ini::parse_str_with(
+	///     "[Fruits]\r\nbanana=yellow\r\nbanana=green",
+	///     &IniParseOptions {
+	///         strict: true,
+	///         duplicates: IniDuplicates::LastWins,
+	///         inline_comments: false,
+	///     },
+	/// )?;
+	///
+	/// assert_eq!(ini.value("Fruits", "banana"), Some("green"));
+	/// # Ok::<_, winsafe::IniError>(())
+	/// ```
+	pub fn parse_str_with(contents: &str,
+		opts: &IniParseOptions) -> Result<Ini, IniError>
+	{
+		let mut ini = Self::default();
+		if let Some(idx) = contents.find('\n') {
+			ini.line_break = if contents[..idx].ends_with('\r') { "\r\n" } else { "\n" }
+				.to_owned();
+		}
+
+		let mut root = IniSection::new("");
+		root.headerless = true;
+		ini.sections.push(root); // removed at the end, if empty
+		let mut cur_idx = 0;
+		let mut pending = String::default(); // comments, blank and unknown lines
 
-		for line in contents.lines() {
-			let line = line.trim();
+		for (line_idx, (line, eol)) in LineIter::new(contents).enumerate() {
+			let line_no = line_idx + 1;
 
-			if line.starts_with('[') && line.ends_with(']') {
-				if cur_section.name != "" || !cur_section.entries.is_empty() {
-					sections.push(cur_section);
+			if line.trim_start().starts_with('[') {
+				match Self::parse_section_line(line, eol) {
+					Some((name, layout)) => {
+						let dup_idx = ini.sections.iter()
+							.position(|s| !s.headerless && names_eq(&s.name, name));
+						match (dup_idx, opts.duplicates) {
+							(Some(_), IniDuplicates::Reject) => return Err(IniError::new(
+								line_no, line, line.find('[').unwrap(),
+								IniErrorKind::DuplicateSection(name.to_owned()),
+							)),
+							(Some(dup_idx), IniDuplicates::FirstWins
+								| IniDuplicates::LastWins
+								| IniDuplicates::Merge) => {
+								cur_idx = dup_idx; // header is dropped, entries go to the first section
+							},
+							_ => {
+								let mut section = IniSection::new(name);
+								section.leading = std::mem::take(&mut pending);
+								section.layout = Some(layout);
+								ini.sections.push(section);
+								cur_idx = ini.sections.len() - 1;
+							},
+						}
+					},
+					None => {
+						if opts.strict {
+							return Err(Self::section_line_error(line_no, line));
+						}
+						pending.push_str(line);
+						pending.push_str(eol);
+					},
 				}
-				cur_section = IniSection {
-					name: line[1..line.len() - 1].to_owned(),
-					entries: Vec::default(),
-				};
 				continue;
 			}
 
-			if let Some(eq_idx) = line.find('=') {
-				cur_section.entries.push(IniEntry {
-					key: line[..eq_idx].to_owned(),
-					val: line[eq_idx + 1..].to_owned(),
-				});
+			match Self::parse_entry_line(line, eol, opts.inline_comments) {
+				Some(mut entry) => {
+					if opts.strict && entry.key.is_empty() {
+						return Err(IniError::new(line_no, line, line.find('=').unwrap(),
+							IniErrorKind::EmptyKey));
+					}
+
+					let section = &mut ini.sections[cur_idx];
+					let dup_idx = section.entry_idx(&entry.key);
+					match (dup_idx, opts.duplicates) {
+						(Some(_), IniDuplicates::Reject) => return Err(IniError::new(
+							line_no, line, line.len() - line.trim_start().len(),
+							IniErrorKind::DuplicateKey(entry.key),
+						)),
+						(Some(_), IniDuplicates::FirstWins) => {}, // line is dropped
+						(Some(dup_idx), IniDuplicates::LastWins) => {
+							section.entries[dup_idx].val = entry.val; // line is dropped
+						},
+						_ => {
+							entry.leading = std::mem::take(&mut pending);
+							section.entries.push(entry);
+						},
+					}
+				},
+				None => {
+					if opts.strict && !is_blank_or_comment(line) {
+						return Err(IniError::new(
+							line_no, line, line.len() - line.trim_start().len(),
+							IniErrorKind::MissingEquals,
+						));
+					}
+					pending.push_str(line);
+					pending.push_str(eol);
+				},
 			}
 		}
-		if cur_section.name != "" || !cur_section.entries.is_empty() {
-			sections.push(cur_section);
-		}
 
-		Self { sections }
+		if ini.sections[0].entries.is_empty() {
+			ini.sections.remove(0); // no entries before the first section header
+		}
+		ini.trailing = pending;
+		Ok(ini)
 	}
 
 	/// Parses an `Ini` from raw bytes. The [`Encoding`](crate::Encoding) will
 	/// be guessed with
-	/// [`WString::guess_encoding`](crate::WString::guess_encoding).
+	/// [`WString::detect_encoding`](crate::WString::detect_encoding), and kept
+	/// for serialization.
 	pub fn parse_bytes(bytes: &[u8]) -> WinResult<Ini> {
-		Ok(
-			Self::parse_str(&WString::parse_str(bytes)?.to_string()),
-		)
+		let ini = Self::parse_str(&WString::parse_str(bytes)?.to_string());
+		Ok(ini.with_encoding_of(bytes))
+	}
+
+	/// Parses an `Ini` from raw bytes, with the given options. The
+	/// [`Encoding`](crate::Encoding) will be guessed with
+	/// [`WString::detect_encoding`](crate::WString::detect_encoding), and kept
+	/// for serialization.
+	pub fn parse_bytes_with(bytes: &[u8], opts: &IniParseOptions) -> ErrResult<Ini> {
+		let ini = Self::parse_str_with(&WString::parse_str(bytes)?.to_string(), opts)?;
+		Ok(ini.with_encoding_of(bytes))
+	}
+
+	fn with_encoding_of(mut self, bytes: &[u8]) -> Self {
+		let (encoding, bom_size) = WString::guess_encoding(bytes);
+		self.encoding = match encoding {
+			Encoding::Ansi => Encoding::Utf8, // plain ASCII is also valid UTF-8
+			encoding => encoding,
+		};
+		self.with_bom = bom_size > 0;
+		self
 	}
 
 	/// Parses an `Ini` directly from a file.
@@ -98,43 +427,88 @@ impl Ini {
 		Self::parse_bytes(fin.as_slice())
 	}
 
+	/// Parses an `Ini` directly from a file, with the given options.
+	pub fn parse_from_file_with(ini_path: &str,
+		opts: &IniParseOptions) -> ErrResult<Ini>
+	{
+		let fin = FileMapped::open(ini_path, FileAccess::ExistingReadOnly)?;
+		Self::parse_bytes_with(fin.as_slice(), opts)
+	}
+
 	/// Serializes the data to a string.
+	///
+	/// Lines which were parsed and not modified are written back verbatim.
+	/// New sections and entries use the line break found in the original
+	/// text, or `\r\n` if none.
 	pub fn serialize_to_str(&self) -> String {
-		let mut tot_size = 0;
-		for (idx, section) in self.sections.iter().enumerate() {
-			tot_size += section.name.len() + 2 + 2;
-			for entry in section.entries.iter() {
-				tot_size += entry.key.len() + 1 + entry.val.len() + 2;
-			}
-			if idx < self.sections.len() - 1 {
-				tot_size += 2;
-			}
-		}
+		let mut buf = String::default();
 
-		let mut buf = String::with_capacity(tot_size);
-		for (idx, section) in self.sections.iter().enumerate() {
-			buf.push('[');
-			buf.push_str(&section.name);
-			buf.push_str("]\r\n");
-
-			for entry in section.entries.iter() {
-				buf.push_str(&entry.key);
-				buf.push('=');
-				buf.push_str(&entry.val);
-				buf.push_str("\r\n");
+		for section in self.sections.iter() {
+			if !section.is_headerless() {
+				match &section.layout {
+					Some(layout) => {
+						self.push_raw(&mut buf, &section.leading);
+						self.begin_line(&mut buf);
+						buf.push_str(&layout.indent);
+						buf.push('[');
+						buf.push_str(&layout.pad_left);
+						buf.push_str(&section.name);
+						buf.push_str(&layout.pad_right);
+						buf.push(']');
+						buf.push_str(&layout.trailing);
+						buf.push_str(&layout.eol);
+					},
+					None => {
+						self.push_raw(&mut buf, &section.leading);
+						if !buf.is_empty() { // blank line between sections
+							self.begin_line(&mut buf);
+							buf.push_str(&self.line_break);
+						}
+						buf.push('[');
+						buf.push_str(&section.name);
+						buf.push(']');
+						buf.push_str(&self.line_break);
+					},
+				}
 			}
 
-			if idx < self.sections.len() - 1 {
-				buf.push_str("\r\n");
+			for entry in section.entries.iter() {
+				self.push_raw(&mut buf, &entry.leading);
+				self.begin_line(&mut buf);
+				match &entry.layout {
+					Some(layout) => {
+						buf.push_str(&layout.indent);
+						buf.push_str(&entry.key);
+						buf.push_str(&layout.sep);
+						buf.push_str(&entry.val);
+						buf.push_str(&layout.trailing);
+						buf.push_str(&layout.eol);
+					},
+					None => {
+						buf.push_str(&entry.key);
+						buf.push('=');
+						buf.push_str(&entry.val);
+						buf.push_str(&self.line_break);
+					},
+				}
 			}
 		}
+
+		self.push_raw(&mut buf, &self.trailing);
 		buf
 	}
 
-	/// Serializes the data to raw bytes with
-	/// [`String::into_bytes`](https://doc.rust-lang.org/std/string/struct.String.html#method.into_bytes).
-	pub fn serialize_to_bytes(&self) -> Vec<u8> {
-		self.serialize_to_str().into_bytes()
+	/// Serializes the data to raw bytes, in the [`Encoding`](crate::Encoding)
+	/// returned by [`encoding`](crate::Ini::encoding), prepending the BOM if
+	/// [`has_bom`](crate::Ini::has_bom).
+	///
+	/// Fails with
+	/// [`ERROR::NO_UNICODE_TRANSLATION`](crate::co::ERROR::NO_UNICODE_TRANSLATION)
+	/// if a char cannot be represented in the encoding, or with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the encoding cannot be written, like SCSU and BOCU-1.
+	pub fn serialize_to_bytes(&self) -> WinResult<Vec<u8>> {
+		encode_str(&self.serialize_to_str(), self.encoding, self.with_bom)
 	}
 
 	/// Serializes the data directly to a file.
@@ -143,28 +517,811 @@ impl Ini {
 			co::FILE_SHARE::NoValue, None, co::DISPOSITION::CREATE_ALWAYS,
 			co::FILE_ATTRIBUTE::NORMAL, None)?;
 
-		fout.WriteFile(&self.serialize_to_bytes(), None)?;
+		fout.WriteFile(&self.serialize_to_bytes()?, None)?;
 		fout.CloseHandle()?;
 		Ok(())
 	}
 
-	/// Returns a reference to the specified value, if any.
+	/// Returns the [`Encoding`](crate::Encoding) used by
+	/// [`serialize_to_bytes`](crate::Ini::serialize_to_bytes) and
+	/// [`serialize_to_file`](crate::Ini::serialize_to_file).
+	///
+	/// This is the encoding detected when parsing from bytes or from a file,
+	/// or [`Encoding::Utf8`](crate::Encoding::Utf8) otherwise. ASCII-only data
+	/// is reported as UTF-8.
+	pub const fn encoding(&self) -> Encoding {
+		self.encoding
+	}
+
+	/// Tells whether a BOM will be written by
+	/// [`serialize_to_bytes`](crate::Ini::serialize_to_bytes) and
+	/// [`serialize_to_file`](crate::Ini::serialize_to_file), which is true if
+	/// the parsed data had one.
+	pub const fn has_bom(&self) -> bool {
+		self.with_bom
+	}
+
+	/// Sets the [`Encoding`](crate::Encoding) and whether a BOM will be
+	/// written by [`serialize_to_bytes`](crate::Ini::serialize_to_bytes) and
+	/// [`serialize_to_file`](crate::Ini::serialize_to_file).
+	///
+	/// [`Encoding::Ansi`](crate::Encoding::Ansi) accepts only ASCII chars.
+	/// Only UTF encodings have a BOM, so `with_bom` is ignored for
+	/// [`Encoding::Ansi`](crate::Encoding::Ansi) and
+	/// [`Encoding::Win1252`](crate::Encoding::Win1252).
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::{Encoding, Ini};
+	///
+	/// let mut fini = Ini::parse_from_file("C:\\Temp\\my_file.ini")?;
+	/// fini.set_encoding(Encoding::Utf16le, true);
+	/// fini.serialize_to_file("C:\\Temp\\my_file.ini")?;
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	pub fn set_encoding(&mut self, encoding: Encoding, with_bom: bool) {
+		self.encoding = encoding;
+		self.with_bom = with_bom;
+	}
+
+	/// Removes the given key from the section, returning its value, if the key
+	/// existed. Section and key names are case-insensitive.
+	///
+	/// Comments and blank lines which preceded the key are kept.
+	pub fn remove_key(&mut self, section: &str, key: &str) -> Option<String> {
+		let sec_idx = self.section_idx(section)?;
+		let entry_idx = self.sections[sec_idx].entry_idx(key)?;
+		let entry = self.sections[sec_idx].entries.remove(entry_idx);
+
+		let section = &mut self.sections[sec_idx];
+		match section.entries.get_mut(entry_idx) {
+			Some(next_entry) => next_entry.leading.insert_str(0, &entry.leading),
+			None => self.reattach_leading(sec_idx + 1, entry.leading),
+		}
+		Some(entry.val)
+	}
+
+	/// Removes the given section with all its entries, returning it, if the
+	/// section existed. The section name is case-insensitive.
+	///
+	/// Comments and blank lines which preceded the section header are kept.
+	pub fn remove_section(&mut self, section: &str) -> Option<IniSection> {
+		let sec_idx = self.section_idx(section)?;
+		let mut removed = self.sections.remove(sec_idx);
+		self.reattach_leading(sec_idx, std::mem::take(&mut removed.leading));
+		Some(removed)
+	}
+
+	/// Renames the given section, returning `false` if it doesn't exist. The
+	/// current section name is case-insensitive.
+	///
+	/// Renaming the entries before the first section header (empty section
+	/// name) gives them a header.
+	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the new name cannot be written to a section header, see
+	/// [`set_value`](crate::Ini::set_value), or with
+	/// [`ERROR::ALREADY_EXISTS`](crate::co::ERROR::ALREADY_EXISTS) if another
+	/// section already has the new name.
+	pub fn rename_section(&mut self,
+		section: &str, new_name: &str) -> WinResult<bool>
+	{
+		if new_name.is_empty() {
+			return Err(co::ERROR::INVALID_PARAMETER);
+		}
+		check_section_name(new_name)?;
+
+		let sec_idx = match self.section_idx(section) {
+			Some(idx) => idx,
+			None => return Ok(false),
+		};
+		if self.sections.iter().enumerate()
+			.any(|(idx, s)| idx != sec_idx && names_eq(&s.name, new_name))
+		{
+			return Err(co::ERROR::ALREADY_EXISTS);
+		}
+
+		let section = &mut self.sections[sec_idx];
+		if section.headerless {
+			section.headerless = false;
+			if let Some(first_entry) = section.entries.first_mut() {
+				// Comments before the first key now precede the new header.
+				section.leading.push_str(&std::mem::take(&mut first_entry.leading));
+			}
+		}
+		section.name = new_name.to_owned();
+		Ok(true)
+	}
+
+	/// Returns a reference to the specified section, if any. The section name is
+	/// case-insensitive.
+	pub fn section(&self, section: &str) -> Option<&IniSection> {
+		self.section_idx(section)
+			.map(|idx| &self.sections[idx])
+	}
+
+	/// Returns a mutable reference to the specified section, if any. The
+	/// section name is case-insensitive.
+	pub fn section_mut(&mut self, section: &str) -> Option<&mut IniSection> {
+		self.section_idx(section)
+			.map(move |idx| &mut self.sections[idx])
+	}
+
+	/// Sets a boolean value with [`set_value`](crate::Ini::set_value).
+	///
+	/// If the current value is already a boolean, its spelling is kept, so
+	/// `yes` becomes `no`, `1` becomes `0` and so on. Otherwise, `true` or
+	/// `false` is written.
+	pub fn set_bool(&mut self,
+		section: &str, key: &str, val: bool) -> WinResult<()>
+	{
+		let spelling = self.value(section, key)
+			.and_then(|cur| BOOL_SPELLINGS.iter()
+				.find(|(t, f)| cur.eq_ignore_ascii_case(t) || cur.eq_ignore_ascii_case(f)))
+			.unwrap_or(&BOOL_SPELLINGS[0]);
+		self.set_value(section, key, if val { spelling.0 } else { spelling.1 })
+	}
+
+	/// Sets a floating point value with [`set_value`](crate::Ini::set_value).
+	pub fn set_float(&mut self,
+		section: &str, key: &str, val: f64) -> WinResult<()>
+	{
+		self.set_value(section, key, &val.to_string())
+	}
+
+	/// Sets an integer value with [`set_value`](crate::Ini::set_value).
+	pub fn set_int(&mut self,
+		section: &str, key: &str, val: i64) -> WinResult<()>
+	{
+		self.set_value(section, key, &val.to_string())
+	}
+
+	/// Sets a list of values, joined with the given delimiter, with
+	/// [`set_value`](crate::Ini::set_value).
+	pub fn set_list(&mut self,
+		section: &str, key: &str,
+		vals: &[impl AsRef<str>], delimiter: char) -> WinResult<()>
+	{
+		let joined = vals.iter()
+			.map(|v| v.as_ref())
+			.collect::<Vec<_>>()
+			.join(&delimiter.to_string());
+		self.set_value(section, key, &joined)
+	}
+
+	/// Sets the value of the given key. Section and key names are
+	/// case-insensitive.
+	///
+	/// If the section doesn't exist, it's appended at the end of the file. If
+	/// the key doesn't exist, it's appended at the end of the section. An empty
+	/// section name refers to the entries before the first section header.
+	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the data would not be parsed back as written:
+	///
+	/// * any name or value with a line break, a null char, or leading or
+	///   trailing whitespace;
+	/// * a section name with `]`;
+	/// * an empty key, a key with `=`, or starting with `[`, `;` or `#`.
+	pub fn set_value(&mut self,
+		section: &str, key: &str, val: &str) -> WinResult<()>
+	{
+		check_section_name(section)?;
+		check_key(key)?;
+		check_value(val)?;
+
+		let sec_idx = match self.section_idx(section) {
+			Some(idx) => idx,
+			None => if section.is_empty() {
+				let mut root = IniSection::new("");
+				root.headerless = true;
+				self.sections.insert(0, root);
+				0
+			} else {
+				self.sections.push(IniSection::new(section));
+				self.sections.len() - 1
+			},
+		};
+
+		let section = &mut self.sections[sec_idx];
+		match section.entry_idx(key) {
+			Some(entry_idx) => section.entries[entry_idx].val = val.to_owned(),
+			None => section.entries.push(IniEntry::new(key, val)),
+		}
+		Ok(())
+	}
+
+	/// Returns a reference to the specified value, if any. Section and key
+	/// names are case-insensitive, like in
+	/// [`GetPrivateProfileString`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getprivateprofilestringw).
 	pub fn value(&self, section: &str, key: &str) -> Option<&str> {
-		self.sections.iter()
-			.find(|s| s.name == section)
-			.and_then(|s| s.entries.iter()
-				.find(|e| e.key == key)
-				.map(|e| e.val.as_ref())
+		self.section(section)
+			.and_then(|s| s.entry_idx(key)
+				.map(|idx| s.entries[idx].val.as_ref())
 			)
 	}
 
-	/// Returns a mutable reference to the specified value, if any.
+	/// Returns the specified value parsed as a boolean, if any.
+	///
+	/// Accepted values are `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0`,
+	/// case-insensitive. Returns `None` if the key doesn't exist or the value
+	/// isn't a boolean.
+	pub fn value_bool(&self, section: &str, key: &str) -> Option<bool> {
+		self.value(section, key)
+			.and_then(parse_bool)
+	}
+
+	/// Returns the specified value parsed as a floating point number, if any.
+	///
+	/// Returns `None` if the key doesn't exist or the value isn't a number.
+	pub fn value_float(&self, section: &str, key: &str) -> Option<f64> {
+		self.value(section, key)
+			.and_then(|val| val.parse().ok())
+	}
+
+	/// Returns the specified value parsed as an integer, if any. Hexadecimal
+	/// values with a `0x` prefix are also accepted.
+	///
+	/// Returns `None` if the key doesn't exist or the value isn't an integer.
+	pub fn value_int(&self, section: &str, key: &str) -> Option<i64> {
+		self.value(section, key)
+			.and_then(parse_int)
+	}
+
+	/// Returns the specified value split by the given delimiter, with each item
+	/// trimmed, if any. An empty value returns an empty `Vec`.
+	pub fn value_list(&self,
+		section: &str, key: &str, delimiter: char) -> Option<Vec<&str>>
+	{
+		self.value(section, key)
+			.map(|val| if val.is_empty() {
+				Vec::default()
+			} else {
+				val.split(delimiter).map(|item| item.trim()).collect()
+			})
+	}
+
+	/// Returns a mutable reference to the specified value, if any. Section and
+	/// key names are case-insensitive.
 	pub fn value_mut(&mut self, section: &str, key: &str) -> Option<&mut str> {
-		self.sections.iter_mut()
-			.find(|s| s.name == section)
-			.and_then(|s| s.entries.iter_mut()
-				.find(|e| e.key == key)
-				.map(|e| e.val.as_mut())
+		self.section_mut(section)
+			.and_then(|s| s.entry_idx(key)
+				.map(move |idx| s.entries[idx].val.as_mut())
 			)
 	}
+
+	/// Returns an iterator over all the values of the given key, in all the
+	/// sections with the given name. Section and key names are
+	/// case-insensitive.
+	///
+	/// This is useful when duplicated keys are kept, like with
+	/// [`IniDuplicates::Merge`](crate::IniDuplicates::Merge).
+	pub fn values<'a>(&'a self,
+		section: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> + 'a
+	{
+		self.sections.iter()
+			.filter(move |s| names_eq(&s.name, section))
+			.flat_map(move |s| s.entries.iter()
+				.filter(move |e| names_eq(&e.key, key))
+				.map(|e| e.val.as_ref())
+			)
+	}
+
+	fn section_idx(&self, section: &str) -> Option<usize> {
+		self.sections.iter()
+			.position(|s| names_eq(&s.name, section))
+	}
+
+	/// Moves comments and blank lines of a removed element to the element
+	/// which now occupies its place.
+	fn reattach_leading(&mut self, sec_idx: usize, leading: String) {
+		if leading.is_empty() {
+			return;
+		}
+		match self.sections.get_mut(sec_idx) {
+			Some(section) => match (section.headerless, section.entries.first_mut()) {
+				(true, Some(first_entry)) => first_entry.leading.insert_str(0, &leading),
+				_ => section.leading.insert_str(0, &leading),
+			},
+			None => self.trailing.insert_str(0, &leading),
+		}
+	}
+
+	fn parse_section_line<'a>(line: &'a str, eol: &str) -> Option<(&'a str, SectionLayout)> {
+		let body = line.trim_start();
+		if !body.starts_with('[') {
+			return None;
+		}
+		let close_idx = body.find(']')?;
+		let trailing = &body[close_idx + 1..];
+		if !is_blank_or_comment(trailing) {
+			return None; // garbage after the closing bracket
+		}
+
+		let inner = &body[1..close_idx];
+		let name = inner.trim();
+		let name_idx = inner.len() - inner.trim_start().len();
+
+		Some((name, SectionLayout {
+			indent: line[..line.len() - body.len()].to_owned(),
+			pad_left: inner[..name_idx].to_owned(),
+			pad_right: inner[name_idx + name.len()..].to_owned(),
+			trailing: trailing.to_owned(),
+			eol: eol.to_owned(),
+		}))
+	}
+
+	fn section_line_error(line_no: usize, line: &str) -> IniError {
+		let open_idx = line.find('[').unwrap();
+		match line.find(']') {
+			None => IniError::new(line_no, line, open_idx,
+				IniErrorKind::UnterminatedSection),
+			Some(close_idx) => {
+				let after = &line[close_idx + 1..];
+				IniError::new(line_no, line,
+					close_idx + 1 + after.len() - after.trim_start().len(),
+					IniErrorKind::TextAfterSection)
+			},
+		}
+	}
+
+	fn parse_entry_line(line: &str, eol: &str, inline_comments: bool) -> Option<IniEntry> {
+		if is_blank_or_comment(line) {
+			return None;
+		}
+		let eq_idx = line.find('=')?;
+		let (key_part, val_part) = (&line[..eq_idx], &line[eq_idx + 1..]);
+
+		let key = key_part.trim();
+		let indent = &key_part[..key_part.len() - key_part.trim_start().len()];
+
+		let comment_idx = inline_comments.then(|| inline_comment_idx(val_part))
+			.flatten()
+			.unwrap_or(val_part.len());
+		let val_region = &val_part[..comment_idx];
+		let val = val_region.trim();
+		let val_idx = val_region.len() - val_region.trim_start().len();
+
+		Some(IniEntry {
+			key: key.to_owned(),
+			val: val.to_owned(),
+			leading: String::default(),
+			layout: Some(EntryLayout {
+				indent: indent.to_owned(),
+				sep: format!("{}={}",
+					&key_part[indent.len() + key.len()..],
+					&val_region[..val_idx]),
+				trailing: val_part[val_idx + val.len()..].to_owned(),
+				eol: eol.to_owned(),
+			}),
+		})
+	}
+
+	/// Ensures the next text starts on a new line, in case the last parsed
+	/// line had no line break.
+	fn begin_line(&self, buf: &mut String) {
+		if !buf.is_empty() && !buf.ends_with('\n') {
+			buf.push_str(&self.line_break);
+		}
+	}
+
+	fn push_raw(&self, buf: &mut String, raw: &str) {
+		if !raw.is_empty() {
+			self.begin_line(buf);
+			buf.push_str(raw);
+		}
+	}
+}
+
+/// Accepted spellings of boolean values, as true/false pairs.
+const BOOL_SPELLINGS: [(&str, &str); 4] = [
+	("true", "false"), ("yes", "no"), ("on", "off"), ("1", "0"),
+];
+
+/// Parses a boolean value, accepting any of the `BOOL_SPELLINGS`.
+pub(super) fn parse_bool(val: &str) -> Option<bool> {
+	BOOL_SPELLINGS.iter()
+		.find_map(|(t, f)| if val.eq_ignore_ascii_case(t) {
+			Some(true)
+		} else if val.eq_ignore_ascii_case(f) {
+			Some(false)
+		} else {
+			None
+		})
+}
+
+/// Parses an integer value, which can be hexadecimal with a `0x` prefix.
+pub(super) fn parse_int(val: &str) -> Option<i64> {
+	let (neg, digits) = match val.strip_prefix('-') {
+		Some(digits) => (true, digits),
+		None => (false, val.strip_prefix('+').unwrap_or(val)),
+	};
+	let num = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+		Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+		None => digits.parse::<i64>().ok()?,
+	};
+	Some(if neg { -num } else { num })
+}
+
+/// Case-insensitive comparison of section and key names.
+pub(super) fn names_eq(a: &str, b: &str) -> bool {
+	a.eq_ignore_ascii_case(b)
+		|| a.chars().flat_map(char::to_lowercase)
+			.eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Validates a section name to be written by the editing methods.
+pub(super) fn check_section_name(name: &str) -> WinResult<()> {
+	if has_line_break_or_padding(name) || name.contains(']') {
+		Err(co::ERROR::INVALID_PARAMETER)
+	} else {
+		Ok(())
+	}
+}
+
+/// Validates a key to be written by the editing methods.
+pub(super) fn check_key(key: &str) -> WinResult<()> {
+	if key.is_empty()
+		|| has_line_break_or_padding(key)
+		|| key.contains('=')
+		|| key.starts_with(['[', ';', '#'])
+	{
+		Err(co::ERROR::INVALID_PARAMETER)
+	} else {
+		Ok(())
+	}
+}
+
+/// Validates a value to be written by the editing methods.
+pub(super) fn check_value(val: &str) -> WinResult<()> {
+	if has_line_break_or_padding(val) {
+		Err(co::ERROR::INVALID_PARAMETER)
+	} else {
+		Ok(())
+	}
+}
+
+/// Tells whether the text has chars which would break its line, or leading or
+/// trailing whitespace, which is trimmed when parsing.
+fn has_line_break_or_padding(text: &str) -> bool {
+	text.contains(['\r', '\n', '\0']) || text.trim() != text
+}
+
+/// Tells whether the line is empty, whitespace only, or a `;` or `#` comment.
+fn is_blank_or_comment(line: &str) -> bool {
+	let line = line.trim_start();
+	line.is_empty() || line.starts_with(';') || line.starts_with('#')
+}
+
+/// Returns the index of the `;` or `#` which starts an inline comment, which
+/// must be preceded by whitespace.
+fn inline_comment_idx(val_part: &str) -> Option<usize> {
+	let mut prev_is_space = false;
+	for (idx, ch) in val_part.char_indices() {
+		if prev_is_space && (ch == ';' || ch == '#') {
+			return Some(idx);
+		}
+		prev_is_space = ch.is_whitespace();
+	}
+	None
+}
+
+//------------------------------------------------------------------------------
+
+/// Iterates over the lines of a string, returning each line and its line
+/// break, which can be `\r\n`, `\n`, or empty for the last line.
+struct LineIter<'a> {
+	text: &'a str,
+}
+
+impl<'a> Iterator for LineIter<'a> {
+	type Item = (&'a str, &'a str);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.text.is_empty() {
+			return None;
+		}
+
+		let (line, eol, rest) = match self.text.find('\n') {
+			Some(idx) => {
+				let line_end = if self.text[..idx].ends_with('\r') { idx - 1 } else { idx };
+				(&self.text[..line_end], &self.text[line_end..idx + 1], &self.text[idx + 1..])
+			},
+			None => (self.text, "", &self.text[self.text.len()..]),
+		};
+		self.text = rest;
+		Some((line, eol))
+	}
+}
+
+impl<'a> LineIter<'a> {
+	fn new(text: &'a str) -> Self {
+		Self { text }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SAMPLE: &str = concat!(
+		"; leading comment\r\n",
+		"orphan = before sections\r\n",
+		"\r\n",
+		"  [ Fruits ]  ; trailing comment\r\n",
+		"banana=yellow\r\n",
+		"\t apple  =  red  \r\n",
+		"# hash comment\r\n",
+		"not a pair\r\n",
+		"[Empty]\r\n",
+		"\r\n",
+		"; last comment",
+	);
+
+	#[test]
+	fn round_trip_unmodified() {
+		let ini = Ini::parse_str(SAMPLE);
+		assert_eq!(ini.serialize_to_str(), SAMPLE);
+
+		let lf = SAMPLE.replace("\r\n", "\n");
+		assert_eq!(Ini::parse_str(&lf).serialize_to_str(), lf);
+		assert_eq!(Ini::parse_str("a=1").serialize_to_str(), "a=1");
+		assert_eq!(Ini::parse_str("").serialize_to_str(), "");
+	}
+
+	#[test]
+	fn parsed_values() {
+		let ini = Ini::parse_str(SAMPLE);
+		assert_eq!(ini.value("", "orphan"), Some("before sections"));
+		assert_eq!(ini.value("fruits", "APPLE"), Some("red"));
+		assert_eq!(ini.section("Empty").map(|s| s.entries.len()), Some(0));
+	}
+
+	#[test]
+	fn modify_keeps_layout() {
+		let mut ini = Ini::parse_str(SAMPLE);
+		ini.set_value("Fruits", "apple", "green").unwrap();
+		assert_eq!(ini.serialize_to_str(),
+			SAMPLE.replace("\t apple  =  red  ", "\t apple  =  green  "));
+	}
+
+	#[test]
+	fn comment_chars_in_values() {
+		let mut ini = Ini::default();
+		ini.set_value("a", "k", "Episode #1").unwrap();
+		ini.set_value("a", "k2", "x ; y").unwrap();
+		let ini = Ini::parse_str(&ini.serialize_to_str());
+		assert_eq!(ini.value("a", "k"), Some("Episode #1"));
+		assert_eq!(ini.value("a", "k2"), Some("x ; y"));
+	}
+
+	#[test]
+	fn inline_comments_option() {
+		let text = "[a]\r\nk = v ; note\r\nk2 = v#not a comment\r\n";
+		let opts = IniParseOptions { inline_comments: true, ..Default::default() };
+
+		let ini = Ini::parse_str_with(text, &opts).unwrap();
+		assert_eq!(ini.value("a", "k"), Some("v"));
+		assert_eq!(ini.value("a", "k2"), Some("v#not a comment"));
+		assert_eq!(ini.serialize_to_str(), text);
+
+		let mut ini = ini;
+		ini.set_value("a", "k", "w").unwrap();
+		assert_eq!(ini.serialize_to_str(), text.replace("v ; note", "w ; note"));
+
+		let ini = Ini::parse_str(text);
+		assert_eq!(ini.value("a", "k"), Some("v ; note"));
+	}
+
+	#[test]
+	fn strict_errors() {
+		let strict = IniParseOptions { strict: true, ..Default::default() };
+		let err = |text: &str| Ini::parse_str_with(text, &strict).unwrap_err();
+
+		assert_eq!(err("[a]\r\nk=v\r\n  [abc\r\n"),
+			IniError { line: 3, column: 3, kind: IniErrorKind::UnterminatedSection });
+		assert_eq!(err("[ä] x\r\n"), // column counts chars, not bytes
+			IniError { line: 1, column: 5, kind: IniErrorKind::TextAfterSection });
+		assert_eq!(err("; ok\r\n\r\n\tno pair\r\n"),
+			IniError { line: 3, column: 2, kind: IniErrorKind::MissingEquals });
+		assert_eq!(err("[a]\r\nk=v\r\né =v\r\n = v\r\n"),
+			IniError { line: 4, column: 2, kind: IniErrorKind::EmptyKey });
+
+		let lenient = "[a]\r\n  [abc\r\n[b] x\r\nno pair\r\n=v\r\n";
+		assert!(Ini::parse_str_with(lenient, &strict).is_err());
+		let ini = Ini::parse_str(lenient);
+		assert_eq!(ini.serialize_to_str(), lenient);
+		assert_eq!(ini.value("a", ""), Some("v"));
+	}
+
+	const DUPLICATES: &str = "[a]\nk=1\nk=2\n[b]\nz=0\n[A]\nk=3\nj=4\n";
+
+	fn parse_duplicates(duplicates: IniDuplicates) -> Result<Ini, IniError> {
+		Ini::parse_str_with(DUPLICATES,
+			&IniParseOptions { duplicates, ..Default::default() })
+	}
+
+	#[test]
+	fn duplicates_keep_all() {
+		let ini = parse_duplicates(IniDuplicates::KeepAll).unwrap();
+		assert_eq!(ini.sections.len(), 3);
+		assert_eq!(ini.value("a", "k"), Some("1"));
+		assert_eq!(ini.values("a", "k").collect::<Vec<_>>(), ["1", "2", "3"]);
+		assert_eq!(ini.serialize_to_str(), DUPLICATES);
+	}
+
+	#[test]
+	fn duplicates_first_wins() {
+		let ini = parse_duplicates(IniDuplicates::FirstWins).unwrap();
+		assert_eq!(ini.sections.len(), 2);
+		assert_eq!(ini.values("a", "k").collect::<Vec<_>>(), ["1"]);
+		assert_eq!(ini.value("a", "j"), Some("4"));
+		assert_eq!(ini.serialize_to_str(), "[a]\nk=1\nj=4\n[b]\nz=0\n");
+	}
+
+	#[test]
+	fn duplicates_last_wins() {
+		let ini = parse_duplicates(IniDuplicates::LastWins).unwrap();
+		assert_eq!(ini.sections.len(), 2);
+		assert_eq!(ini.values("a", "k").collect::<Vec<_>>(), ["3"]);
+		assert_eq!(ini.serialize_to_str(), "[a]\nk=3\nj=4\n[b]\nz=0\n");
+	}
+
+	#[test]
+	fn duplicates_merge() {
+		let ini = parse_duplicates(IniDuplicates::Merge).unwrap();
+		assert_eq!(ini.sections.len(), 2);
+		assert_eq!(ini.value("a", "k"), Some("1"));
+		assert_eq!(ini.values("a", "k").collect::<Vec<_>>(), ["1", "2", "3"]);
+		assert_eq!(ini.serialize_to_str(), "[a]\nk=1\nk=2\nk=3\nj=4\n[b]\nz=0\n");
+	}
+
+	#[test]
+	fn duplicates_reject() {
+		assert_eq!(parse_duplicates(IniDuplicates::Reject).unwrap_err(), IniError {
+			line: 3, column: 1, kind: IniErrorKind::DuplicateKey("k".to_owned()),
+		});
+
+		let reject = IniParseOptions { duplicates: IniDuplicates::Reject, ..Default::default() };
+		assert_eq!(Ini::parse_str_with("[a]\nk=1\n[b]\n  [A]\n", &reject).unwrap_err(), IniError {
+			line: 4, column: 3, kind: IniErrorKind::DuplicateSection("A".to_owned()),
+		});
+		assert_eq!(Ini::parse_str_with("[a]\nk=1\n[b]\n\tK = 2\n", &reject).unwrap().value("b", "k"),
+			Some("2")); // same key in another section is fine
+	}
+
+	#[test]
+	fn edit_values() {
+		let mut ini = Ini::parse_str("; top\r\n[A]\r\nx=1\r\n; about y\r\ny=yes\r\n");
+		ini.set_value("a", "X", "2").unwrap();
+		ini.set_bool("A", "y", false).unwrap();
+		ini.set_int("B", "n", -0x10).unwrap();
+		ini.set_list("B", "l", &["p", "q"], ',').unwrap();
+		ini.set_value("", "root", "r").unwrap();
+
+		assert_eq!(ini.value_int("A", "x"), Some(2));
+		assert_eq!(ini.value("a", "y"), Some("no")); // spelling kept
+		assert_eq!(ini.value_int("b", "N"), Some(-16));
+		assert_eq!(ini.value_list("B", "l", ','), Some(vec!["p", "q"]));
+		assert_eq!(ini.serialize_to_str(),
+			"root=r\r\n; top\r\n[A]\r\nx=2\r\n; about y\r\ny=no\r\n\r\n[B]\r\nn=-16\r\nl=p,q\r\n");
+
+		assert_eq!(ini.remove_key("a", "x"), Some("2".to_owned()));
+		assert_eq!(ini.remove_key("a", "x"), None);
+		assert!(ini.rename_section("b", "C").unwrap());
+		assert!(!ini.rename_section("nope", "D").unwrap());
+		assert!(ini.remove_section("c").is_some());
+		assert_eq!(ini.serialize_to_str(),
+			"root=r\r\n; top\r\n[A]\r\n; about y\r\ny=no\r\n");
+	}
+
+	#[test]
+	fn edit_rejects_injection() {
+		let mut ini = Ini::default();
+		let bad = co::ERROR::INVALID_PARAMETER;
+		assert_eq!(ini.set_value("a", "k", "x\r\n[evil]\r\nadmin=1"), Err(bad));
+		assert_eq!(ini.set_value("a", "k", "x\ny"), Err(bad));
+		assert_eq!(ini.set_value("a", "k", "x\0"), Err(bad));
+		assert_eq!(ini.set_value("a", "k", " padded"), Err(bad));
+		assert_eq!(ini.set_value("a]\r\n[b", "k", "v"), Err(bad));
+		assert_eq!(ini.set_value("a]", "k", "v"), Err(bad));
+		assert_eq!(ini.set_value("a", "k=v", "v"), Err(bad));
+		assert_eq!(ini.set_value("a", "", "v"), Err(bad));
+		assert_eq!(ini.set_value("a", ";k", "v"), Err(bad));
+		assert_eq!(ini.set_value("a", "[k", "v"), Err(bad));
+		assert_eq!(ini.set_value("a", "k\r", "v"), Err(bad));
+		assert_eq!(ini.rename_section("a", "b]"), Err(bad));
+		assert!(ini.sections.is_empty());
+
+		ini.set_value("a", "k", "v=w [x] ; y").unwrap();
+		let ini = Ini::parse_str(&ini.serialize_to_str());
+		assert_eq!(ini.sections.len(), 1);
+		assert_eq!(ini.value("a", "k"), Some("v=w [x] ; y"));
+	}
+
+	#[test]
+	fn rename_headerless_root() {
+		let mut ini = Ini::parse_str("; top\r\nk=v\r\n[A]\r\nx=1\r\n");
+		assert!(ini.rename_section("", "Root").unwrap());
+		assert_eq!(ini.serialize_to_str(), "; top\r\n\r\n[Root]\r\nk=v\r\n[A]\r\nx=1\r\n");
+
+		let ini = Ini::parse_str(&ini.serialize_to_str());
+		assert_eq!(ini.value("root", "k"), Some("v"));
+		assert!(ini.section("").is_none());
+	}
+
+	#[test]
+	fn rename_onto_existing() {
+		let mut ini = Ini::parse_str("[A]\r\nx=1\r\n[B]\r\ny=2\r\n");
+		assert_eq!(ini.rename_section("a", "b"), Err(co::ERROR::ALREADY_EXISTS));
+		assert_eq!(ini.value("A", "x"), Some("1"));
+		assert_eq!(ini.sections.len(), 2);
+
+		assert!(ini.rename_section("a", "a").unwrap()); // only the case changes
+		assert_eq!(ini.serialize_to_str(), "[a]\r\nx=1\r\n[B]\r\ny=2\r\n");
+	}
+
+	#[test]
+	fn encoding_utf16le_bom() {
+		let text = "; notes\r\n[Fruits]\r\napple = maçã 🍎\r\n";
+		let bytes = encode_str(text, Encoding::Utf16le, true).unwrap();
+		let mut ini = Ini::parse_bytes(&bytes).unwrap();
+		assert_eq!((ini.encoding(), ini.has_bom()), (Encoding::Utf16le, true));
+		assert_eq!(ini.value("fruits", "apple"), Some("maçã 🍎"));
+		assert_eq!(ini.serialize_to_bytes().unwrap(), bytes);
+
+		ini.set_value("Fruits", "apple", "pêra").unwrap();
+		assert_eq!(ini.serialize_to_bytes().unwrap(),
+			encode_str(&text.replace("maçã 🍎", "pêra"), Encoding::Utf16le, true).unwrap());
+	}
+
+	#[test]
+	fn encoding_utf8_no_bom() {
+		let bytes = "[Städte]\nname=Zürich\n".as_bytes();
+		let ini = Ini::parse_bytes(bytes).unwrap();
+		assert_eq!((ini.encoding(), ini.has_bom()), (Encoding::Utf8, false));
+		assert_eq!(ini.value("städte", "name"), Some("Zürich"));
+		assert_eq!(ini.serialize_to_bytes().unwrap(), bytes);
+	}
+
+	#[test]
+	fn encoding_win1252() {
+		let bytes = b"[a]\r\nprice=5 \x80\r\nname=caf\xe9\r\n";
+		let mut ini = Ini::parse_bytes(bytes).unwrap();
+		assert_eq!((ini.encoding(), ini.has_bom()), (Encoding::Win1252, false));
+		assert_eq!(ini.value("a", "price"), Some("5 €"));
+		assert_eq!(ini.value("a", "name"), Some("café"));
+		assert_eq!(ini.serialize_to_bytes().unwrap(), bytes);
+
+		ini.set_value("a", "name", "naïve").unwrap();
+		assert_eq!(ini.serialize_to_bytes().unwrap(), b"[a]\r\nprice=5 \x80\r\nname=na\xefve\r\n");
+		ini.set_value("a", "name", "日本").unwrap();
+		assert_eq!(ini.serialize_to_bytes(), Err(co::ERROR::NO_UNICODE_TRANSLATION));
+	}
+
+	#[test]
+	fn set_encoding_changes_output() {
+		let text = "[a]\r\nk=é\r\n";
+		let mut ini = Ini::parse_bytes(&encode_str(text, Encoding::Utf16le, true).unwrap())
+			.unwrap();
+
+		ini.set_encoding(Encoding::Utf8, false);
+		assert_eq!(ini.serialize_to_bytes().unwrap(), text.as_bytes());
+		ini.set_encoding(Encoding::Utf16be, true);
+		assert_eq!(ini.serialize_to_bytes().unwrap(),
+			[0xfe, 0xff, 0, b'[', 0, b'a', 0, b']', 0, b'\r', 0, b'\n', 0, b'k', 0, b'=', 0, 0xe9, 0, b'\r', 0, b'\n']);
+		ini.set_encoding(Encoding::Win1252, true); // no BOM in Win1252
+		assert_eq!(ini.serialize_to_bytes().unwrap(), b"[a]\r\nk=\xe9\r\n");
+	}
+
+	#[test]
+	fn parse_bytes_unpaired_surrogate() {
+		let ini = Ini::parse_bytes(&[0xff, 0xfe, b'[', 0x00, b'a', 0x00, b']', 0x00, 0x00, 0xdc])
+			.unwrap();
+		assert_eq!(ini.serialize_to_str(), "[a]\u{fffd}"); // kept as a malformed line
+		assert_eq!(ini.encoding(), Encoding::Utf16le);
+	}
 }
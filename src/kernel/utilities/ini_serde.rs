@@ -0,0 +1,986 @@
+//! [Serde](https://serde.rs) serialization and deserialization of `.ini`
+//! files, on top of the [`Ini`](crate::Ini) parser.
+//!
+//! Data is mapped as follows:
+//!
+//! * the top-level value must be a struct or a map;
+//! * top-level fields which are structs or maps become sections, and their
+//!   fields become the keys of the section;
+//! * other top-level fields become keys placed before the first section
+//!   header;
+//! * `Option` fields are `None` when the key is absent or its value is empty;
+//!   when serializing, `None` keys are not written, and `Some` with an empty
+//!   value fails, since it would be read back as `None`;
+//! * `Vec` and other sequences are comma-separated lists; a list with a single
+//!   empty item fails to serialize, since it would be read back as an empty
+//!   list;
+//! * enums are written with the names of their unit variants.
+//!
+//! Since values are trimmed when parsed, serializing a name or a value which
+//! would not be read back as written – with line breaks, null chars, or leading
+//! or trailing whitespace – fails.
+//!
+//! Section and key names are matched case-insensitively against the struct
+//! fields.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use serde::{Deserialize, Serialize};
+//! use winsafe::ini_serde;
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Config {
+//!     title: String,
+//!     window: Window,
+//! }
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Window {
+//!     width: u32,
+//!     height: u32,
+//!     maximized: Option<bool>,
+//!     recent: Vec<String>,
+//! }
+//!
+//! let cfg: Config = ini_serde::from_str(
+//!     "title=Foo\r\n[Window]\r\nwidth=800\r\nheight=600\r\nrecent=a.txt,b.txt",
+//! )?;
+//!
+//! let text = ini_serde::to_string(&cfg)?;
+//! # Ok::<_, ini_serde::Error>(())
+//! ```
+
+use serde::{de, ser};
+use serde::de::IntoDeserializer;
+
+use crate::kernel::decl::{Ini, IniSection};
+use super::ini::{check_section_name, check_value, names_eq, parse_bool,
+	parse_int};
+
+/// Error returned by the [`ini_serde`](crate::ini_serde) functions.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error(String);
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+impl de::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Self(msg.to_string())
+	}
+}
+
+impl ser::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Self(msg.to_string())
+	}
+}
+
+/// Deserializes a value from the contents of an `.ini` file, which is parsed
+/// with [`Ini::parse_str`](crate::Ini::parse_str).
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+pub fn from_str<T: de::DeserializeOwned>(contents: &str) -> Result<T, Error> {
+	from_ini(&Ini::parse_str(contents))
+}
+
+/// Deserializes a value from an already parsed [`Ini`](crate::Ini).
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+pub fn from_ini<'a, T: de::Deserialize<'a>>(ini: &'a Ini) -> Result<T, Error> {
+	T::deserialize(IniDeserializer { ini })
+}
+
+/// Serializes a value into a new [`Ini`](crate::Ini).
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+pub fn to_ini<T: ser::Serialize + ?Sized>(value: &T) -> Result<Ini, Error> {
+	let mut ini = Ini::default();
+	update_ini(&mut ini, value)?;
+	Ok(ini)
+}
+
+/// Serializes a value into the contents of an `.ini` file.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+pub fn to_string<T: ser::Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+	to_ini(value).map(|ini| ini.serialize_to_str())
+}
+
+/// Serializes a value into an existing [`Ini`](crate::Ini), setting the keys
+/// with [`Ini::set_value`](crate::Ini::set_value). Comments and other keys
+/// are kept, and `None` values remove their keys.
+///
+/// This is useful to save a struct back to the file it was read from.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "kernel", feature = "serde"))))]
+pub fn update_ini<T: ser::Serialize + ?Sized>(
+	ini: &mut Ini, value: &T) -> Result<(), Error>
+{
+	value.serialize(IniSerializer { ini })
+}
+
+fn invalid_value(val: &str, expected: &str) -> Error {
+	Error(format!("invalid value \"{}\", expected {}", val, expected))
+}
+
+//------------------------------------------------------------------------------
+
+/// Deserializes the whole `Ini` as a map.
+struct IniDeserializer<'a> {
+	ini: &'a Ini,
+}
+
+impl<'de> de::Deserializer<'de> for IniDeserializer<'de> {
+	type Error = Error;
+
+	fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_map(NodeMapAccess::top_level(self.ini, &[]))
+	}
+
+	fn deserialize_struct<V: de::Visitor<'de>>(self,
+		_: &'static str,
+		fields: &'static [&'static str],
+		visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_map(NodeMapAccess::top_level(self.ini, fields))
+	}
+
+	serde::forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map enum identifier ignored_any
+	}
+}
+
+/// A value being deserialized: either the value of a key, or a whole section.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+	Value(&'a str),
+	Section(&'a IniSection),
+}
+
+macro_rules! deserialize_int {
+	($method:ident, $visit:ident, $ty:ty) => {
+		fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+			let val = self.value()?;
+			let num = val.parse::<$ty>().ok()
+				.or_else(|| parse_int(val).and_then(|n| <$ty>::try_from(n).ok()))
+				.ok_or_else(|| invalid_value(val, stringify!($ty)))?;
+			visitor.$visit(num)
+		}
+	};
+}
+
+macro_rules! deserialize_float {
+	($method:ident, $visit:ident, $ty:ty) => {
+		fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+			let val = self.value()?;
+			visitor.$visit(
+				val.parse::<$ty>()
+					.map_err(|_| invalid_value(val, stringify!($ty)))?,
+			)
+		}
+	};
+}
+
+impl<'de> de::Deserializer<'de> for Node<'de> {
+	type Error = Error;
+
+	fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self {
+			Self::Value(val) => visitor.visit_borrowed_str(val),
+			Self::Section(section) => visitor.visit_map(NodeMapAccess::section(section, &[])),
+		}
+	}
+
+	fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let val = self.value()?;
+		visitor.visit_bool(
+			parse_bool(val).ok_or_else(|| invalid_value(val, "a boolean"))?,
+		)
+	}
+
+	deserialize_int!(deserialize_i8, visit_i8, i8);
+	deserialize_int!(deserialize_i16, visit_i16, i16);
+	deserialize_int!(deserialize_i32, visit_i32, i32);
+	deserialize_int!(deserialize_i64, visit_i64, i64);
+	deserialize_int!(deserialize_u8, visit_u8, u8);
+	deserialize_int!(deserialize_u16, visit_u16, u16);
+	deserialize_int!(deserialize_u32, visit_u32, u32);
+	deserialize_int!(deserialize_u64, visit_u64, u64);
+	deserialize_float!(deserialize_f32, visit_f32, f32);
+	deserialize_float!(deserialize_f64, visit_f64, f64);
+
+	fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let val = self.value()?;
+		let mut chars = val.chars();
+		match (chars.next(), chars.next()) {
+			(Some(ch), None) => visitor.visit_char(ch),
+			_ => Err(invalid_value(val, "a single char")),
+		}
+	}
+
+	fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_borrowed_str(self.value()?)
+	}
+
+	fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_borrowed_bytes(self.value()?.as_bytes())
+	}
+
+	fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self {
+			Self::Value("") => visitor.visit_none(),
+			_ => visitor.visit_some(self),
+		}
+	}
+
+	fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		match self.value()? {
+			"" => visitor.visit_unit(),
+			val => Err(invalid_value(val, "an empty value")),
+		}
+	}
+
+	fn deserialize_unit_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_unit(visitor)
+	}
+
+	fn deserialize_newtype_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		let val = self.value()?;
+		let items = val.split(',')
+			.filter(|_| !val.is_empty()) // empty value is an empty list
+			.map(|item| Node::Value(item.trim()));
+
+		let mut seq = de::value::SeqDeserializer::new(items);
+		let ret = visitor.visit_seq(&mut seq)?;
+		seq.end()?;
+		Ok(ret)
+	}
+
+	fn deserialize_tuple<V: de::Visitor<'de>>(self,
+		_: usize, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V: de::Visitor<'de>>(self,
+		_: &'static str, _: usize, visitor: V) -> Result<V::Value, Error>
+	{
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_map(NodeMapAccess::section(self.section()?, &[]))
+	}
+
+	fn deserialize_struct<V: de::Visitor<'de>>(self,
+		_: &'static str,
+		fields: &'static [&'static str],
+		visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_map(NodeMapAccess::section(self.section()?, fields))
+	}
+
+	fn deserialize_enum<V: de::Visitor<'de>>(self,
+		_: &'static str,
+		_: &'static [&'static str],
+		visitor: V) -> Result<V::Value, Error>
+	{
+		visitor.visit_enum(
+			IntoDeserializer::<Error>::into_deserializer(self.value()?),
+		)
+	}
+
+	fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+		visitor.visit_unit()
+	}
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Node<'de> {
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self::Deserializer {
+		self
+	}
+}
+
+impl<'a> Node<'a> {
+	fn value(self) -> Result<&'a str, Error> {
+		match self {
+			Self::Value(val) => Ok(val),
+			Self::Section(section) => Err(Error(
+				format!("expected a value, found section \"{}\"", section.name))),
+		}
+	}
+
+	fn section(self) -> Result<&'a IniSection, Error> {
+		match self {
+			Self::Value(val) => Err(invalid_value(val, "a section")),
+			Self::Section(section) => Ok(section),
+		}
+	}
+}
+
+/// Iterates over key/node pairs, translating the names to the struct fields,
+/// if any.
+struct NodeMapAccess<'a> {
+	items: std::vec::IntoIter<(&'a str, Node<'a>)>,
+	fields: &'static [&'static str],
+	cur_node: Option<Node<'a>>,
+}
+
+impl<'de> de::MapAccess<'de> for NodeMapAccess<'de> {
+	type Error = Error;
+
+	fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self,
+		seed: K) -> Result<Option<K::Value>, Error>
+	{
+		match self.items.next() {
+			None => Ok(None),
+			Some((name, node)) => {
+				self.cur_node = Some(node);
+				let name: &'de str = self.fields.iter()
+					.find(|field| names_eq(field, name))
+					.map_or(name, |field| field);
+				seed.deserialize(de::value::BorrowedStrDeserializer::new(name))
+					.map(Some)
+			},
+		}
+	}
+
+	fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self,
+		seed: V) -> Result<V::Value, Error>
+	{
+		seed.deserialize(
+			self.cur_node.take()
+				.ok_or_else(|| Error("value requested before key".to_owned()))?,
+		)
+	}
+}
+
+impl<'a> NodeMapAccess<'a> {
+	/// Keys before the first section header, followed by all the sections.
+	fn top_level(ini: &'a Ini, fields: &'static [&'static str]) -> Self {
+		let items = ini.sections.iter()
+			.flat_map(|section| if section.is_headerless() {
+				section.entries.iter()
+					.map(|e| (e.key.as_str(), Node::Value(&e.val)))
+					.collect()
+			} else {
+				vec![(section.name.as_str(), Node::Section(section))]
+			})
+			.collect::<Vec<_>>();
+		Self { items: items.into_iter(), fields, cur_node: None }
+	}
+
+	fn section(section: &'a IniSection, fields: &'static [&'static str]) -> Self {
+		let items = section.entries.iter()
+			.map(|e| (e.key.as_str(), Node::Value(&e.val)))
+			.collect::<Vec<_>>();
+		Self { items: items.into_iter(), fields, cur_node: None }
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// A serialized value, to be written to the `Ini`.
+enum Field {
+	Absent,
+	Bool(bool),
+	Text(String),
+	Section(Vec<(String, Field)>),
+}
+
+/// Where a value is being serialized, which restricts the accepted types.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Level {
+	/// A top-level field, which can be a section.
+	TopField,
+	/// A key of a section.
+	Entry,
+	/// An item of a list.
+	Item,
+}
+
+macro_rules! serialize_unsupported {
+	($msg:expr; $( $method:ident($ty:ty) )*) => {
+		$(
+			fn $method(self, _: $ty) -> Result<Self::Ok, Error> {
+				Err(Error($msg.to_owned()))
+			}
+		)*
+	};
+}
+
+macro_rules! serialize_display {
+	($( $method:ident($ty:ty) )*) => {
+		$(
+			fn $method(self, v: $ty) -> Result<Field, Error> {
+				Ok(Field::Text(v.to_string()))
+			}
+		)*
+	};
+}
+
+/// Serializes the top-level struct or map into the `Ini`.
+struct IniSerializer<'a> {
+	ini: &'a mut Ini,
+}
+
+const TOP_LEVEL_ERR: &str = "top-level value must be a struct or a map";
+
+impl<'a> ser::Serializer for IniSerializer<'a> {
+	type Ok = ();
+	type Error = Error;
+	type SerializeSeq = ser::Impossible<(), Error>;
+	type SerializeTuple = ser::Impossible<(), Error>;
+	type SerializeTupleStruct = ser::Impossible<(), Error>;
+	type SerializeTupleVariant = ser::Impossible<(), Error>;
+	type SerializeMap = TopLevelSerializer<'a>;
+	type SerializeStruct = TopLevelSerializer<'a>;
+	type SerializeStructVariant = ser::Impossible<(), Error>;
+
+	serialize_unsupported! { TOP_LEVEL_ERR;
+		serialize_bool(bool) serialize_i8(i8) serialize_i16(i16)
+		serialize_i32(i32) serialize_i64(i64) serialize_u8(u8)
+		serialize_u16(u16) serialize_u32(u32) serialize_u64(u64)
+		serialize_f32(f32) serialize_f64(f64) serialize_char(char)
+		serialize_str(&str) serialize_bytes(&[u8])
+		serialize_unit_struct(&'static str)
+	}
+
+	fn serialize_none(self) -> Result<(), Error> {
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<(), Error> {
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_unit_variant(self,
+		_: &'static str, _: u32, _: &'static str) -> Result<(), Error>
+	{
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, value: &T) -> Result<(), Error>
+	{
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, _: u32, _: &'static str, _: &T) -> Result<(), Error>
+	{
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_tuple_struct(self,
+		_: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, Error>
+	{
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_tuple_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeTupleVariant, Error>
+	{
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+		Ok(TopLevelSerializer { ini: self.ini, cur_key: None })
+	}
+
+	fn serialize_struct(self,
+		_: &'static str, _: usize) -> Result<Self::SerializeStruct, Error>
+	{
+		Ok(TopLevelSerializer { ini: self.ini, cur_key: None })
+	}
+
+	fn serialize_struct_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeStructVariant, Error>
+	{
+		Err(Error(TOP_LEVEL_ERR.to_owned()))
+	}
+}
+
+/// Writes each top-level field into the `Ini`.
+struct TopLevelSerializer<'a> {
+	ini: &'a mut Ini,
+	cur_key: Option<String>,
+}
+
+impl<'a> ser::SerializeStruct for TopLevelSerializer<'a> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ser::Serialize + ?Sized>(&mut self,
+		key: &'static str, value: &T) -> Result<(), Error>
+	{
+		let field = value.serialize(FieldSerializer { level: Level::TopField })?;
+		self.write_field(key, field)
+	}
+
+	fn end(self) -> Result<(), Error> {
+		Ok(())
+	}
+}
+
+impl<'a> ser::SerializeMap for TopLevelSerializer<'a> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+		self.cur_key = Some(serialize_key(key)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let key = self.cur_key.take()
+			.ok_or_else(|| Error("value serialized before key".to_owned()))?;
+		let field = value.serialize(FieldSerializer { level: Level::TopField })?;
+		self.write_field(&key, field)
+	}
+
+	fn end(self) -> Result<(), Error> {
+		Ok(())
+	}
+}
+
+impl<'a> TopLevelSerializer<'a> {
+	fn write_field(&mut self, name: &str, field: Field) -> Result<(), Error> {
+		match field {
+			Field::Absent => {
+				self.ini.remove_key("", name);
+				self.ini.remove_section(name);
+			},
+			Field::Section(entries) => {
+				if name.is_empty() || check_section_name(name).is_err() {
+					return Err(invalid_name("section", name));
+				}
+				if self.ini.section(name).is_none() {
+					self.ini.sections.push(IniSection::new(name));
+				}
+				for (key, field) in entries.into_iter() {
+					write_entry(self.ini, name, &key, field)?;
+				}
+			},
+			field => write_entry(self.ini, "", name, field)?,
+		}
+		Ok(())
+	}
+}
+
+fn write_entry(ini: &mut Ini,
+	section: &str, key: &str, field: Field) -> Result<(), Error>
+{
+	match field {
+		Field::Absent => { ini.remove_key(section, key); },
+		Field::Bool(b) => ini.set_bool(section, key, b)
+			.map_err(|_| invalid_name("key", key))?,
+		Field::Text(text) => {
+			check_value(&text).map_err(|_| invalid_text(&text))?;
+			ini.set_value(section, key, &text)
+				.map_err(|_| invalid_name("key", key))?;
+		},
+		Field::Section(_) => unreachable!(), // rejected by FieldSerializer
+	}
+	Ok(())
+}
+
+fn invalid_name(kind: &str, name: &str) -> Error {
+	Error(format!("{} name \"{}\" cannot be written", kind, name.escape_debug()))
+}
+
+fn invalid_text(text: &str) -> Error {
+	Error(format!("value \"{}\" has line breaks, null chars, or leading or trailing whitespace",
+		text.escape_debug()))
+}
+
+fn serialize_key<T: ser::Serialize + ?Sized>(key: &T) -> Result<String, Error> {
+	match key.serialize(FieldSerializer { level: Level::Item })? {
+		Field::Text(text) => Ok(text),
+		_ => Err(Error("keys must be strings".to_owned())),
+	}
+}
+
+/// Serializes a value into a `Field`.
+struct FieldSerializer {
+	level: Level,
+}
+
+impl ser::Serializer for FieldSerializer {
+	type Ok = Field;
+	type Error = Error;
+	type SerializeSeq = ListSerializer;
+	type SerializeTuple = ListSerializer;
+	type SerializeTupleStruct = ListSerializer;
+	type SerializeTupleVariant = ser::Impossible<Field, Error>;
+	type SerializeMap = SectionSerializer;
+	type SerializeStruct = SectionSerializer;
+	type SerializeStructVariant = ser::Impossible<Field, Error>;
+
+	serialize_display! {
+		serialize_i8(i8) serialize_i16(i16) serialize_i32(i32)
+		serialize_i64(i64) serialize_u8(u8) serialize_u16(u16)
+		serialize_u32(u32) serialize_u64(u64) serialize_f32(f32)
+		serialize_f64(f64) serialize_char(char) serialize_str(&str)
+	}
+
+	fn serialize_bool(self, v: bool) -> Result<Field, Error> {
+		Ok(Field::Bool(v))
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Field, Error> {
+		std::str::from_utf8(v)
+			.map(|s| Field::Text(s.to_owned()))
+			.map_err(|_| Error("bytes must be valid UTF-8".to_owned()))
+	}
+
+	fn serialize_none(self) -> Result<Field, Error> {
+		Ok(Field::Absent)
+	}
+
+	fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Field, Error> {
+		match value.serialize(self)? {
+			Field::Text(text) if text.is_empty() => Err(Error(
+				"Some with an empty value would be read back as None".to_owned())),
+			field => Ok(field),
+		}
+	}
+
+	fn serialize_unit(self) -> Result<Field, Error> {
+		Ok(Field::Text(String::default()))
+	}
+
+	fn serialize_unit_struct(self, _: &'static str) -> Result<Field, Error> {
+		self.serialize_unit()
+	}
+
+	fn serialize_unit_variant(self,
+		_: &'static str, _: u32, variant: &'static str) -> Result<Field, Error>
+	{
+		Ok(Field::Text(variant.to_owned()))
+	}
+
+	fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, value: &T) -> Result<Field, Error>
+	{
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(self,
+		_: &'static str, _: u32, _: &'static str, _: &T) -> Result<Field, Error>
+	{
+		Err(Error("only unit enum variants are supported".to_owned()))
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<ListSerializer, Error> {
+		match self.level {
+			Level::Item => Err(Error("nested lists are not supported".to_owned())),
+			_ => Ok(ListSerializer { items: Vec::default() }),
+		}
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<ListSerializer, Error> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self,
+		_: &'static str, len: usize) -> Result<ListSerializer, Error>
+	{
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeTupleVariant, Error>
+	{
+		Err(Error("only unit enum variants are supported".to_owned()))
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<SectionSerializer, Error> {
+		match self.level {
+			Level::TopField => Ok(SectionSerializer { entries: Vec::default(), cur_key: None }),
+			_ => Err(Error("sections cannot be nested".to_owned())),
+		}
+	}
+
+	fn serialize_struct(self,
+		_: &'static str, len: usize) -> Result<SectionSerializer, Error>
+	{
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(self,
+		_: &'static str, _: u32, _: &'static str,
+		_: usize) -> Result<Self::SerializeStructVariant, Error>
+	{
+		Err(Error("only unit enum variants are supported".to_owned()))
+	}
+}
+
+/// Collects the items of a comma-separated list.
+struct ListSerializer {
+	items: Vec<String>,
+}
+
+impl ser::SerializeSeq for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let item = match value.serialize(FieldSerializer { level: Level::Item })? {
+			Field::Bool(b) => b.to_string(),
+			Field::Text(text) => text,
+			_ => return Err(Error("list items must be plain values".to_owned())),
+		};
+		if item.contains(',') {
+			return Err(Error(format!("list item \"{}\" contains a comma", item)));
+		}
+		check_value(&item).map_err(|_| invalid_text(&item))?;
+		self.items.push(item);
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		if let [item] = self.items.as_slice() {
+			if item.is_empty() {
+				return Err(Error(
+					"a list with a single empty item would be read back as an empty list".to_owned()));
+			}
+		}
+		Ok(Field::Text(self.items.join(",")))
+	}
+}
+
+impl ser::SerializeTuple for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+/// Collects the keys of a section.
+struct SectionSerializer {
+	entries: Vec<(String, Field)>,
+	cur_key: Option<String>,
+}
+
+impl ser::SerializeStruct for SectionSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_field<T: ser::Serialize + ?Sized>(&mut self,
+		key: &'static str, value: &T) -> Result<(), Error>
+	{
+		let field = value.serialize(FieldSerializer { level: Level::Entry })?;
+		self.entries.push((key.to_owned(), field));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		Ok(Field::Section(self.entries))
+	}
+}
+
+impl ser::SerializeMap for SectionSerializer {
+	type Ok = Field;
+	type Error = Error;
+
+	fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+		self.cur_key = Some(serialize_key(key)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let key = self.cur_key.take()
+			.ok_or_else(|| Error("value serialized before key".to_owned()))?;
+		let field = value.serialize(FieldSerializer { level: Level::Entry })?;
+		self.entries.push((key, field));
+		Ok(())
+	}
+
+	fn end(self) -> Result<Field, Error> {
+		Ok(Field::Section(self.entries))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde::{Deserialize, Serialize};
+	use super::*;
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	struct Config {
+		title: String,
+		retries: i32,
+		size: u64,
+		ratio: f64,
+		verbose: bool,
+		mode: Mode,
+		note: Option<String>,
+		window: Window,
+		extra: Option<Window>,
+	}
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	struct Window {
+		width: u32,
+		maximized: Option<bool>,
+		recent: Vec<String>,
+	}
+
+	#[derive(Debug, PartialEq, Deserialize, Serialize)]
+	enum Mode { Fast, Safe }
+
+	fn sample() -> Config {
+		Config {
+			title: "Episode #1; = [x]".to_owned(),
+			retries: -3,
+			size: u64::MAX,
+			ratio: 0.5,
+			verbose: true,
+			mode: Mode::Safe,
+			note: None,
+			window: Window {
+				width: 800,
+				maximized: Some(false),
+				recent: vec!["a b.txt".to_owned(), "c.txt".to_owned()],
+			},
+			extra: None,
+		}
+	}
+
+	#[test]
+	fn round_trip() {
+		let cfg = sample();
+		let text = to_string(&cfg).unwrap();
+		assert_eq!(text, "title=Episode #1; = [x]\r\nretries=-3\r\n\
+			size=18446744073709551615\r\nratio=0.5\r\nverbose=true\r\nmode=Safe\r\n\
+			\r\n[window]\r\nwidth=800\r\nmaximized=false\r\nrecent=a b.txt,c.txt\r\n");
+		assert_eq!(from_str::<Config>(&text).unwrap(), cfg);
+	}
+
+	#[test]
+	fn update_keeps_comments() {
+		let mut ini = Ini::parse_str(
+			"; my config\r\nTITLE=old\r\nnote=gone\r\n[Window]\r\n; width in px\r\nWidth=1\r\n");
+		update_ini(&mut ini, &sample()).unwrap();
+		let text = ini.serialize_to_str();
+		assert!(text.starts_with("; my config\r\nTITLE=Episode #1; = [x]\r\n"));
+		assert!(text.contains("[Window]\r\n; width in px\r\nWidth=800\r\n"));
+		assert!(!text.contains("note="));
+		assert_eq!(from_str::<Config>(&text).unwrap(), sample());
+	}
+
+	#[test]
+	fn reject_unwritable() {
+		let mut cfg = sample();
+		cfg.title = "x\r\n[evil]\r\nadmin=1".to_owned();
+		assert!(to_string(&cfg).is_err());
+		cfg.title = " padded ".to_owned();
+		assert!(to_string(&cfg).is_err());
+		cfg.title = "nul\0".to_owned();
+		assert!(to_string(&cfg).is_err());
+
+		let mut cfg = sample();
+		cfg.window.recent = vec!["a,b".to_owned()];
+		assert!(to_string(&cfg).is_err());
+		cfg.window.recent = vec![" a".to_owned()];
+		assert!(to_string(&cfg).is_err());
+
+		let mut map = std::collections::BTreeMap::new();
+		map.insert("k=v", 1);
+		assert!(to_string(&map).is_err());
+
+		let mut sections = std::collections::BTreeMap::new();
+		sections.insert("a]\r\n[b", std::collections::BTreeMap::from([("k", 1)]));
+		assert!(to_string(&sections).is_err());
+	}
+
+	#[test]
+	fn reject_ambiguous_empty() {
+		let mut cfg = sample();
+		cfg.note = Some(String::default());
+		assert!(to_string(&cfg).is_err());
+		cfg.window.recent = vec![String::default()];
+		cfg.note = None;
+		assert!(to_string(&cfg).is_err());
+
+		cfg.window.recent = vec![String::default(), String::default()]; // ","
+		let text = to_string(&cfg).unwrap();
+		assert!(text.contains("recent=,\r\n"));
+		assert_eq!(from_str::<Config>(&text).unwrap(), cfg);
+
+		cfg.window.recent = Vec::default();
+		assert_eq!(from_str::<Config>(&to_string(&cfg).unwrap()).unwrap(), cfg);
+		let w: Window = from_str("width=1\r\nmaximized=\r\nrecent=").unwrap();
+		assert_eq!(w, Window { width: 1, maximized: None, recent: Vec::default() });
+	}
+
+	#[test]
+	fn deserialize_errors() {
+		assert!(from_str::<Window>("width=abc\r\nrecent=").is_err());
+		assert!(from_str::<Config>("[window]\r\nwidth=1").is_err()); // missing fields
+		let w: Window = from_str("WIDTH = 0x10 \r\nrecent = x , y").unwrap();
+		assert_eq!(w, Window { width: 16, maximized: None, recent: vec!["x".to_owned(), "y".to_owned()] });
+	}
+}
@@ -0,0 +1,320 @@
+use crate::{co, kernel};
+use crate::kernel::decl::{HJOB, HPROCESS,
+	JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION,
+	JOBOBJECT_BASIC_UI_RESTRICTIONS, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+	JOBOBJECT_EXTENDED_LIMIT_INFORMATION, WinResult};
+use crate::kernel::privs::bool_to_winresult;
+use crate::prelude::{Handle, HandleClose, KernelHjob, KernelHprocess};
+
+/// Manages a [job object](https://docs.microsoft.com/en-us/windows/win32/procthread/job-objects),
+/// which groups processes so they can be limited, accounted and terminated
+/// together. The [`HJOB`](crate::HJOB) handle is closed automatically when the
+/// object goes out of scope.
+///
+/// Processes created by a process in the job are associated to the job too,
+/// so the job holds the whole process tree.
+///
+/// # Examples
+///
+/// Binding helper processes to the lifetime of the current process: when the
+/// `Job` is dropped – or the current process exits, even if it crashes – the
+/// system closes the last job handle, and all the processes are terminated:
+///
+/// ```rust,no_run
+/// use winsafe::{Command, Job};
+///
+/// let job = Job::new()?;
+/// job.set_kill_on_close(true)?;
+///
+/// let child = Command::new("helper.exe")
+///     .arg("--serve")
+///     .spawn_in_job(&job)?;
+///
+/// // keep "job" alive as long as the helpers should run
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct Job {
+	hjob: HJOB,
+}
+
+impl Drop for Job {
+	fn drop(&mut self) {
+		self.hjob.CloseHandle().ok(); // ignore errors
+	}
+}
+
+impl Job {
+	/// Creates a new anonymous job, without limits.
+	pub fn new() -> WinResult<Job> {
+		HJOB::CreateJobObject(None, None)
+			.map(|hjob| Self { hjob })
+	}
+
+	/// Returns the accounting information of the job: processor times, number
+	/// of processes and I/O counters.
+	pub fn accounting(&self) -> WinResult<JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION> {
+		let mut info = JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION::default();
+		unsafe {
+			self.hjob.QueryInformationJobObject(
+				co::JOBOBJECTINFOCLASS::BasicAndIoAccountingInformation, &mut info)?;
+		}
+		Ok(info)
+	}
+
+	/// Associates a running process to the job, along with the processes it
+	/// creates afterwards.
+	///
+	/// To make sure a new process doesn't create other processes before being
+	/// associated, spawn it with
+	/// [`Command::spawn_in_job`](crate::Command::spawn_in_job).
+	pub fn assign(&self, hprocess: HPROCESS) -> WinResult<()> {
+		self.hjob.AssignProcessToJobObject(hprocess)
+	}
+
+	/// Tells whether the process is associated to the job.
+	pub fn contains(&self, hprocess: HPROCESS) -> WinResult<bool> {
+		hprocess.IsProcessInJob(Some(self.hjob))
+	}
+
+	/// Returns the underlying job handle.
+	pub const fn hjob(&self) -> HJOB {
+		self.hjob
+	}
+
+	/// Returns the current limits of the job, along with its I/O counters and
+	/// peak memory usage.
+	pub fn limits(&self) -> WinResult<JOBOBJECT_EXTENDED_LIMIT_INFORMATION> {
+		let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+		unsafe {
+			self.hjob.QueryInformationJobObject(
+				co::JOBOBJECTINFOCLASS::ExtendedLimitInformation, &mut info)?;
+		}
+		Ok(info)
+	}
+
+	/// Returns the IDs of the processes currently associated to the job.
+	pub fn process_ids(&self) -> WinResult<Vec<u32>> {
+		const PTR_SIZE: usize = std::mem::size_of::<usize>();
+		let mut buf = vec![0usize; id_list_len(64, PTR_SIZE) / PTR_SIZE]; // usize-aligned for the IDs
+
+		loop {
+			match bool_to_winresult(
+				unsafe {
+					kernel::ffi::QueryInformationJobObject(
+						self.hjob.as_ptr(),
+						co::JOBOBJECTINFOCLASS::BasicProcessIdList.0,
+						buf.as_mut_ptr() as _,
+						(buf.len() * std::mem::size_of::<usize>()) as _,
+						std::ptr::null_mut(),
+					)
+				},
+			) {
+				Ok(_) => return Ok(decode_id_list(as_bytes(&buf), PTR_SIZE)),
+				Err(co::ERROR::MORE_DATA) => {
+					let num_assigned = id_list_counts(as_bytes(&buf)).0;
+					let new_len = id_list_len(num_assigned + 16, PTR_SIZE); // processes may be created meanwhile
+					buf.resize(new_len.div_ceil(PTR_SIZE), 0);
+				},
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	/// Limits the number of processes simultaneously associated to the job;
+	/// `None` removes the limit.
+	pub fn set_active_process_limit(&self, max_processes: Option<u32>) -> WinResult<()> {
+		self.update_limits(|info| {
+			let basic = &mut info.BasicLimitInformation;
+			basic.ActiveProcessLimit = max_processes.unwrap_or_default();
+			set_flag(&mut basic.LimitFlags, co::JOB_OBJECT_LIMIT::ACTIVE_PROCESS, max_processes.is_some());
+		})
+	}
+
+	/// Limits the processor usage of the whole job, in percent, from 1 to 100;
+	/// `None` removes the limit. The limit is a hard cap: the processes are
+	/// not scheduled once the job used its share.
+	///
+	/// Fails with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the percentage is out of range.
+	pub fn set_cpu_rate_limit(&self, percent: Option<u32>) -> WinResult<()> {
+		let mut info = JOBOBJECT_CPU_RATE_CONTROL_INFORMATION::default();
+		if let Some(percent) = percent {
+			if !(1..=100).contains(&percent) {
+				return Err(co::ERROR::INVALID_PARAMETER);
+			}
+			info.ControlFlags = co::JOB_OBJECT_CPU_RATE_CONTROL::ENABLE
+				| co::JOB_OBJECT_CPU_RATE_CONTROL::HARD_CAP;
+			info.set_CpuRate(percent * 100); // in hundredths of percent
+		}
+		unsafe {
+			self.hjob.SetInformationJobObject(
+				co::JOBOBJECTINFOCLASS::CpuRateControlInformation, &info)
+		}
+	}
+
+	/// If `true`, all processes associated to the job are terminated when the
+	/// last handle to the job is closed.
+	pub fn set_kill_on_close(&self, kill_on_close: bool) -> WinResult<()> {
+		self.update_limits(|info| set_flag(
+			&mut info.BasicLimitInformation.LimitFlags,
+			co::JOB_OBJECT_LIMIT::KILL_ON_JOB_CLOSE,
+			kill_on_close,
+		))
+	}
+
+	/// Replaces all limits of the job. The I/O counters and the peak memory
+	/// usage are ignored.
+	pub fn set_limits(&self, limits: &JOBOBJECT_EXTENDED_LIMIT_INFORMATION) -> WinResult<()> {
+		unsafe {
+			self.hjob.SetInformationJobObject(
+				co::JOBOBJECTINFOCLASS::ExtendedLimitInformation, limits)
+		}
+	}
+
+	/// Limits the committed memory, in bytes, of each process and of the whole
+	/// job; `None` removes the limit. A process trying to commit more memory
+	/// fails to allocate it.
+	pub fn set_memory_limit(&self,
+		per_process: Option<usize>, per_job: Option<usize>) -> WinResult<()>
+	{
+		self.update_limits(|info| {
+			info.ProcessMemoryLimit = per_process.unwrap_or_default();
+			info.JobMemoryLimit = per_job.unwrap_or_default();
+			let flags = &mut info.BasicLimitInformation.LimitFlags;
+			set_flag(flags, co::JOB_OBJECT_LIMIT::PROCESS_MEMORY, per_process.is_some());
+			set_flag(flags, co::JOB_OBJECT_LIMIT::JOB_MEMORY, per_job.is_some());
+		})
+	}
+
+	/// Restricts what the processes of the job can do with the user interface,
+	/// like reading the clipboard or changing system parameters.
+	pub fn set_ui_restrictions(&self, restrictions: co::JOB_OBJECT_UILIMIT) -> WinResult<()> {
+		let info = JOBOBJECT_BASIC_UI_RESTRICTIONS { UIRestrictionsClass: restrictions };
+		unsafe {
+			self.hjob.SetInformationJobObject(
+				co::JOBOBJECTINFOCLASS::BasicUIRestrictions, &info)
+		}
+	}
+
+	/// Terminates all processes associated to the job, with the given exit
+	/// code.
+	pub fn terminate(&self, exit_code: u32) -> WinResult<()> {
+		self.hjob.TerminateJobObject(exit_code)
+	}
+
+	/// Reads the current limits, changes them, and writes them back.
+	fn update_limits<F>(&self, func: F) -> WinResult<()>
+		where F: FnOnce(&mut JOBOBJECT_EXTENDED_LIMIT_INFORMATION),
+	{
+		let mut limits = self.limits()?;
+		func(&mut limits);
+		self.set_limits(&limits)
+	}
+}
+
+fn as_bytes(buf: &[usize]) -> &[u8] {
+	unsafe {
+		std::slice::from_raw_parts(buf.as_ptr() as _, std::mem::size_of_val(buf))
+	}
+}
+
+// JOBOBJECT_BASIC_PROCESS_ID_LIST: two u32 counts, then the IDs as ULONG_PTR,
+// whose size is given, so both 32 and 64-bit layouts can be decoded.
+
+/// Size, in bytes, of a list with room for the given number of IDs.
+const fn id_list_len(num_ids: usize, ptr_size: usize) -> usize {
+	8 + num_ids * ptr_size
+}
+
+/// Returns the number of processes assigned to the job, and the number of IDs
+/// actually in the list.
+fn id_list_counts(bytes: &[u8]) -> (usize, usize) {
+	let read_u32 = |idx: usize| bytes.get(idx..idx + 4)
+		.map_or(0, |b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as usize);
+	(read_u32(0), read_u32(4))
+}
+
+/// Decodes the IDs in the list, ignoring the ones beyond the buffer.
+fn decode_id_list(bytes: &[u8], ptr_size: usize) -> Vec<u32> {
+	let num_in_list = id_list_counts(bytes).1;
+	bytes.get(8..).unwrap_or_default()
+		.chunks_exact(ptr_size)
+		.take(num_in_list)
+		.map(|id| u32::from_ne_bytes([id[0], id[1], id[2], id[3]])) // IDs fit in a u32
+		.collect()
+}
+
+fn set_flag(flags: &mut co::JOB_OBJECT_LIMIT, flag: co::JOB_OBJECT_LIMIT, set: bool) {
+	if set {
+		*flags |= flag;
+	} else {
+		*flags &= !flag;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Builds a list as the system would, with IDs of the given size.
+	fn id_list(num_assigned: u32, ids: &[u32], ptr_size: usize, capacity: usize) -> Vec<u8> {
+		let mut bytes = vec![0; id_list_len(capacity, ptr_size)];
+		bytes[..4].copy_from_slice(&num_assigned.to_ne_bytes());
+		bytes[4..8].copy_from_slice(&(ids.len() as u32).to_ne_bytes());
+		for (idx, id) in ids.iter().enumerate() {
+			let off = 8 + idx * ptr_size;
+			match ptr_size {
+				4 => bytes[off..off + 4].copy_from_slice(&id.to_ne_bytes()),
+				_ => bytes[off..off + 8].copy_from_slice(&(*id as u64).to_ne_bytes()),
+			}
+		}
+		bytes
+	}
+
+	#[test]
+	fn decode_both_widths() {
+		for ptr_size in [4, 8] {
+			let bytes = id_list(3, &[100, 0xffff_fffc, 7], ptr_size, 5);
+			assert_eq!(bytes.len(), 8 + 5 * ptr_size);
+			assert_eq!(id_list_counts(&bytes), (3, 3));
+			assert_eq!(decode_id_list(&bytes, ptr_size), [100, 0xffff_fffc, 7], "{}-byte IDs", ptr_size);
+
+			let empty = id_list(0, &[], ptr_size, 0);
+			assert!(decode_id_list(&empty, ptr_size).is_empty());
+		}
+	}
+
+	#[test]
+	fn decode_unaligned() {
+		for ptr_size in [4, 8] {
+			let mut bytes = vec![0xee];
+			bytes.extend(id_list(2, &[5, 6], ptr_size, 2));
+			assert_eq!(id_list_counts(&bytes[1..]), (2, 2));
+			assert_eq!(decode_id_list(&bytes[1..], ptr_size), [5, 6]);
+		}
+	}
+
+	#[test]
+	fn decode_more_data() {
+		for ptr_size in [4, 8] {
+			// Buffer for 2 IDs, but 40 processes are assigned: only the counts are valid.
+			let bytes = id_list(40, &[1, 2], ptr_size, 2);
+			let (num_assigned, _) = id_list_counts(&bytes);
+			assert_eq!(num_assigned, 40);
+			assert_eq!(id_list_len(num_assigned + 16, ptr_size), 8 + 56 * ptr_size);
+		}
+	}
+
+	#[test]
+	fn decode_truncated() {
+		for ptr_size in [4, 8] {
+			let mut bytes = id_list(3, &[1, 2, 3], ptr_size, 3);
+			bytes.truncate(8 + 2 * ptr_size + 1); // count says 3, room for 2
+			assert_eq!(decode_id_list(&bytes, ptr_size), [1, 2]);
+			assert_eq!(decode_id_list(&bytes[..6], ptr_size), Vec::<u32>::new());
+			assert_eq!(id_list_counts(&bytes[..6]), (3, 0));
+		}
+	}
+}
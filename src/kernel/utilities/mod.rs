@@ -1,11 +1,25 @@
+mod command;
+mod completion_port;
+mod dir_walk;
 mod file_mapped;
 mod file;
 mod ini;
+mod job;
+mod named_pipe;
+mod w_str;
 mod w_string;
 
+#[cfg(feature = "serde")] pub mod ini_serde;
 pub mod path;
 
+pub use command::{Child, ChildPipe, Command, Output, Stdio};
+pub use completion_port::{CompletionPort, IoCompletion, IoFuture};
+pub use dir_walk::{DirEntry, DirSort, DirWalk, DirWalkOptions};
 pub use file_mapped::FileMapped;
 pub use file::{File, FileAccess};
-pub use ini::{Ini, IniEntry, IniSection};
-pub use w_string::{Encoding, WString};
+pub use ini::{Ini, IniDuplicates, IniEntry, IniError, IniErrorKind,
+	IniParseOptions, IniSection};
+pub use job::Job;
+pub use named_pipe::{NamedPipeClient, NamedPipeOptions, NamedPipeServer};
+pub use w_str::{ToWStr, WStr};
+pub use w_string::{Encoding, EncodingGuess, WString};
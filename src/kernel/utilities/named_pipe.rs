@@ -0,0 +1,503 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::co;
+use crate::kernel::decl::{HFILE, HPIPE, WinResult};
+use crate::prelude::{HandleClose, KernelHfile, KernelHpipe};
+
+/// Implements `io::Read` and `io::Write` for a pipe object and its reference.
+macro_rules! impl_pipe_io {
+	($name:ident) => {
+		impl io::Read for $name {
+			fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+				io::Read::read(&mut &*self, buf)
+			}
+		}
+
+		impl io::Read for &$name {
+			fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+				match read_pipe(self.hpipe, buf) {
+					Ok(bytes_read) => Ok(bytes_read),
+					Err(co::ERROR::BROKEN_PIPE) => Ok(0), // the other end was closed
+					Err(e) => Err(e.into()),
+				}
+			}
+		}
+
+		impl io::Write for $name {
+			fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+				io::Write::write(&mut &*self, buf)
+			}
+
+			fn flush(&mut self) -> io::Result<()> {
+				io::Write::flush(&mut &*self)
+			}
+		}
+
+		impl io::Write for &$name {
+			fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+				let len = buf.len().min(u32::MAX as _);
+				self.hpipe.WriteFile(&buf[..len], None)
+					.map(|bytes_written| bytes_written as _)
+					.map_err(|e| e.into())
+			}
+
+			fn flush(&mut self) -> io::Result<()> {
+				Ok(()) // writes are not buffered by us
+			}
+		}
+	};
+}
+
+/// Options to
+/// [`NamedPipeServer::create`](crate::NamedPipeServer::create).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NamedPipeOptions {
+	/// Direction of the data flow.
+	///
+	/// Default is [`PIPE_ACCESS::DUPLEX`](crate::co::PIPE_ACCESS::DUPLEX).
+	pub access: co::PIPE_ACCESS,
+	/// If `true`, data is written and read as messages, otherwise as a stream
+	/// of bytes.
+	///
+	/// Default is `false`.
+	pub message_mode: bool,
+	/// If `true`, creating the pipe fails if another instance with the same
+	/// name already exists – possibly created by another process.
+	///
+	/// Default is `false`.
+	pub first_instance: bool,
+	/// Maximum number of instances of the pipe, `None` meaning no limit.
+	///
+	/// Default is `None`.
+	pub max_instances: Option<u32>,
+	/// Size of the input and the output buffers, in bytes. The system uses
+	/// this as a hint.
+	///
+	/// Default is 4096.
+	pub buffer_size: u32,
+	/// If `true`, connections from other computers are rejected.
+	///
+	/// Default is `true`.
+	pub reject_remote_clients: bool,
+	/// If `true`, the pipe is opened for asynchronous I/O, to be used with a
+	/// [`CompletionPort`](crate::CompletionPort). The blocking methods of the
+	/// server must not be used then.
+	///
+	/// Default is `false`.
+	pub overlapped: bool,
+}
+
+impl Default for NamedPipeOptions {
+	fn default() -> Self {
+		Self {
+			access: co::PIPE_ACCESS::DUPLEX,
+			message_mode: false,
+			first_instance: false,
+			max_instances: None,
+			buffer_size: 4096,
+			reject_remote_clients: true,
+			overlapped: false,
+		}
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// Manages the server end of a named pipe instance, whose
+/// [`HPIPE`](crate::HPIPE) handle is closed automatically when the object
+/// goes out of scope.
+///
+/// Each instance serves one client at a time. To serve several clients
+/// concurrently, create more instances with
+/// [`new_instance`](crate::NamedPipeServer::new_instance).
+///
+/// Implements the standard
+/// [`Read`](https://doc.rust-lang.org/std/io/trait.Read.html) and
+/// [`Write`](https://doc.rust-lang.org/std/io/trait.Write.html) traits, also
+/// for `&NamedPipeServer`.
+///
+/// # Examples
+///
+/// A server which answers each client in a new thread:
+///
+/// ```rust,no_run
+/// use winsafe::{NamedPipeOptions, NamedPipeServer};
+///
+/// let options = NamedPipeOptions {
+///     message_mode: true,
+///     first_instance: true,
+///     ..Default::default()
+/// };
+/// let mut server = NamedPipeServer::create("my-service", options)?;
+///
+/// loop {
+///     server.connect()?;
+///     let next = server.new_instance()?;
+///     let connected = std::mem::replace(&mut server, next);
+///
+///     std::thread::spawn(move || -> winsafe::WinResult<()> {
+///         let request = connected.read_message()?;
+///         connected.write(&request)?; // echo
+///         connected.disconnect()
+///     });
+/// }
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct NamedPipeServer {
+	hpipe: HPIPE,
+	name: String,
+	options: NamedPipeOptions,
+}
+
+impl Drop for NamedPipeServer {
+	fn drop(&mut self) {
+		self.hpipe.CloseHandle().ok(); // ignore errors
+	}
+}
+
+impl_pipe_io!(NamedPipeServer);
+
+impl NamedPipeServer {
+	/// Creates an instance of a named pipe by calling
+	/// [`HPIPE::CreateNamedPipe`](crate::prelude::KernelHpipe::CreateNamedPipe).
+	///
+	/// The name can be either a full pipe name, like `\\.\pipe\my-service`, or
+	/// just the last part, like `my-service`.
+	pub fn create(name: &str, options: NamedPipeOptions) -> WinResult<NamedPipeServer> {
+		let name = full_pipe_name(name);
+
+		let mut open_mode = options.access;
+		if options.first_instance {
+			open_mode |= co::PIPE_ACCESS::FIRST_PIPE_INSTANCE;
+		}
+		if options.overlapped {
+			open_mode |= co::PIPE_ACCESS::OVERLAPPED;
+		}
+
+		let mut pipe_mode = co::PIPE::WAIT;
+		if options.message_mode {
+			pipe_mode |= co::PIPE::TYPE_MESSAGE | co::PIPE::READMODE_MESSAGE;
+		}
+		if options.reject_remote_clients {
+			pipe_mode |= co::PIPE::REJECT_REMOTE_CLIENTS;
+		}
+
+		let hpipe = HPIPE::CreateNamedPipe(&name, open_mode, pipe_mode,
+			options.max_instances, options.buffer_size, options.buffer_size,
+			None, None)?;
+		Ok(Self { hpipe, name, options })
+	}
+
+	/// Blocks until a client connects to this instance, by calling
+	/// [`HPIPE::ConnectNamedPipe`](crate::prelude::KernelHpipe::ConnectNamedPipe).
+	pub fn connect(&self) -> WinResult<()> {
+		self.hpipe.ConnectNamedPipe(None)
+	}
+
+	/// Waits until the client reads all data written to the pipe, then
+	/// disconnects it, so the instance can be connected to another client.
+	pub fn disconnect(&self) -> WinResult<()> {
+		match self.hpipe.FlushFileBuffers() {
+			Ok(_) | Err(co::ERROR::BROKEN_PIPE) => {}, // client already gone
+			Err(e) => return Err(e),
+		}
+		self.hpipe.DisconnectNamedPipe()
+	}
+
+	/// Returns the underlying pipe handle.
+	pub const fn hpipe(&self) -> HPIPE {
+		self.hpipe
+	}
+
+	/// Returns the full name of the pipe.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Creates another instance of the same pipe, with the same options, so
+	/// another client can connect while this instance is busy.
+	pub fn new_instance(&self) -> WinResult<NamedPipeServer> {
+		Self::create(&self.name, NamedPipeOptions {
+			first_instance: false,
+			..self.options
+		})
+	}
+
+	/// Returns the number of bytes which can be read without blocking.
+	pub fn peek(&self) -> WinResult<usize> {
+		self.hpipe.PeekNamedPipe(None)
+			.map(|(_, total_avail, _)| total_avail as _)
+	}
+
+	/// Reads bytes from the pipe, blocking until there is some data, and
+	/// returns the number of bytes read. In message mode, if the message
+	/// doesn't fit the buffer, the rest is returned by the next calls.
+	///
+	/// Fails with [`ERROR::BROKEN_PIPE`](crate::co::ERROR::BROKEN_PIPE) if the
+	/// client disconnected.
+	pub fn read(&self, buffer: &mut [u8]) -> WinResult<usize> {
+		read_pipe(self.hpipe, buffer)
+	}
+
+	/// Reads a whole message from the pipe. In byte mode, returns whatever is
+	/// read by a single read.
+	pub fn read_message(&self) -> WinResult<Vec<u8>> {
+		read_message(self.hpipe)
+	}
+
+	/// Writes all the bytes to the pipe. In message mode, they're written as a
+	/// single message.
+	///
+	/// Fails with [`ERROR::WRITE_FAULT`](crate::co::ERROR::WRITE_FAULT) if the
+	/// pipe stops accepting bytes before all of them are written.
+	pub fn write(&self, data: &[u8]) -> WinResult<()> {
+		write_pipe(self.hpipe, data)
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// Manages the client end of a named pipe, whose [`HPIPE`](crate::HPIPE)
+/// handle is closed automatically when the object goes out of scope.
+///
+/// Implements the standard
+/// [`Read`](https://doc.rust-lang.org/std/io/trait.Read.html) and
+/// [`Write`](https://doc.rust-lang.org/std/io/trait.Write.html) traits, also
+/// for `&NamedPipeClient`.
+///
+/// # Examples
+///
+/// Sending a request to a message-mode server:
+///
+/// ```rust,no_run
+/// use winsafe::NamedPipeClient;
+///
+/// let client = NamedPipeClient::open("my-service", Some(5000))?;
+/// client.set_message_mode(true)?;
+/// let reply = client.transact(b"status")?;
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub struct NamedPipeClient {
+	hpipe: HPIPE,
+}
+
+impl Drop for NamedPipeClient {
+	fn drop(&mut self) {
+		self.hpipe.CloseHandle().ok(); // ignore errors
+	}
+}
+
+impl_pipe_io!(NamedPipeClient);
+
+impl NamedPipeClient {
+	/// Connects to a named pipe for reading and writing. See
+	/// [`open_with`](crate::NamedPipeClient::open_with).
+	pub fn open(name: &str, milliseconds: Option<u32>) -> WinResult<NamedPipeClient> {
+		Self::open_with(name, co::GENERIC::READ | co::GENERIC::WRITE, milliseconds)
+	}
+
+	/// Connects to a named pipe with the given access, which must agree with
+	/// the direction of the pipe – for example, a pipe created with
+	/// [`PIPE_ACCESS::INBOUND`](crate::co::PIPE_ACCESS::INBOUND) can only be
+	/// opened with [`GENERIC::WRITE`](crate::co::GENERIC::WRITE).
+	///
+	/// The name can be either a full pipe name, like `\\.\pipe\my-service`, or
+	/// just the last part, like `my-service`.
+	///
+	/// If all instances of the pipe are busy, waits up to `milliseconds` for
+	/// one of them to be available; `None` waits forever. The pipe is always
+	/// opened in byte read mode.
+	pub fn open_with(
+		name: &str,
+		access: co::GENERIC,
+		milliseconds: Option<u32>) -> WinResult<NamedPipeClient>
+	{
+		let name = full_pipe_name(name);
+		let deadline = milliseconds
+			.map(|ms| Instant::now() + Duration::from_millis(ms as _));
+
+		loop {
+			match HFILE::CreateFile(&name, access, co::FILE_SHARE::NoValue, None,
+				co::DISPOSITION::OPEN_EXISTING, co::FILE_ATTRIBUTE::NORMAL, None)
+			{
+				Ok((hfile, _)) => return Ok(Self { hpipe: HPIPE(hfile.0) }),
+				Err(co::ERROR::PIPE_BUSY) => {},
+				Err(e) => return Err(e),
+			}
+
+			let remaining = remaining_wait(deadline, Instant::now())?;
+			HPIPE::WaitNamedPipe(&name, remaining)?; // then try again, another client may be faster
+		}
+	}
+
+	/// Returns the underlying pipe handle.
+	pub const fn hpipe(&self) -> HPIPE {
+		self.hpipe
+	}
+
+	/// Returns the number of bytes which can be read without blocking.
+	pub fn peek(&self) -> WinResult<usize> {
+		self.hpipe.PeekNamedPipe(None)
+			.map(|(_, total_avail, _)| total_avail as _)
+	}
+
+	/// Reads bytes from the pipe, blocking until there is some data, and
+	/// returns the number of bytes read. In message mode, if the message
+	/// doesn't fit the buffer, the rest is returned by the next calls.
+	///
+	/// Fails with [`ERROR::BROKEN_PIPE`](crate::co::ERROR::BROKEN_PIPE) if the
+	/// server closed the pipe.
+	pub fn read(&self, buffer: &mut [u8]) -> WinResult<usize> {
+		read_pipe(self.hpipe, buffer)
+	}
+
+	/// Reads a whole message from the pipe. In byte mode, returns whatever is
+	/// read by a single read.
+	pub fn read_message(&self) -> WinResult<Vec<u8>> {
+		read_message(self.hpipe)
+	}
+
+	/// Switches between message and byte read modes, by calling
+	/// [`HPIPE::SetNamedPipeHandleState`](crate::prelude::KernelHpipe::SetNamedPipeHandleState).
+	/// Message mode requires a server created with
+	/// [`NamedPipeOptions::message_mode`](crate::NamedPipeOptions::message_mode).
+	pub fn set_message_mode(&self, message_mode: bool) -> WinResult<()> {
+		let mode = if message_mode { co::PIPE::READMODE_MESSAGE } else { co::PIPE::READMODE_BYTE };
+		self.hpipe.SetNamedPipeHandleState(Some(mode), None, None)
+	}
+
+	/// Writes a request message and reads the whole reply message in a single
+	/// operation, by calling
+	/// [`HPIPE::TransactNamedPipe`](crate::prelude::KernelHpipe::TransactNamedPipe).
+	///
+	/// The pipe must be in message mode, see
+	/// [`set_message_mode`](crate::NamedPipeClient::set_message_mode).
+	pub fn transact(&self, request: &[u8]) -> WinResult<Vec<u8>> {
+		let mut reply = vec![0; CHUNK_SIZE];
+		match self.hpipe.TransactNamedPipe(request, &mut reply, None) {
+			Ok(bytes_read) => {
+				reply.truncate(bytes_read as _);
+				Ok(reply)
+			},
+			Err(co::ERROR::MORE_DATA) => { // reply buffer is full
+				reply.append(&mut read_message(self.hpipe)?);
+				Ok(reply)
+			},
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Writes all the bytes to the pipe. In message mode, they're written as a
+	/// single message.
+	///
+	/// Fails with [`ERROR::WRITE_FAULT`](crate::co::ERROR::WRITE_FAULT) if the
+	/// pipe stops accepting bytes before all of them are written.
+	pub fn write(&self, data: &[u8]) -> WinResult<()> {
+		write_pipe(self.hpipe, data)
+	}
+}
+
+//------------------------------------------------------------------------------
+
+const CHUNK_SIZE: usize = 4096;
+
+/// Prepends `\\.\pipe\` to a name without it.
+fn full_pipe_name(name: &str) -> String {
+	if name.starts_with("\\\\") {
+		name.to_owned()
+	} else {
+		format!("\\\\.\\pipe\\{}", name)
+	}
+}
+
+/// Returns the timeout, in milliseconds, to wait for a busy pipe until the
+/// deadline, or `None` to wait forever. Fails with
+/// [`ERROR::PIPE_BUSY`](crate::co::ERROR::PIPE_BUSY) if the deadline was
+/// reached.
+fn remaining_wait(deadline: Option<Instant>, now: Instant) -> WinResult<Option<u32>> {
+	match deadline {
+		None => Ok(None),
+		Some(deadline) if now >= deadline => Err(co::ERROR::PIPE_BUSY),
+		Some(deadline) => Ok(Some(
+			(deadline - now).as_millis()
+				.clamp(1, u32::MAX as u128 - 1) as u32, // zero would be the pipe default, u32::MAX is forever
+		)),
+	}
+}
+
+fn read_pipe(hpipe: HPIPE, buffer: &mut [u8]) -> WinResult<usize> {
+	let len = buffer.len().min(u32::MAX as _);
+	match hpipe.ReadFile(&mut buffer[..len], None) {
+		Ok(bytes_read) => Ok(bytes_read as _),
+		Err(co::ERROR::MORE_DATA) => Ok(len), // partial message, buffer is full
+		Err(e) => Err(e),
+	}
+}
+
+fn read_message(hpipe: HPIPE) -> WinResult<Vec<u8>> {
+	let mut message = Vec::default();
+	let mut chunk = vec![0; CHUNK_SIZE];
+	loop {
+		match hpipe.ReadFile(&mut chunk, None) {
+			Ok(bytes_read) => {
+				message.extend_from_slice(&chunk[..bytes_read as usize]);
+				return Ok(message);
+			},
+			Err(co::ERROR::MORE_DATA) => message.extend_from_slice(&chunk),
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+fn write_pipe(hpipe: HPIPE, mut data: &[u8]) -> WinResult<()> {
+	loop {
+		let len = data.len().min(u32::MAX as _);
+		let bytes_written = hpipe.WriteFile(&data[..len], None)? as usize;
+		data = &data[bytes_written..];
+		if data.is_empty() {
+			return Ok(());
+		} else if bytes_written == 0 {
+			return Err(co::ERROR::WRITE_FAULT); // no progress, would loop forever
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pipe_names() {
+		assert_eq!(full_pipe_name("my-service"), r"\\.\pipe\my-service");
+		assert_eq!(full_pipe_name(r"sub\my-service"), r"\\.\pipe\sub\my-service");
+		assert_eq!(full_pipe_name(r"\\.\pipe\my-service"), r"\\.\pipe\my-service");
+		assert_eq!(full_pipe_name(r"\\server\pipe\my-service"), r"\\server\pipe\my-service");
+	}
+
+	#[test]
+	fn wait_forever() {
+		assert_eq!(remaining_wait(None, Instant::now()), Ok(None));
+	}
+
+	#[test]
+	fn wait_until_deadline() {
+		let now = Instant::now();
+		let ms = |ms| Some(now + Duration::from_millis(ms));
+
+		assert_eq!(remaining_wait(ms(1500), now), Ok(Some(1500)));
+		assert_eq!(remaining_wait(ms(1500), now + Duration::from_millis(1000)), Ok(Some(500)));
+		assert_eq!(remaining_wait(Some(now + Duration::from_micros(300)), now), Ok(Some(1))); // not zero
+		assert_eq!(remaining_wait(ms(u32::MAX as _), now), Ok(Some(u32::MAX - 1))); // not forever
+	}
+
+	#[test]
+	fn wait_expired() {
+		let now = Instant::now();
+		assert_eq!(remaining_wait(Some(now), now), Err(co::ERROR::PIPE_BUSY));
+		assert_eq!(remaining_wait(Some(now), now + Duration::from_millis(1)), Err(co::ERROR::PIPE_BUSY));
+	}
+}
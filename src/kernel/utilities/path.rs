@@ -5,9 +5,23 @@
 //! ones, but here they work directly upon
 //! [`&str`](https://doc.rust-lang.org/std/primitive.str.html) instead of
 //! [`&OsStr`](https://doc.rust-lang.org/std/ffi/struct.OsStr.html).
+//!
+//! All of them understand the Windows path syntax, which is parsed by
+//! [`WinPath`](crate::path::WinPath): drive letters, UNC shares, `\\?\`
+//! verbatim paths, `\\.\` device paths, and both backslashes and forward
+//! slashes as separators.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::co;
+use crate::kernel::decl::{GetFileAttributes, GetFullPathName, HFINDFILE,
+	HINSTANCE, WIN32_FIND_DATA, WinResult};
+use crate::kernel::privs::MAX_PATH;
+use crate::prelude::{Handle, KernelHfindfile, KernelHinstance, NativeBitflag};
 
-use crate::kernel::decl::{HINSTANCE, WinResult};
-use crate::prelude::{Handle, KernelHinstance};
+static LONG_PATHS: AtomicBool = AtomicBool::new(false);
 
 /// Returns the path of the current EXE file, without the EXE filename, and
 /// without a trailing backslash.
@@ -40,10 +54,80 @@ pub fn exe_path() -> WinResult<String> {
 	)
 }
 
-/// Returns an iterator over each part of the path.
+/// Converts a long path in the `\\\\?\\` verbatim form, as returned by
+/// [`to_long_path`](crate::path::to_long_path), back to its ordinary form, to
+/// be displayed. Other paths are returned unchanged.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// let p = path::from_long_path("\\\\?\\C:\\Temp"); // C:\Temp
+/// let q = path::from_long_path("\\\\?\\UNC\\srv\\share\\a"); // \\srv\share\a
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn from_long_path(full_path: &str) -> String {
+	match WinPath::parse(full_path).prefix() {
+		Some(PathPrefix::VerbatimDisk(_)) => full_path[4..].to_owned(), // skip \\?\
+		Some(PathPrefix::VerbatimUnc { .. }) => format!("\\\\{}", &full_path[8..]), // skip \\?\UNC\
+		_ => full_path.to_owned(),
+	}
+}
+
+/// Returns an iterator over the files and directories matching the given
+/// pattern, which may have wildcards in any of its components.
+///
+/// Each component is matched by
+/// [`HFINDFILE::FindFirstFile`](crate::prelude::KernelHfindfile::FindFirstFile),
+/// so `*` and `?` follow the same rules of
+/// [`Wildcard`](crate::path::Wildcard). Additionally, a component which is
+/// exactly `**` matches zero or more directories, recursively; a trailing `**`
+/// matches everything below the directory. Reparse points, like junctions and
+/// symbolic links, are not followed by `**`, so cycles can't happen.
+///
+/// The paths are returned in the order they're found, files of a directory
+/// before the contents of its subdirectories. Directories which don't exist
+/// are silently skipped, while other errors – like access denied – are
+/// returned, and the search goes on.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// for file_path in path::glob("C:\\Projects\\**\\src\\*.rs") {
+///     let file_path = file_path?;
+///     println!("File: {}", file_path);
+/// }
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn glob(pattern: &str) -> impl Iterator<Item = WinResult<String>> {
+	GlobIter::new(pattern)
+}
+
+/// Returns an iterator over each part of the path. The prefix, like `C:` or
+/// `\\server\share`, is the first part, if any.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// let parts = path::iter("\\\\server\\share\\dir/foo.txt")
+///     .collect::<Vec<_>>(); // \\server\share, dir, foo.txt
+/// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub fn iter(full_path: &str) -> impl Iterator<Item = &str> {
-	PathIterator { path: full_path }
+	let path = WinPath::parse(full_path);
+	Some(path.prefix_str())
+		.filter(|prefix| !prefix.is_empty())
+		.into_iter()
+		.chain(path.components())
 }
 
 /// Extracts the file name from a full path, if any.
@@ -58,15 +142,7 @@ pub fn iter(full_path: &str) -> impl Iterator<Item = &str> {
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub fn get_file_name(full_path: &str) -> Option<&str> {
-	full_path.rfind('\\')
-		.map_or(
-			Some(full_path), // if no backslash, the whole string is the file name
-			|idx| if idx == full_path.chars().count() - 1 {
-				None // last char is '\\', no file name
-			} else {
-				Some(&full_path[idx + 1..])
-			},
-		)
+	WinPath::parse(full_path).file_name()
 }
 
 /// Extracts the full path, but the last part.
@@ -83,8 +159,44 @@ pub fn get_file_name(full_path: &str) -> Option<&str> {
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub fn get_path(full_path: &str) -> Option<&str> {
-	full_path.rfind('\\') // if no backslash, the whole string is the file name, so no path
-		.map(|idx| &full_path[0..idx])
+	let path = WinPath::parse(full_path);
+	let name_idx = path.file_name_idx()
+		.unwrap_or(full_path.len()); // ends with a separator, the whole path is a directory
+
+	if name_idx <= path.prefix_len {
+		None // no separator, the whole string is the file name, so no path
+	} else {
+		Some(path.trim_separators(&full_path[..name_idx]))
+	}
+}
+
+/// Tells whether the file functions automatically convert long paths to the
+/// `\\\\?\\` verbatim form. See
+/// [`set_long_paths`](crate::path::set_long_paths).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn long_paths() -> bool {
+	LONG_PATHS.load(Ordering::Relaxed)
+}
+
+/// Tells whether the file name matches the wildcard pattern, with the same
+/// rules of
+/// [`HFINDFILE::FindFirstFile`](crate::prelude::KernelHfindfile::FindFirstFile).
+/// See [`Wildcard`](crate::path::Wildcard) for the details.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// let names = ["a.txt", "B.TXT", "c.txt.bak", "README"];
+/// let txts = names.iter()
+///     .filter(|name| path::matches_wildcard("*.txt", name))
+///     .collect::<Vec<_>>(); // a.txt, B.TXT
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn matches_wildcard(pattern: &str, file_name: &str) -> bool {
+	Wildcard::new(pattern).is_match(file_name)
 }
 
 /// Tells whether the full path ends in one of the given extensions,
@@ -110,6 +222,54 @@ pub fn has_extension(full_path: &str, extensions: &[impl AsRef<str>]) -> bool {
 		.is_some()
 }
 
+/// Converts an absolute path to the `\\\\?\\` verbatim form, which is not
+/// limited to `MAX_PATH` chars. The path is normalized first, and trailing
+/// dots and spaces are removed from each component, because Windows doesn't do
+/// either for verbatim paths.
+///
+/// Drive paths become `\\\\?\\C:\\…`, and UNC paths become
+/// `\\\\?\\UNC\\server\\share\\…`. Other paths – relative, already verbatim, or
+/// device paths – are returned unchanged; relative paths can be made absolute
+/// with [`GetFullPathName`](crate::GetFullPathName).
+///
+/// To convert the path back, use
+/// [`from_long_path`](crate::path::from_long_path).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// let p = path::to_long_path("C:\\Temp\\..\\foo"); // \\?\C:\foo
+/// let q = path::to_long_path("\\\\srv\\share\\a"); // \\?\UNC\srv\share\a
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn to_long_path(full_path: &str) -> String {
+	let path = WinPath::parse(full_path);
+	match path.kind() {
+		PathKind::Drive => format!("\\\\?\\{}", win32_trim(&path.normalize())),
+		PathKind::Unc => format!("\\\\?\\UNC\\{}", &win32_trim(&path.normalize())[2..]), // skip leading \\
+		_ => full_path.to_owned(),
+	}
+}
+
+/// Removes the trailing dots and spaces of each component of a normalized
+/// path, like Win32 does; components left empty are dropped.
+fn win32_trim(normalized: &str) -> String {
+	let path = WinPath::parse(normalized);
+	let mut buf = path.prefix_str().to_owned();
+	if path.has_root() {
+		buf.push('\\');
+	}
+	let parts = path.components()
+		.map(|comp| comp.trim_end_matches(['.', ' ']))
+		.filter(|comp| !comp.is_empty())
+		.collect::<Vec<_>>();
+	buf.push_str(&parts.join("\\"));
+	buf
+}
+
 /// Replaces the extension by the given one.
 ///
 /// # Examples
@@ -123,26 +283,22 @@ pub fn has_extension(full_path: &str, extensions: &[impl AsRef<str>]) -> bool {
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 pub fn replace_extension(full_path: &str, new_extension: &str) -> String {
-	if let Some(last) = full_path.chars().last() {
-		if last == '\\' { // full_path is a directory, do nothing
-			return rtrim_backslash(full_path).to_owned();
-		}
-	}
+	let path = WinPath::parse(full_path);
+	let name_idx = match path.file_name_idx() {
+		Some(name_idx) => name_idx,
+		None => return rtrim_backslash(full_path).to_owned(), // full_path is a directory, do nothing
+	};
 
-	let new_has_dot = new_extension.chars().next() == Some('.');
-	full_path.rfind('.')
-		.map_or_else(
-			|| format!("{}{}{}", // file name without extension, just append it
-				full_path,
-				if new_has_dot { "" } else { "." },
-				new_extension,
-			),
-			|idx| format!("{}{}{}",
-				&full_path[0..idx],
-				if new_has_dot { "" } else { "." },
-				new_extension,
-			),
-		)
+	let new_has_dot = new_extension.starts_with('.');
+	let stem = full_path[name_idx..].rfind('.')
+		.filter(|dot_idx| *dot_idx > 0) // a leading dot is not an extension
+		.map_or(full_path, |dot_idx| &full_path[..name_idx + dot_idx]);
+
+	format!("{}{}{}",
+		stem,
+		if new_has_dot { "" } else { "." },
+		new_extension,
+	)
 }
 
 /// Replaces the file name by the given one.
@@ -177,7 +333,41 @@ pub fn replace_path(full_path: &str, new_path: &str) -> String {
 		file_name.unwrap_or(""))
 }
 
-/// Removes a trailing backslash, if any.
+/// Enables or disables the automatic conversion of long paths to the
+/// `\\\\?\\` verbatim form, so that deep directory trees can be used even without
+/// the [long path opt-in](https://docs.microsoft.com/en-us/windows/win32/fileio/maximum-file-path-limitation)
+/// in the registry. This setting is global, and disabled by default.
+///
+/// When enabled, paths whose full form has at least 248 chars – the
+/// `CreateDirectory` limit – are converted with
+/// [`to_long_path`](crate::path::to_long_path), after relative paths are made
+/// absolute with [`GetFullPathName`](crate::GetFullPathName). Shorter paths
+/// are passed unchanged. This affects:
+/// * [`CopyFile`](crate::CopyFile);
+/// * [`DeleteFile`](crate::DeleteFile);
+/// * [`GetFileAttributes`](crate::GetFileAttributes);
+/// * [`HFILE::CreateFile`](crate::prelude::KernelHfile::CreateFile), and so
+///   [`File`](crate::File), [`FileMapped`](crate::FileMapped) and
+///   [`Ini`](crate::Ini);
+/// * [`HFINDFILE::FindFirstFile`](crate::prelude::KernelHfindfile::FindFirstFile),
+///   and so [`HFINDFILE::iter`](crate::prelude::KernelHfindfile::iter);
+/// * [`MoveFile`](crate::MoveFile);
+/// * [`ReplaceFile`](crate::ReplaceFile).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path;
+///
+/// path::set_long_paths(true);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub fn set_long_paths(enable: bool) {
+	LONG_PATHS.store(enable, Ordering::Relaxed);
+}
+
+/// Removes a trailing backslash, or forward slash, if any.
 ///
 /// # Examples
 ///
@@ -193,7 +383,7 @@ pub fn rtrim_backslash(full_path: &str) -> &str {
 		.last()
 		.map_or(
 			full_path, // empty string
-			|last_ch| if last_ch == '\\' {
+			|last_ch| if last_ch == '\\' || last_ch == '/' {
 				let mut chars = full_path.chars();
 				chars.next_back(); // remove last char
 				chars.as_str()
@@ -203,31 +393,977 @@ pub fn rtrim_backslash(full_path: &str) -> &str {
 		)
 }
 
-//------------------------------------------------------------------------------
+/// The prefix of a [`WinPath`](crate::path::WinPath), which comes before the
+/// root separator.
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathPrefix<'a> {
+	/// Drive letter, like `C:`.
+	Disk(char),
+	/// UNC share, like `\\server\share`.
+	Unc {
+		/// Server name.
+		server: &'a str,
+		/// Share name.
+		share: &'a str,
+	},
+	/// Verbatim drive letter, like `\\?\C:`.
+	VerbatimDisk(char),
+	/// Verbatim UNC share, like `\\?\UNC\server\share`.
+	VerbatimUnc {
+		/// Server name.
+		server: &'a str,
+		/// Share name.
+		share: &'a str,
+	},
+	/// Any other verbatim prefix, like `\\?\Volume{…}`.
+	Verbatim(&'a str),
+	/// Device namespace, like `\\.\COM1`.
+	Device(&'a str),
+}
 
-struct PathIterator<'a> {
-	path: &'a str,
+impl<'a> std::fmt::Display for PathPrefix<'a> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Disk(letter) => write!(f, "{}:", letter),
+			Self::Unc { server, share } => write!(f, "\\\\{}{}{}",
+				server, if share.is_empty() { "" } else { "\\" }, share),
+			Self::VerbatimDisk(letter) => write!(f, "\\\\?\\{}:", letter),
+			Self::VerbatimUnc { server, share } => write!(f, "\\\\?\\UNC\\{}{}{}",
+				server, if share.is_empty() { "" } else { "\\" }, share),
+			Self::Verbatim(name) => write!(f, "\\\\?\\{}", name),
+			Self::Device(name) => write!(f, "\\\\.\\{}", name),
+		}
+	}
 }
 
-impl<'a> Iterator for PathIterator<'a> {
-	type Item = &'a str;
+impl<'a> PathPrefix<'a> {
+	/// Tells whether the prefix starts with `\\?\`, which tells Windows to
+	/// pass the path verbatim, without normalization. In verbatim paths, only
+	/// backslashes are separators.
+	pub const fn is_verbatim(&self) -> bool {
+		matches!(self,
+			Self::VerbatimDisk(_) | Self::VerbatimUnc { .. } | Self::Verbatim(_))
+	}
 
-	fn next(&mut self) -> Option<Self::Item> {
-		if self.path.is_empty() {
+	/// Returns the drive letter, if any.
+	pub const fn drive_letter(&self) -> Option<char> {
+		match self {
+			Self::Disk(letter) | Self::VerbatimDisk(letter) => Some(*letter),
+			_ => None,
+		}
+	}
+}
+
+/// How a [`WinPath`](crate::path::WinPath) is anchored, as returned by
+/// [`WinPath::kind`](crate::path::WinPath::kind).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathKind {
+	/// Relative to the current directory, like `dir\file.txt`.
+	Relative,
+	/// Relative to the root of the current drive, like `\dir\file.txt`.
+	Rooted,
+	/// Relative to the current directory of the given drive, like
+	/// `C:dir\file.txt`.
+	DriveRelative,
+	/// Absolute path in a drive, like `C:\dir\file.txt`.
+	Drive,
+	/// Absolute path in a UNC share, like `\\server\share\file.txt`.
+	Unc,
+	/// Verbatim path, like `\\?\C:\file.txt`.
+	Verbatim,
+	/// Device path, like `\\.\COM1`.
+	Device,
+}
+
+/// A parsed Windows path, borrowing the original string.
+///
+/// The path is split into an optional [`PathPrefix`](crate::path::PathPrefix),
+/// an optional root separator, and the components. Both backslashes and
+/// forward slashes are separators, except in verbatim paths.
+///
+/// All operations are purely lexical: the file system is never accessed.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path::{PathKind, PathPrefix, WinPath};
+///
+/// let p = WinPath::parse("\\\\server\\share\\dir\\..\\foo.txt");
+/// assert_eq!(p.kind(), PathKind::Unc);
+/// assert_eq!(p.prefix(), Some(PathPrefix::Unc { server: "server", share: "share" }));
+/// assert_eq!(p.normalize(), "\\\\server\\share\\foo.txt");
+///
+/// let q = WinPath::parse("C:\\Temp\\a\\b.txt");
+/// assert_eq!(q.relative_to("C:\\Temp\\x").unwrap(), "..\\a\\b.txt");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WinPath<'a> {
+	raw: &'a str,
+	prefix: Option<PathPrefix<'a>>,
+	prefix_len: usize,
+	has_root: bool,
+}
+
+impl<'a> std::fmt::Display for WinPath<'a> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.raw)
+	}
+}
+
+impl<'a> WinPath<'a> {
+	/// Parses the given path. Parsing never fails, any string is a path.
+	pub fn parse(path: &'a str) -> WinPath<'a> {
+		let (prefix, prefix_len) = parse_prefix(path);
+		let is_verbatim = prefix.is_some_and(|p| p.is_verbatim());
+		let has_root = path[prefix_len..].chars()
+			.next()
+			.is_some_and(|ch| is_separator(ch, is_verbatim));
+
+		Self { raw: path, prefix, prefix_len, has_root }
+	}
+
+	/// Returns the original string.
+	pub const fn as_str(&self) -> &'a str {
+		self.raw
+	}
+
+	/// Returns an iterator over the components after the prefix and the root,
+	/// skipping empty ones. The `.` and `..` components are returned as they
+	/// are.
+	pub fn components(&self) -> impl Iterator<Item = &'a str> + 'a {
+		let is_verbatim = self.is_verbatim();
+		self.raw[self.prefix_len..]
+			.split(move |ch| is_separator(ch, is_verbatim))
+			.filter(|comp| !comp.is_empty())
+	}
+
+	/// Returns the last component, unless the path ends with a separator, or
+	/// has only a prefix and a root.
+	pub fn file_name(&self) -> Option<&'a str> {
+		self.file_name_idx()
+			.map(|idx| &self.raw[idx..])
+	}
+
+	/// Tells whether the path has a root separator right after the prefix.
+	pub const fn has_root(&self) -> bool {
+		self.has_root
+	}
+
+	/// Tells whether the path doesn't depend on the current directory or the
+	/// current drive.
+	pub fn is_absolute(&self) -> bool {
+		!matches!(self.kind(),
+			PathKind::Relative | PathKind::Rooted | PathKind::DriveRelative)
+	}
+
+	/// Tells whether the path is verbatim, starting with `\\?\`.
+	pub fn is_verbatim(&self) -> bool {
+		self.prefix.is_some_and(|p| p.is_verbatim())
+	}
+
+	/// Joins another path to this one, following the Windows rules:
+	/// * if `other` is absolute, it replaces this path;
+	/// * if `other` is rooted, like `\dir`, it's placed after the prefix of
+	///   this path;
+	/// * if `other` is drive-relative, like `D:dir`, it's joined only if this
+	///   path is in the same drive, otherwise it replaces this path;
+	/// * otherwise `other` is appended with a backslash.
+	///
+	/// The result is not normalized.
+	pub fn join(&self, other: &str) -> String {
+		let other_path = WinPath::parse(other);
+		match other_path.kind() {
+			PathKind::Relative => {
+				if other.is_empty() {
+					return self.raw.to_owned();
+				}
+				let mut buf = self.raw.to_owned();
+				if self.needs_separator() {
+					buf.push('\\');
+				}
+				if self.is_verbatim() {
+					buf.extend(other.chars().map(|ch| if ch == '/' { '\\' } else { ch }));
+				} else {
+					buf.push_str(other);
+				}
+				buf
+			},
+			PathKind::Rooted => format!("{}{}", self.prefix_str(), other),
+			PathKind::DriveRelative => {
+				let same_drive = match (self.drive_letter(), other_path.drive_letter()) {
+					(Some(mine), Some(theirs)) => mine.eq_ignore_ascii_case(&theirs),
+					_ => false,
+				};
+				if same_drive {
+					self.join(&other[other_path.prefix_len..])
+				} else {
+					other.to_owned()
+				}
+			},
+			_ => other.to_owned(),
+		}
+	}
+
+	/// Returns how the path is anchored.
+	pub fn kind(&self) -> PathKind {
+		match self.prefix {
+			None => if self.has_root { PathKind::Rooted } else { PathKind::Relative },
+			Some(PathPrefix::Disk(_)) => if self.has_root { PathKind::Drive } else { PathKind::DriveRelative },
+			Some(PathPrefix::Unc { .. }) => PathKind::Unc,
+			Some(PathPrefix::Device(_)) => PathKind::Device,
+			Some(_) => PathKind::Verbatim,
+		}
+	}
+
+	/// Lexically normalizes the path:
+	/// * forward slashes become backslashes;
+	/// * repeated separators, `.` components and trailing separators are
+	///   removed;
+	/// * `..` components remove the previous one, and are dropped at the root.
+	///
+	/// Verbatim paths are returned unchanged, since Windows doesn't normalize
+	/// them either. An empty relative path becomes `.`.
+	pub fn normalize(&self) -> String {
+		if self.is_verbatim() {
+			return self.raw.to_owned();
+		}
+
+		let mut parts = Vec::<&str>::default();
+		for comp in self.components() {
+			match comp {
+				"." => {},
+				".." => match parts.last() {
+					Some(last) if *last != ".." => { parts.pop(); },
+					_ => if !self.has_root && self.kind() != PathKind::Unc
+						&& self.kind() != PathKind::Device
+					{
+						parts.push(".."); // can't go above a relative path
+					},
+				},
+				comp => parts.push(comp),
+			}
+		}
+
+		let mut buf = self.prefix.map_or(String::default(), |p| p.to_string());
+		if self.has_root {
+			buf.push('\\');
+		}
+		buf.push_str(&parts.join("\\"));
+		if buf.is_empty() {
+			buf.push('.');
+		}
+		buf
+	}
+
+	/// Returns the path without its last component, keeping the prefix and the
+	/// root. Returns `None` if there are no components.
+	///
+	/// Unlike [`path::get_path`](crate::path::get_path), a trailing separator
+	/// is ignored, and the root separator is kept.
+	pub fn parent(&self) -> Option<&'a str> {
+		let root_end = self.prefix_len + if self.has_root { 1 } else { 0 };
+		let is_verbatim = self.is_verbatim();
+		let trimmed = self.raw.trim_end_matches(|ch| is_separator(ch, is_verbatim));
+		if trimmed.len() <= root_end {
+			return None; // only prefix and root
+		}
+
+		let cut = trimmed[root_end..]
+			.rfind(|ch| is_separator(ch, is_verbatim))
+			.map_or(root_end, |idx| root_end + idx);
+		let parent = self.trim_separators(&self.raw[..cut]);
+		Some(if parent.len() < root_end { &self.raw[..root_end] } else { parent })
+	}
+
+	/// Returns the prefix, if any.
+	pub const fn prefix(&self) -> Option<PathPrefix<'a>> {
+		self.prefix
+	}
+
+	/// Returns the prefix exactly as it appears in the original string, or an
+	/// empty string if there's none.
+	pub fn prefix_str(&self) -> &'a str {
+		&self.raw[..self.prefix_len]
+	}
+
+	/// Computes the relative path which leads from `base` to this path, after
+	/// normalizing both. Names are compared case-insensitively.
+	///
+	/// Returns `None` if the paths have different prefixes or roots, or if
+	/// `base` goes above a relative starting point with `..`.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::prelude::*;
+	/// use winsafe::path::WinPath;
+	///
+	/// let p = WinPath::parse("C:\\Temp\\a\\b.txt");
+	/// assert_eq!(p.relative_to("c:/temp/x/y").unwrap(), "..\\..\\a\\b.txt");
+	/// ```
+	pub fn relative_to(&self, base: &str) -> Option<String> {
+		let (target, base) = (self.normalize(), WinPath::parse(base).normalize());
+		let (target, base) = (WinPath::parse(&target), WinPath::parse(&base));
+
+		let same_prefix = match (target.prefix, base.prefix) {
+			(None, None) => true,
+			(Some(t), Some(b)) => t.to_string().to_lowercase() == b.to_string().to_lowercase(),
+			_ => false,
+		};
+		if !same_prefix || target.has_root != base.has_root {
 			return None;
 		}
 
-		Some(match self.path.find('\\') {
-			Some(idx) => {
-				let cur_part = &self.path[..idx];
-				self.path = &self.path[idx + 1..];
-				cur_part
+		let target_comps = target.components().filter(|c| *c != ".").collect::<Vec<_>>();
+		let base_comps = base.components().filter(|c| *c != ".").collect::<Vec<_>>();
+		let num_common = target_comps.iter()
+			.zip(base_comps.iter())
+			.take_while(|(t, b)| t.to_lowercase() == b.to_lowercase())
+			.count();
+
+		if base_comps[num_common..].contains(&"..") {
+			return None; // can't know the name of the directory above
+		}
+
+		let parts = std::iter::repeat_n("..", base_comps.len() - num_common)
+			.chain(target_comps[num_common..].iter().copied())
+			.collect::<Vec<_>>();
+		Some(if parts.is_empty() { ".".to_owned() } else { parts.join("\\") })
+	}
+
+	fn drive_letter(&self) -> Option<char> {
+		self.prefix.and_then(|p| p.drive_letter())
+	}
+
+	/// Index of the last component, unless the path ends with a separator, or
+	/// has only a prefix and a root.
+	fn file_name_idx(&self) -> Option<usize> {
+		let is_verbatim = self.is_verbatim();
+		let rest = &self.raw[self.prefix_len..];
+		let idx = rest.rfind(|ch| is_separator(ch, is_verbatim))
+			.map_or(0, |idx| idx + 1); // separators are ASCII
+		if idx == rest.len() {
+			None
+		} else {
+			Some(self.prefix_len + idx)
+		}
+	}
+
+	/// Whether appending a relative path requires a separator.
+	fn needs_separator(&self) -> bool {
+		let is_verbatim = self.is_verbatim();
+		match self.raw.chars().last() {
+			None => false, // empty path
+			Some(ch) if is_separator(ch, is_verbatim) => false,
+			Some(_) => self.kind() != PathKind::DriveRelative
+				|| self.raw.len() > self.prefix_len, // bare C: is joined as C:dir
+		}
+	}
+
+	/// Removes all trailing separators.
+	fn trim_separators<'s>(&self, s: &'s str) -> &'s str {
+		let is_verbatim = self.is_verbatim();
+		s.trim_end_matches(|ch| is_separator(ch, is_verbatim))
+	}
+}
+
+/// A compiled wildcard pattern, which matches file names with the same rules
+/// of
+/// [`HFINDFILE::FindFirstFile`](crate::prelude::KernelHfindfile::FindFirstFile),
+/// without accessing the file system.
+///
+/// Names are compared case-insensitively, and:
+/// * `*` matches zero or more characters;
+/// * `?` matches exactly one character, or zero characters when at the end of
+///   the name or right before a dot – so `???` matches `a` and `abc`, and
+///   `a?.txt` matches `a.txt`;
+/// * `*.*` matches any name, even one without a dot;
+/// * `*` followed by a dot doesn't go past the last dot of the name, so
+///   `*.txt` matches `a.b.txt`, but not `a.txt.bak`;
+/// * a dot followed by `*`, `?` or at the end of the pattern also matches the
+///   end of the name, so `foo.` matches `foo`, and `*.` matches names without
+///   an extension.
+///
+/// `FindFirstFile` also matches the pattern against the 8.3 short name of each
+/// file, which is why `*.htm` also finds `index.html` – whose short name is
+/// `INDEX~1.HTM`. To reproduce this quirk, use
+/// [`is_match_find_data`](crate::path::Wildcard::is_match_find_data).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::path::Wildcard;
+///
+/// let w = Wildcard::new("*.txt");
+/// assert!(w.is_match("notes.TXT"));
+/// assert!(!w.is_match("notes.txt.bak"));
+///
+/// let no_ext = Wildcard::new("*.");
+/// assert!(no_ext.is_match("README"));
+/// assert!(!no_ext.is_match("README.md"));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Wildcard {
+	pattern: String,
+	tokens: Vec<WildToken>,
+}
+
+impl std::fmt::Display for Wildcard {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.pattern)
+	}
+}
+
+impl Wildcard {
+	/// Compiles the given pattern. Any string is a valid pattern; separators
+	/// are matched literally.
+	pub fn new(pattern: &str) -> Wildcard {
+		let tokens = if pattern == "*.*" {
+			vec![WildToken::Star] // the classic DOS "everything"
+		} else {
+			let chars = pattern.chars().collect::<Vec<_>>();
+			chars.iter()
+				.enumerate()
+				.map(|(idx, ch)| {
+					let next = chars.get(idx + 1).copied();
+					match *ch {
+						'?' => WildToken::DosQm,
+						'*' if next == Some('.') => WildToken::DosStar,
+						'*' => WildToken::Star,
+						'.' if matches!(next, None | Some('*') | Some('?')) => WildToken::DosDot,
+						ch => WildToken::Char(ch),
+					}
+				})
+				.collect()
+		};
+
+		Self { pattern: pattern.to_owned(), tokens }
+	}
+
+	/// Returns the original pattern.
+	pub fn as_str(&self) -> &str {
+		&self.pattern
+	}
+
+	/// Tells whether the file name matches the pattern.
+	pub fn is_match(&self, file_name: &str) -> bool {
+		let name = file_name.chars().collect::<Vec<_>>();
+		let last_dot = name.iter().rposition(|ch| *ch == '.');
+		let mut states = vec![false; self.tokens.len() + 1]; // positions in the pattern
+		states[0] = true;
+
+		for (idx, ch) in name.iter().enumerate() {
+			self.follow_empty(&mut states, Some(*ch));
+			let mut next_states = vec![false; states.len()];
+			for (pos, tok) in self.tokens.iter().enumerate().filter(|(pos, _)| states[*pos]) {
+				match *tok {
+					WildToken::Char(pat_ch) => if eq_ignore_case(pat_ch, *ch) {
+						next_states[pos + 1] = true;
+					},
+					WildToken::Star => next_states[pos] = true,
+					WildToken::DosStar => if last_dot.is_none_or(|dot| idx < dot) {
+						next_states[pos] = true; // the last dot must be matched by what comes next
+					},
+					WildToken::DosQm => if *ch != '.' {
+						next_states[pos + 1] = true;
+					},
+					WildToken::DosDot => if *ch == '.' {
+						next_states[pos + 1] = true;
+					},
+				}
+			}
+			if !next_states.contains(&true) {
+				return false;
+			}
+			states = next_states;
+		}
+
+		self.follow_empty(&mut states, None);
+		states[self.tokens.len()]
+	}
+
+	/// Tells whether the long file name, or the 8.3 short name, of the
+	/// [`WIN32_FIND_DATA`](crate::WIN32_FIND_DATA) matches the pattern, just
+	/// like `FindFirstFile` does.
+	pub fn is_match_find_data(&self, wfd: &WIN32_FIND_DATA) -> bool {
+		let short_name = wfd.cAlternateFileName();
+		self.is_match(&wfd.cFileName())
+			|| (!short_name.is_empty() && self.is_match(&short_name))
+	}
+
+	/// Marks the pattern positions which can be reached without consuming a
+	/// char; `ch` is the next char of the name, or `None` at its end.
+	fn follow_empty(&self, states: &mut [bool], ch: Option<char>) {
+		for (pos, tok) in self.tokens.iter().enumerate() { // transitions only go forward
+			if states[pos] && match *tok {
+				WildToken::Star | WildToken::DosStar => true,
+				WildToken::DosQm => ch.is_none_or(|ch| ch == '.'),
+				WildToken::DosDot => ch.is_none(),
+				WildToken::Char(_) => false,
+			} {
+				states[pos + 1] = true;
+			}
+		}
+	}
+}
+
+//------------------------------------------------------------------------------
+
+/// Converts the path to the verbatim form if
+/// [`set_long_paths`](crate::path::set_long_paths) is enabled and the path is
+/// long, otherwise returns it unchanged.
+pub(crate) fn to_win32_path(file_path: &str) -> Cow<'_, str> {
+	const LONG_LEN: usize = MAX_PATH - 12; // room for an 8.3 file name, like CreateDirectory
+
+	if !long_paths() {
+		return Cow::Borrowed(file_path);
+	}
+
+	let full_path = match WinPath::parse(file_path).kind() {
+		PathKind::Drive | PathKind::Unc => Cow::Borrowed(file_path),
+		PathKind::Relative | PathKind::Rooted | PathKind::DriveRelative =>
+			match GetFullPathName(file_path) {
+				Ok(full_path) => Cow::Owned(full_path),
+				Err(_) => return Cow::Borrowed(file_path), // let the caller fail
 			},
-			None => {
-				let cur_part = self.path; // until the end
-				self.path = &self.path[self.path.len()..]; // empty
-				cur_part
+		PathKind::Verbatim | PathKind::Device => return Cow::Borrowed(file_path),
+	};
+
+	if full_path.encode_utf16().count() < LONG_LEN {
+		Cow::Borrowed(file_path)
+	} else {
+		Cow::Owned(to_long_path(&full_path))
+	}
+}
+
+/// An element of a compiled [`Wildcard`](crate::path::Wildcard), after the
+/// same translation made by `FindFirstFile`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum WildToken {
+	/// Matches the char itself, case-insensitively.
+	Char(char),
+	/// Matches zero or more chars.
+	Star,
+	/// A `*` before a dot: matches zero or more chars, up to the last dot.
+	DosStar,
+	/// A `?`: matches one char, or zero before a dot or at the end.
+	DosQm,
+	/// A dot before `*`, `?` or at the end: matches a dot, or the end.
+	DosDot,
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+	a == b || a.to_uppercase().eq(b.to_uppercase())
+}
+
+/// Iterator returned by [`glob`](crate::path::glob).
+struct GlobIter {
+	comps: Vec<String>,
+	stack: Vec<(String, usize)>, // directory already matched, index of the next component
+	pending: VecDeque<WinResult<String>>,
+}
+
+impl Iterator for GlobIter {
+	type Item = WinResult<String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(item) = self.pending.pop_front() {
+				return Some(item);
+			}
+			let (dir, idx) = self.stack.pop()?;
+			self.visit(&dir, idx);
+		}
+	}
+}
+
+impl GlobIter {
+	fn new(pattern: &str) -> Self {
+		let path = WinPath::parse(pattern);
+		let mut comps = path.components()
+			.map(|comp| comp.to_owned())
+			.collect::<Vec<_>>();
+		comps.dedup_by(|a, b| a == "**" && b == "**"); // consecutive ** are redundant
+		if comps.last().is_some_and(|last| last == "**") {
+			comps.push("*".to_owned()); // everything below
+		}
+
+		// Components before the first wildcard are simply joined, but the last
+		// one is always checked.
+		let num_literal = comps.iter()
+			.take_while(|comp| !has_wildcards(comp))
+			.count()
+			.min(comps.len().saturating_sub(1));
+		let root = format!("{}{}", path.prefix_str(), if path.has_root() { "\\" } else { "" });
+		let base = comps[..num_literal].iter()
+			.fold(root, |dir, comp| WinPath::parse(&dir).join(comp));
+		comps.drain(..num_literal);
+
+		Self {
+			stack: if comps.is_empty() { Vec::default() } else { vec![(base, 0)] },
+			comps,
+			pending: VecDeque::default(),
+		}
+	}
+
+	/// Matches the component at `idx` within `dir`, queueing the results and
+	/// the directories to be visited next.
+	fn visit(&mut self, dir: &str, idx: usize) {
+		let comp = self.comps[idx].clone();
+		let is_last = idx + 1 == self.comps.len();
+		let mut next_dirs = Vec::<(String, usize)>::default();
+
+		if comp == "**" {
+			next_dirs.push((dir.to_owned(), idx + 1)); // zero directories
+			match list_dir(dir, "*") {
+				Err(e) => self.pending.push_back(Err(e)),
+				Ok(entries) => next_dirs.extend(
+					entries.into_iter()
+						.filter(|(_, attrs)| attrs.has(co::FILE_ATTRIBUTE::DIRECTORY)
+							&& !attrs.has(co::FILE_ATTRIBUTE::REPARSE_POINT))
+						.map(|(sub_dir, _)| (sub_dir, idx)),
+				),
+			}
+		} else if !has_wildcards(&comp) {
+			let entry = WinPath::parse(dir).join(&comp);
+			match GetFileAttributes(&entry) {
+				Ok(attrs) => if is_last {
+					self.pending.push_back(Ok(entry));
+				} else if attrs.has(co::FILE_ATTRIBUTE::DIRECTORY) {
+					next_dirs.push((entry, idx + 1));
+				},
+				Err(co::ERROR::FILE_NOT_FOUND | co::ERROR::PATH_NOT_FOUND) => {},
+				Err(e) => self.pending.push_back(Err(e)),
+			}
+		} else {
+			match list_dir(dir, &comp) {
+				Err(e) => self.pending.push_back(Err(e)),
+				Ok(entries) => for (entry, attrs) in entries {
+					if is_last {
+						self.pending.push_back(Ok(entry));
+					} else if attrs.has(co::FILE_ATTRIBUTE::DIRECTORY) {
+						next_dirs.push((entry, idx + 1));
+					}
+				},
+			}
+		}
+
+		self.stack.extend(next_dirs.into_iter().rev()); // visit in the order found
+	}
+}
+
+fn has_wildcards(s: &str) -> bool {
+	s.contains(['*', '?'])
+}
+
+/// Lists the entries of a directory which match the pattern, skipping `.` and
+/// `..`. A directory which doesn't exist has no entries.
+fn list_dir(dir: &str, pattern: &str) -> WinResult<Vec<(String, co::FILE_ATTRIBUTE)>> {
+	let dir_path = WinPath::parse(dir);
+	let mut wfd = WIN32_FIND_DATA::default();
+	let (hfind, mut found) = match HFINDFILE::FindFirstFile(&dir_path.join(pattern), &mut wfd) {
+		Err(co::ERROR::PATH_NOT_FOUND) => return Ok(Vec::default()),
+		res => res?,
+	};
+
+	let mut entries = Vec::default();
+	while found {
+		let name = wfd.cFileName();
+		if name != "." && name != ".." {
+			entries.push((dir_path.join(&name), wfd.dwFileAttributes));
+		}
+		found = match hfind.FindNextFile(&mut wfd) {
+			Err(e) => {
+				hfind.FindClose().ok(); // ignore error
+				return Err(e);
 			},
-		})
+			Ok(found) => found,
+		};
+	}
+
+	if !hfind.is_null() {
+		hfind.FindClose()?;
+	}
+	Ok(entries)
+}
+
+const fn is_separator(ch: char, is_verbatim: bool) -> bool {
+	ch == '\\' || (!is_verbatim && ch == '/')
+}
+
+/// Parses the prefix of a path, returning it with its length in bytes.
+fn parse_prefix(path: &str) -> (Option<PathPrefix<'_>>, usize) {
+	let bytes = path.as_bytes();
+	let is_sep = |idx: usize| bytes.get(idx).is_some_and(|by| *by == b'\\' || *by == b'/');
+	let is_drive = |b: &[u8]| b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':';
+
+	if let Some(rest) = path.strip_prefix("\\\\?\\") { // verbatim, only backslashes
+		if rest.get(..4).is_some_and(|unc| unc.eq_ignore_ascii_case("UNC\\")) {
+			let (server, share, len) = parse_server_share(&rest[4..], true);
+			return (Some(PathPrefix::VerbatimUnc { server, share }), 8 + len);
+		} else if is_drive(rest.as_bytes()) {
+			return (Some(PathPrefix::VerbatimDisk(rest.as_bytes()[0] as _)), 6);
+		}
+		let len = rest.find('\\').unwrap_or(rest.len());
+		return (Some(PathPrefix::Verbatim(&rest[..len])), 4 + len);
+	}
+
+	if is_sep(0) && is_sep(1) {
+		if matches!(bytes.get(2), Some(b'.') | Some(b'?')) && is_sep(3) { // \\.\ or //?/
+			let rest = &path[4..];
+			let len = rest.find(|ch| is_separator(ch, false)).unwrap_or(rest.len());
+			return (Some(PathPrefix::Device(&rest[..len])), 4 + len);
+		}
+		let (server, share, len) = parse_server_share(&path[2..], false);
+		return (Some(PathPrefix::Unc { server, share }), 2 + len);
+	}
+
+	if is_drive(bytes) {
+		return (Some(PathPrefix::Disk(bytes[0] as _)), 2);
+	}
+
+	(None, 0)
+}
+
+/// Parses the `server\share` part of a UNC path, returning both with their
+/// total length in bytes.
+fn parse_server_share(s: &str, is_verbatim: bool) -> (&str, &str, usize) {
+	let sep_idx = |s: &str| s.find(|ch| is_separator(ch, is_verbatim)).unwrap_or(s.len());
+
+	let server_len = sep_idx(s);
+	if server_len == s.len() {
+		return (s, "", server_len); // no share
+	}
+
+	let rest = &s[server_len + 1..];
+	let share_len = sep_idx(rest);
+	(&s[..server_len], &rest[..share_len], server_len + 1 + share_len)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_prefixes() {
+		let p = WinPath::parse("\\\\server\\share\\dir\\file.txt");
+		assert_eq!(p.prefix(), Some(PathPrefix::Unc { server: "server", share: "share" }));
+		assert_eq!(p.kind(), PathKind::Unc);
+		assert!(p.is_absolute());
+		assert_eq!(p.components().collect::<Vec<_>>(), ["dir", "file.txt"]);
+
+		let p = WinPath::parse("//server/share");
+		assert_eq!(p.prefix(), Some(PathPrefix::Unc { server: "server", share: "share" }));
+		assert!(!p.has_root());
+
+		let p = WinPath::parse("\\\\.\\COM1");
+		assert_eq!(p.prefix(), Some(PathPrefix::Device("COM1")));
+		assert_eq!(p.kind(), PathKind::Device);
+
+		let p = WinPath::parse("\\\\?\\C:\\a/b\\c");
+		assert_eq!(p.prefix(), Some(PathPrefix::VerbatimDisk('C')));
+		assert_eq!(p.kind(), PathKind::Verbatim);
+		assert_eq!(p.components().collect::<Vec<_>>(), ["a/b", "c"]); // only backslashes
+
+		let p = WinPath::parse("\\\\?\\UNC\\srv\\sh\\f");
+		assert_eq!(p.prefix(), Some(PathPrefix::VerbatimUnc { server: "srv", share: "sh" }));
+		assert_eq!(p.prefix_str(), "\\\\?\\UNC\\srv\\sh");
+
+		let p = WinPath::parse("\\\\?\\Volume{1234}\\x");
+		assert_eq!(p.prefix(), Some(PathPrefix::Verbatim("Volume{1234}")));
+		assert_eq!(p.file_name(), Some("x"));
+	}
+
+	#[test]
+	fn parse_kinds() {
+		assert_eq!(WinPath::parse("C:\\x").kind(), PathKind::Drive);
+		assert_eq!(WinPath::parse("c:x\\y").kind(), PathKind::DriveRelative);
+		assert_eq!(WinPath::parse("C:").kind(), PathKind::DriveRelative);
+		assert_eq!(WinPath::parse("\\x").kind(), PathKind::Rooted);
+		assert_eq!(WinPath::parse("x\\y").kind(), PathKind::Relative);
+		assert_eq!(WinPath::parse("").kind(), PathKind::Relative);
+		assert!(!WinPath::parse("C:x").is_absolute());
+		assert!(!WinPath::parse("\\x").is_absolute());
+		assert_eq!(WinPath::parse("C:x").prefix(), Some(PathPrefix::Disk('C')));
+	}
+
+	#[test]
+	fn file_name_and_parent() {
+		let p = WinPath::parse("C:\\dir\\file.txt");
+		assert_eq!(p.file_name(), Some("file.txt"));
+		assert_eq!(p.parent(), Some("C:\\dir"));
+		assert_eq!(WinPath::parse("C:\\dir").parent(), Some("C:\\"));
+		assert_eq!(WinPath::parse("C:\\").parent(), None);
+		assert_eq!(WinPath::parse("C:\\dir\\").file_name(), None);
+		assert_eq!(WinPath::parse("C:\\dir\\").parent(), Some("C:\\"));
+		assert_eq!(WinPath::parse("C:file").parent(), Some("C:"));
+		assert_eq!(WinPath::parse("\\\\srv\\sh\\a").parent(), Some("\\\\srv\\sh\\"));
+	}
+
+	#[test]
+	fn normalize() {
+		let cases = [
+			("C:/a//b/./c/../d/", "C:\\a\\b\\d"),
+			("C:\\..\\..\\a", "C:\\a"),
+			("a\\..\\..\\b", "..\\b"),
+			("a\\..", "."),
+			("", "."),
+			("C:a\\..\\..", "C:.."),
+			("\\\\srv\\sh\\x\\..\\..\\y", "\\\\srv\\sh\\y"),
+			("//srv/sh/x", "\\\\srv\\sh\\x"),
+			("\\\\.\\pipe\\a\\..\\b", "\\\\.\\pipe\\b"),
+			("\\\\?\\C:\\a\\..\\b", "\\\\?\\C:\\a\\..\\b"), // verbatim is untouched
+		];
+		for (path, expected) in cases {
+			assert_eq!(WinPath::parse(path).normalize(), expected, "{}", path);
+		}
+	}
+
+	#[test]
+	fn join() {
+		let cases = [
+			("C:\\a", "b\\c", "C:\\a\\b\\c"),
+			("C:\\a\\", "b", "C:\\a\\b"),
+			("C:\\a", "", "C:\\a"),
+			("C:\\a", "D:\\x", "D:\\x"),
+			("C:\\a", "\\x", "C:\\x"),
+			("\\\\srv\\sh\\a", "\\x", "\\\\srv\\sh\\x"),
+			("C:\\a", "c:x", "C:\\a\\x"),
+			("C:\\a", "D:x", "D:x"),
+			("C:", "x", "C:x"),
+			("", "x", "x"),
+			("\\\\?\\C:\\a", "b/c", "\\\\?\\C:\\a\\b\\c"),
+			("a", "\\\\srv\\sh", "\\\\srv\\sh"),
+		];
+		for (base, other, expected) in cases {
+			assert_eq!(WinPath::parse(base).join(other), expected, "{} + {}", base, other);
+		}
+	}
+
+	#[test]
+	fn long_path() {
+		let cases = [
+			("C:\\Temp\\..\\foo", "\\\\?\\C:\\foo"),
+			("C:\\dir\\file.txt.", "\\\\?\\C:\\dir\\file.txt"),
+			("C:\\dir. .\\file.txt . ", "\\\\?\\C:\\dir\\file.txt"),
+			("C:\\a\\...\\b", "\\\\?\\C:\\a\\b"),
+			("C:\\", "\\\\?\\C:\\"),
+			("\\\\srv\\share\\a.\\b", "\\\\?\\UNC\\srv\\share\\a\\b"),
+			("\\\\?\\C:\\x.", "\\\\?\\C:\\x."), // already verbatim
+			("dir\\x.", "dir\\x."), // relative
+		];
+		for (path, expected) in cases {
+			assert_eq!(to_long_path(path), expected, "{}", path);
+		}
+	}
+
+	#[test]
+	fn relative_to() {
+		let p = WinPath::parse("C:\\Temp\\a\\b.txt");
+		assert_eq!(p.relative_to("c:/temp/x/y").as_deref(), Some("..\\..\\a\\b.txt"));
+		assert_eq!(p.relative_to("C:\\Temp\\a\\b.txt").as_deref(), Some("."));
+		assert_eq!(p.relative_to("C:\\").as_deref(), Some("Temp\\a\\b.txt"));
+		assert_eq!(p.relative_to("D:\\Temp"), None);
+		assert_eq!(p.relative_to("Temp"), None);
+
+		let r = WinPath::parse("a\\b");
+		assert_eq!(r.relative_to("a\\c").as_deref(), Some("..\\b"));
+		assert_eq!(r.relative_to("..\\x"), None);
+		assert_eq!(WinPath::parse("\\\\srv\\sh\\a").relative_to("\\\\SRV\\SH\\b").as_deref(),
+			Some("..\\a"));
+	}
+
+	#[test]
+	fn wildcard_star_and_qm() {
+		let cases = [
+			("*", "anything.txt", true),
+			("*", "", true),
+			("a*c", "abbbc", true),
+			("a*c", "abbbd", false),
+			("*.txt", "a.b.txt", true),
+			("*.txt", "a.txt.bak", false),
+			("*.txt", ".txt", true),
+			("a?c", "abc", true),
+			("a?c", "ac", false),
+			("a?c", "abbc", false),
+			("a?.txt", "a.txt", true), // ? matches nothing before a dot
+			("a?.txt", "ab.txt", true),
+			("file??", "file1", true), // ... or at the end
+			("?", ".", false),
+			("*x*", "abxcd", true),
+		];
+		for (pattern, name, expected) in cases {
+			assert_eq!(Wildcard::new(pattern).is_match(name), expected, "{} ~ {}", pattern, name);
+		}
+	}
+
+	#[test]
+	fn wildcard_case_insensitive() {
+		assert!(Wildcard::new("*.TXT").is_match("notes.txt"));
+		assert!(Wildcard::new("ÁRVORE.*").is_match("árvore.doc"));
+		assert!(matches_wildcard("Read?E", "readme"));
+	}
+
+	#[test]
+	fn wildcard_dot_quirks() {
+		let cases = [
+			("*.*", "README", true), // the DOS "everything"
+			("*.*", "a.b", true),
+			("*.*", "", true),
+			("*.", "README", true),
+			("*.", "README.md", false),
+			("foo.", "foo", true),
+			("foo.", "foo.", true),
+			("foo.", "foobar", false),
+			("foo.*", "foo", true),
+			("foo.*", "foo.bar", true),
+			("foo.?", "foo", true),
+			("foo.?", "foo.c", true),
+			("foo.?", "foo.cc", false),
+			("a.b", "a.b", true),
+			("a.b", "ab", false),
+		];
+		for (pattern, name, expected) in cases {
+			assert_eq!(Wildcard::new(pattern).is_match(name), expected, "{} ~ {}", pattern, name);
+		}
+	}
+
+	#[test]
+	fn wildcard_short_name() {
+		let w = Wildcard::new("*.htm");
+		let mut wfd = WIN32_FIND_DATA::default();
+		wfd.set_cFileName("index.html");
+		assert!(!w.is_match(&wfd.cFileName()));
+		assert!(!w.is_match_find_data(&wfd)); // no short name
+		wfd.set_cAlternateFileName("INDEX~1.HTM");
+		assert!(w.is_match_find_data(&wfd));
+	}
+
+	#[test]
+	fn glob_components() {
+		let g = GlobIter::new("C:\\Projects\\**\\src\\*.rs");
+		assert_eq!(g.comps, ["**", "src", "*.rs"]);
+		assert_eq!(g.stack, [("C:\\Projects".to_owned(), 0)]);
+
+		let g = GlobIter::new("C:\\a\\**\\**\\b");
+		assert_eq!(g.comps, ["**", "b"]); // consecutive ** are merged
+
+		let g = GlobIter::new("\\\\srv\\sh\\**");
+		assert_eq!(g.comps, ["**", "*"]); // trailing ** lists everything below
+		assert_eq!(g.stack, [("\\\\srv\\sh\\".to_owned(), 0)]);
+
+		let g = GlobIter::new("C:\\a\\b.txt");
+		assert_eq!(g.comps, ["b.txt"]); // the last component is always checked
+		assert_eq!(g.stack, [("C:\\a".to_owned(), 0)]);
+
+		let g = GlobIter::new("*.txt");
+		assert_eq!(g.comps, ["*.txt"]);
+		assert_eq!(g.stack, [("".to_owned(), 0)]);
 	}
 }
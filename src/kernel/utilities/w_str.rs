@@ -0,0 +1,271 @@
+#[cfg(windows)] use std::ffi::OsString;
+#[cfg(windows)] use std::os::windows::ffi::OsStringExt;
+
+use std::borrow::Cow;
+
+use crate::kernel::decl::WString;
+
+/// A borrowed, null-terminated
+/// [Unicode UTF-16](https://docs.microsoft.com/en-us/windows/win32/intl/unicode-in-the-windows-api)
+/// wide string. This is to [`WString`](crate::WString) what
+/// [`CStr`](https://doc.rust-lang.org/std/ffi/struct.CStr.html) is to
+/// [`CString`](https://doc.rust-lang.org/std/ffi/struct.CString.html).
+///
+/// A `&WStr` can be passed to native Win32 functions with
+/// [`as_ptr`](crate::WStr::as_ptr) without copying the string, since the
+/// terminating null is always there.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::{WStr, WString};
+///
+/// static HELLO: [u16; 6] = [0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x00];
+///
+/// let hello = WStr::from_slice_until_nul(&HELLO).unwrap();
+/// assert_eq!(hello.len(), 5);
+/// assert_eq!(hello.to_string(), "Hello");
+///
+/// let owned: WString = hello.to_owned();
+/// assert_eq!(owned.as_wstr(), hello);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct WStr {
+	chars: [u16], // always ends with the terminating null
+}
+
+impl Default for &WStr {
+	fn default() -> Self {
+		const EMPTY: &[u16] = &[0x0000];
+		unsafe { WStr::from_slice_with_nul_unchecked(EMPTY) }
+	}
+}
+
+impl std::fmt::Display for WStr {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		char::decode_utf16(self.as_slice().iter().copied())
+			.map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+			.try_for_each(|ch| std::fmt::Write::write_char(f, ch))
+	}
+}
+
+impl std::fmt::Debug for WStr {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{:?}", self.to_string())
+	}
+}
+
+impl AsRef<[u16]> for WStr {
+	fn as_ref(&self) -> &[u16] {
+		self.as_slice()
+	}
+}
+
+impl AsRef<WStr> for WStr {
+	fn as_ref(&self) -> &WStr {
+		self
+	}
+}
+
+impl ToOwned for WStr {
+	type Owned = WString;
+
+	fn to_owned(&self) -> WString {
+		WString::from(self)
+	}
+}
+
+#[cfg(windows)]
+impl From<&WStr> for OsString {
+	fn from(s: &WStr) -> Self {
+		s.to_os_string()
+	}
+}
+
+impl WStr {
+	/// Creates a view to the given slice, up to and including its first null.
+	///
+	/// Returns `None` if the slice has no null.
+	pub fn from_slice_until_nul(src: &[u16]) -> Option<&WStr> {
+		src.iter()
+			.position(|ch| *ch == 0x0000)
+			.map(|idx| unsafe { Self::from_slice_with_nul_unchecked(&src[..=idx]) })
+	}
+
+	/// Creates a view to the given slice, which must end with a null and have
+	/// no other nulls.
+	///
+	/// # Safety
+	///
+	/// If the last char is not null, methods like
+	/// [`as_ptr`](crate::WStr::as_ptr) will return a pointer to a string which
+	/// is not null-terminated.
+	pub const unsafe fn from_slice_with_nul_unchecked(src: &[u16]) -> &WStr {
+		&*(src as *const [u16] as *const WStr)
+	}
+
+	/// Creates a view to a null-terminated string.
+	///
+	/// # Safety
+	///
+	/// The pointer must point to a valid, null-terminated string, which must
+	/// outlive the returned view and not be modified meanwhile.
+	pub unsafe fn from_ptr<'a>(src: *const u16) -> &'a WStr {
+		let mut len = 0;
+		while *src.add(len) != 0x0000 {
+			len += 1;
+		}
+		Self::from_slice_with_nul_unchecked(std::slice::from_raw_parts(src, len + 1))
+	}
+
+	/// Returns a
+	/// [`LPCWSTR`](https://docs.microsoft.com/en-us/windows/win32/learnwin32/working-with-strings)
+	/// const pointer to the null-terminated string, to be passed to native
+	/// Win32 functions.
+	pub const fn as_ptr(&self) -> *const u16 {
+		self.chars.as_ptr()
+	}
+
+	/// Returns the chars of the string, without the terminating null.
+	pub fn as_slice(&self) -> &[u16] {
+		&self.chars[..self.chars.len() - 1]
+	}
+
+	/// Returns the chars of the string, including the terminating null.
+	pub const fn as_slice_with_nul(&self) -> &[u16] {
+		&self.chars
+	}
+
+	/// Tells whether the string has a length of zero.
+	pub const fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the number of
+	/// [`u16`](https://doc.rust-lang.org/std/primitive.u16.html) characters,
+	/// not counting the terminating null.
+	pub const fn len(&self) -> usize {
+		self.chars.len() - 1
+	}
+
+	/// Converts into an
+	/// [`OsString`](https://doc.rust-lang.org/std/ffi/struct.OsString.html),
+	/// without any validation.
+	#[cfg(windows)]
+	pub fn to_os_string(&self) -> OsString {
+		OsString::from_wide(self.as_slice())
+	}
+
+	/// Converts into
+	/// [`String`](https://doc.rust-lang.org/std/string/struct.String.html) by
+	/// calling
+	/// [`String::from_utf16`](https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf16).
+	///
+	/// To replace invalid characters with U+FFFD instead, use the
+	/// [`Display`](https://doc.rust-lang.org/std/fmt/trait.Display.html)
+	/// implementation, like `to_string`.
+	pub fn to_string_checked(&self) -> Result<String, std::string::FromUtf16Error> {
+		String::from_utf16(self.as_slice())
+	}
+}
+
+/// A string which can be passed to a native Win32 function as a
+/// null-terminated wide string.
+///
+/// Functions which take an `&impl ToWStr` accept a [`WStr`](crate::WStr) or a
+/// [`WString`](crate::WString) without copying, while `str` and `String` are
+/// converted into a new `WString`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::{OutputDebugString, WString};
+///
+/// OutputDebugString("converted at each call");
+///
+/// let msg = WString::from_str("converted only once");
+/// for _ in 0..10 {
+///     OutputDebugString(&msg);
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+pub trait ToWStr {
+	/// Returns the string as a [`WStr`](crate::WStr), borrowed if possible.
+	fn to_wstr(&self) -> Cow<'_, WStr>;
+}
+
+impl ToWStr for WStr {
+	fn to_wstr(&self) -> Cow<'_, WStr> {
+		Cow::Borrowed(self)
+	}
+}
+
+impl ToWStr for WString {
+	fn to_wstr(&self) -> Cow<'_, WStr> {
+		Cow::Borrowed(self.as_wstr())
+	}
+}
+
+impl ToWStr for str {
+	fn to_wstr(&self) -> Cow<'_, WStr> {
+		Cow::Owned(WString::from_str(self))
+	}
+}
+
+impl ToWStr for String {
+	fn to_wstr(&self) -> Cow<'_, WStr> {
+		self.as_str().to_wstr()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn until_nul() {
+		let chars = [0x61, 0x62, 0x0000, 0x63, 0x0000];
+		let ws = WStr::from_slice_until_nul(&chars).unwrap();
+		assert_eq!((ws.len(), ws.as_slice()), (2, &chars[..2]));
+		assert_eq!(ws.as_slice_with_nul(), &chars[..3]);
+		assert_eq!(ws.to_string(), "ab");
+
+		assert!(WStr::from_slice_until_nul(&[0x61, 0x62]).is_none());
+		assert_eq!(<&WStr>::default().as_slice_with_nul(), [0x0000]);
+		assert_eq!(unsafe { WStr::from_ptr(chars.as_ptr()) }, ws);
+	}
+
+	#[test]
+	fn to_owned_keeps_nul() {
+		let ws = WStr::from_slice_until_nul(&[0x61, 0x0000]).unwrap();
+		let owned = ws.to_owned();
+		assert_eq!(owned.as_slice(), [0x61, 0x0000]);
+		assert_eq!(owned.as_wstr(), ws);
+		assert!(<&WStr>::default().to_owned().as_wstr().is_empty());
+	}
+
+	#[test]
+	fn to_wstr_borrows() {
+		let ws = WString::from_str("abc");
+		let Cow::Borrowed(borrowed) = ws.to_wstr() else { panic!("WString was copied") };
+		assert_eq!(borrowed.as_ptr(), unsafe { ws.as_ptr() });
+
+		let Cow::Borrowed(reborrowed) = borrowed.to_wstr() else { panic!("WStr was copied") };
+		assert_eq!(reborrowed.as_ptr(), borrowed.as_ptr());
+
+		let null = WString::default();
+		let Cow::Borrowed(null) = null.to_wstr() else { panic!("null WString was copied") };
+		assert_eq!(null.as_slice_with_nul(), [0x0000]);
+	}
+
+	#[test]
+	fn to_wstr_copies() {
+		for cow in ["abc".to_wstr(), "abc".to_owned().to_wstr()] {
+			assert!(matches!(cow, Cow::Owned(_)));
+			assert_eq!(cow.as_slice_with_nul(), [0x61, 0x62, 0x63, 0x0000]);
+		}
+		assert_eq!("".to_wstr().as_slice_with_nul(), [0x0000]);
+	}
+}
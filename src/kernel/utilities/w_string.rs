@@ -1,13 +1,25 @@
-use std::cmp::Ordering;
+use std::borrow::Borrow;
+#[cfg(windows)] use std::ffi::{OsStr, OsString};
+#[cfg(windows)] use std::os::windows::ffi::OsStrExt;
 
 use crate::co;
 use crate::kernel;
-use crate::kernel::decl::{MultiByteToWideChar, WinResult};
+use crate::kernel::decl::{WinResult, WStr};
+
+/// Unicode code points of the [Windows-1252](https://en.wikipedia.org/wiki/Windows-1252)
+/// bytes `0x80` to `0x9f`. Undefined bytes are mapped to the C1 control char
+/// with the same value, like `MultiByteToWideChar` does.
+const WIN1252_80_9F: [u16; 32] = [
+	0x20ac, 0x0081, 0x201a, 0x0192, 0x201e, 0x2026, 0x2020, 0x2021,
+	0x02c6, 0x2030, 0x0160, 0x2039, 0x0152, 0x008d, 0x017d, 0x008f,
+	0x0090, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014,
+	0x02dc, 0x2122, 0x0161, 0x203a, 0x0153, 0x009d, 0x017e, 0x0178,
+];
 
 /// String encodings that can be guessed by
-/// [`WString::guess_encoding`](crate::WString::guess_encoding).
+/// [`WString::detect_encoding`](crate::WString::detect_encoding).
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Encoding {
 	/// Unknown encoding.
 	Unknown,
@@ -48,24 +60,101 @@ impl std::fmt::Display for Encoding {
 	}
 }
 
-/// Stores a `Vec<u16>` buffer for a null-terminated
+/// The result of
+/// [`WString::detect_encoding`](crate::WString::detect_encoding).
+#[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EncodingGuess {
+	/// The detected encoding.
+	pub encoding: Encoding,
+	/// Size of the [BOM](https://en.wikipedia.org/wiki/Byte_order_mark), in
+	/// bytes, or zero if there is none.
+	pub bom_size: usize,
+	/// How confident the detection is, from `0.0` to `1.0`. Data with a BOM,
+	/// and ASCII data without null bytes, have `1.0`.
+	pub confidence: f32,
+}
+
+/// Number of [`u16`](https://doc.rust-lang.org/std/primitive.u16.html)
+/// chars, including the terminating null, which a [`WString`](crate::WString)
+/// stores inline, without a heap allocation.
+const SSO_LEN: usize = 20;
+
+/// Storage of a [`WString`](crate::WString).
+#[derive(Clone)]
+enum Buffer {
+	/// Null string pointer.
+	Null,
+	/// Small buffer stored inline; only the first `len` chars are used.
+	Stack { chars: [u16; SSO_LEN], len: usize },
+	/// Heap-allocated buffer.
+	Heap(Vec<u16>),
+}
+
+impl Buffer {
+	/// Creates a buffer from the given chars, appending a terminating null.
+	/// `max_len` is an upper bound to the number of chars, not counting the
+	/// terminating null; if it fits, the buffer is stored inline.
+	fn from_chars(chars: impl Iterator<Item = u16>, max_len: usize) -> Self {
+		if max_len < SSO_LEN { // room for terminating null
+			let mut buf = [0x0000; SSO_LEN];
+			let mut len = 0;
+			chars.for_each(|ch| {
+				buf[len] = ch;
+				len += 1;
+			});
+			Self::Stack { chars: buf, len: len + 1 } // terminating null already there
+		} else {
+			let mut vec_u16 = Vec::with_capacity(max_len + 1);
+			vec_u16.extend(chars);
+			vec_u16.push(0x0000); // terminating null
+			Self::Heap(vec_u16)
+		}
+	}
+
+	fn as_slice(&self) -> Option<&[u16]> {
+		match self {
+			Self::Null => None,
+			Self::Stack { chars, len } => Some(&chars[..*len]),
+			Self::Heap(vec_u16) => Some(vec_u16.as_slice()),
+		}
+	}
+
+	fn as_mut_slice(&mut self) -> Option<&mut [u16]> {
+		match self {
+			Self::Null => None,
+			Self::Stack { chars, len } => Some(&mut chars[..*len]),
+			Self::Heap(vec_u16) => Some(vec_u16.as_mut_slice()),
+		}
+	}
+}
+
+/// Stores a buffer for a null-terminated
 /// [Unicode UTF-16](https://docs.microsoft.com/en-us/windows/win32/intl/unicode-in-the-windows-api)
 /// wide string natively used by Windows.
 ///
 /// Performs UTF-8 conversions and can be used as a buffer to low-level Win32
 /// functions.
 ///
+/// Short strings are stored inline, so creating them doesn't allocate memory;
+/// larger ones are stored in a `Vec<u16>`. For this reason, moving a `WString`
+/// invalidates any pointer returned by
+/// [`as_ptr`](crate::WString::as_ptr) or
+/// [`as_mut_ptr`](crate::WString::as_mut_ptr).
+///
+/// A borrowed view to a `WString` is a [`WStr`](crate::WStr).
+///
 /// This is struct is mostly used internally by the library, as a bridge between
 /// Windows and Rust strings.
 #[cfg_attr(docsrs, doc(cfg(feature = "kernel")))]
 #[derive(Clone)]
 pub struct WString {
-	vec_u16: Option<Vec<u16>>,
+	buf: Buffer,
 }
 
 impl Default for WString {
 	fn default() -> Self {
-		Self { vec_u16: None }
+		Self { buf: Buffer::Null }
 	}
 }
 
@@ -75,6 +164,45 @@ impl std::fmt::Display for WString {
 	}
 }
 
+impl AsRef<WStr> for WString {
+	fn as_ref(&self) -> &WStr {
+		self.as_wstr()
+	}
+}
+
+impl Borrow<WStr> for WString {
+	fn borrow(&self) -> &WStr {
+		self.as_wstr()
+	}
+}
+
+impl From<&WStr> for WString {
+	fn from(s: &WStr) -> Self {
+		Self::from_wchars_slice(s.as_slice())
+	}
+}
+
+#[cfg(windows)]
+impl From<&OsStr> for WString {
+	fn from(s: &OsStr) -> Self {
+		Self::from_os_str(s)
+	}
+}
+
+#[cfg(windows)]
+impl From<OsString> for WString {
+	fn from(s: OsString) -> Self {
+		Self::from_os_str(&s)
+	}
+}
+
+#[cfg(windows)]
+impl From<WString> for OsString {
+	fn from(s: WString) -> Self {
+		s.to_os_string()
+	}
+}
+
 impl WString {
 	/// Creates and stores a new UTF-16 string from an optional
 	/// [`&str`](https://doc.rust-lang.org/std/primitive.str.html).
@@ -84,11 +212,8 @@ impl WString {
 	/// If `s` is `None`, the internal buffer is not allocated.
 	pub fn from_opt_str(s: Option<&str>) -> WString {
 		Self {
-			vec_u16: s.map(
-				|s| s.encode_utf16()
-					.chain(std::iter::once(0x0000)) // append a terminating null
-					.collect(),
-			)
+			buf: s.map_or(Buffer::Null,
+				|s| Buffer::from_chars(s.encode_utf16(), s.len())), // UTF-16 is never longer than UTF-8
 		}
 	}
 
@@ -100,6 +225,16 @@ impl WString {
 		Self::from_opt_str(Some(s))
 	}
 
+	/// Creates and stores a new UTF-16 string from an
+	/// [`OsStr`](https://doc.rust-lang.org/std/ffi/struct.OsStr.html), without
+	/// any validation.
+	///
+	/// The string will be stored with a terminating null.
+	#[cfg(windows)]
+	pub fn from_os_str(s: &OsStr) -> WString {
+		Self { buf: Buffer::from_chars(s.encode_wide(), s.len()) }
+	}
+
 	/// Creates and stores a new UTF-16 string from a
 	/// [`Vec`](https://doc.rust-lang.org/std/vec/struct.Vec.html) of ordinary
 	/// strings. This new string will be stored as sequential null-separated
@@ -122,7 +257,7 @@ impl WString {
 		);
 		buf16.push(0x0000); // double terminating null
 
-		Self { vec_u16: Some(buf16) }
+		Self { buf: Buffer::Heap(buf16) }
 	}
 
 	/// Creates a new UTF-16 string by copying from a buffer, specifying the
@@ -133,14 +268,8 @@ impl WString {
 		if src.is_null() || num_chars == 0 {
 			Self::default()
 		} else {
-			let tot_chars = num_chars + 1; // add room for terminating null
-			let mut buf16 = vec![0x0000; tot_chars];
-			unsafe {
-				src.copy_to_nonoverlapping(buf16.as_mut_ptr(), tot_chars - 1); // no terminating null to copy
-				*buf16.get_unchecked_mut(tot_chars - 1) = 0x0000;
-			}
-
-			Self { vec_u16: Some(buf16) }
+			let src = unsafe { std::slice::from_raw_parts(src, num_chars) }; // no terminating null to copy
+			Self { buf: Buffer::from_chars(src.iter().copied(), num_chars) }
 		}
 	}
 
@@ -181,11 +310,7 @@ impl WString {
 	/// Panics if the buffer wasn't previously allocated. Be sure to alloc
 	/// enough room, otherwise a buffer overrun may occur.
 	pub unsafe fn as_mut_ptr(&mut self) -> *mut u16 {
-		self.vec_u16.as_mut()
-			.map_or_else(
-				|| panic!("Trying to use an unallocated WString buffer."),
-				|v| v.as_mut_ptr(),
-			)
+		self.as_mut_slice().as_mut_ptr()
 	}
 
 	/// Returns a
@@ -195,9 +320,9 @@ impl WString {
 	///
 	/// **Note:** Returns a null pointer if the buffer wasn't previously
 	/// allocated. Make sure the `WString` object outlives the function call,
-	/// otherwise it will point to an invalid memory location.
+	/// and is not moved, otherwise it will point to an invalid memory location.
 	pub unsafe fn as_ptr(&self) -> *const u16 {
-		self.vec_u16.as_ref()
+		self.buf.as_slice()
 			.map_or(std::ptr::null(), |v| v.as_ptr())
 	}
 
@@ -210,11 +335,8 @@ impl WString {
 	/// Panics if the buffer wasn't previously allocated. Be sure to alloc
 	/// enough room, otherwise a buffer overrun may occur.
 	pub fn as_mut_slice(&mut self) -> &mut [u16] {
-		self.vec_u16.as_mut()
-			.map_or_else(
-				|| panic!("Trying to use an unallocated WString buffer."),
-				|v| v.as_mut_slice(),
-			)
+		self.buf.as_mut_slice()
+			.unwrap_or_else(|| panic!("Trying to use an unallocated WString buffer."))
 	}
 
 	/// Returns a slice to the internal UTF-16 string buffer.
@@ -225,18 +347,26 @@ impl WString {
 	/// `WString` object outlives the function call, otherwise it will point to
 	/// an invalid memory location.
 	pub fn as_slice(&self) -> &[u16] {
-		self.vec_u16.as_ref()
-			.map_or_else(
-				|| panic!("Trying to use an unallocated WString buffer."),
-				|v| v.as_slice(),
-			)
+		self.buf.as_slice()
+			.unwrap_or_else(|| panic!("Trying to use an unallocated WString buffer."))
+	}
+
+	/// Returns a borrowed [`WStr`](crate::WStr) view of the string, up to the
+	/// first null.
+	///
+	/// A null string pointer, or a buffer without any null, results in an
+	/// empty string.
+	pub fn as_wstr(&self) -> &WStr {
+		self.buf.as_slice()
+			.and_then(WStr::from_slice_until_nul)
+			.unwrap_or_default()
 	}
 
 	/// Returns the size of the allocated internal buffer.
 	///
 	/// If the buffer was not allocated yet, returns zero.
 	pub fn buffer_size(&self) -> usize {
-		self.vec_u16.as_ref()
+		self.buf.as_slice()
 			.map_or(0, |v| v.len())
 	}
 
@@ -254,27 +384,19 @@ impl WString {
 			panic!("Destination buffer cannot have zero length.");
 		}
 
-		if let Some(vec_u16_ref) = self.vec_u16.as_ref() {
-			let num_chars = std::cmp::min(vec_u16_ref.len() - 1, dest.len() - 1); // no terminating null
-			unsafe {
-				vec_u16_ref.as_ptr()
-					.copy_to_nonoverlapping(dest.as_mut_ptr(), num_chars);
-
-				for i in num_chars..dest.len() {
-					*dest.get_unchecked_mut(i) = 0x0000; // zero the rest of the slice
-				}
-			}
+		if let Some(src) = self.buf.as_slice() {
+			let num_chars = std::cmp::min(src.len() - 1, dest.len() - 1); // no terminating null
+			dest[..num_chars].copy_from_slice(&src[..num_chars]);
+			dest[num_chars..].fill(0x0000); // zero the rest of the slice
 		}
 	}
 
 	/// Fills the entire buffer with zero values. The buffer size is not
 	/// changed.
 	pub fn fill_with_zero(&mut self) {
-		self.vec_u16.as_mut()
-			.map(|vec_u16|
-				vec_u16.iter_mut()
-					.for_each(|wchar| *wchar = 0x0000),
-			);
+		if let Some(chars) = self.buf.as_mut_slice() {
+			chars.fill(0x0000);
+		}
 	}
 
 	/// Tells whether the internal buffer is storing a null string pointer, or
@@ -285,26 +407,27 @@ impl WString {
 
 	/// Tells whether the internal buffer is storing a null string pointer.
 	pub fn is_null(&self) -> bool {
-		self.vec_u16.is_none()
+		matches!(self.buf, Buffer::Null)
 	}
 
-	/// Wrapper to
-	/// [`lstrlen`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-lstrlenw).
-	///
 	/// Returns the number of
 	/// [`u16`](https://doc.rust-lang.org/std/primitive.u16.html) characters
-	/// stored in the internal buffer, not counting the terminating null.
+	/// stored in the internal buffer, not counting the terminating null, like
+	/// [`lstrlen`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-lstrlenw).
+	///
+	/// If the buffer has no null, its whole size is returned.
 	pub fn len(&self) -> usize {
-		self.vec_u16.as_ref()
-			.map_or(0,
-				|vec_u16| unsafe { kernel::ffi::lstrlenW(vec_u16.as_ptr()) } as _)
+		self.buf.as_slice()
+			.map_or(0, |chars|
+				chars.iter()
+					.position(|ch| *ch == 0x0000)
+					.unwrap_or(chars.len()),
+			)
 	}
 
 	/// Resizes the internal buffer, to be used as a buffer for native Win32
-	/// functions. All UTF-16 chars will be set to zero.
-	///
-	/// The underlying `Vec` will be resized with a call to
-	/// [`Vec::resize`](https://doc.rust-lang.org/std/vec/struct.Vec.html#method.resize).
+	/// functions. The existing chars are kept, and the new ones will be set to
+	/// zero.
 	///
 	/// If the new size is zero, the internal buffer is deallocated.
 	///
@@ -318,13 +441,27 @@ impl WString {
 	/// * [`as_slice`](crate::WString::as_slice).
 	pub fn realloc_buffer(&mut self, new_size: usize) {
 		if new_size == 0 {
-			self.vec_u16 = None; // dealloc
-		} else {
-			if self.vec_u16.is_none() {
-				self.vec_u16 = Some(Vec::default()); // create if not yet; default Vec is empty
-			}
-			self.vec_u16.as_mut().unwrap().resize(new_size, 0x0000); // filled with nulls
+			self.buf = Buffer::Null; // dealloc
+			return;
+		}
+
+		if let Buffer::Heap(vec_u16) = &mut self.buf {
+			vec_u16.resize(new_size, 0x0000); // filled with nulls
+			return;
 		}
+
+		let cur = self.buf.as_slice().unwrap_or_default();
+		let num_kept = std::cmp::min(cur.len(), new_size);
+
+		self.buf = if new_size <= SSO_LEN {
+			let mut chars = [0x0000; SSO_LEN]; // filled with nulls
+			chars[..num_kept].copy_from_slice(&cur[..num_kept]);
+			Buffer::Stack { chars, len: new_size }
+		} else {
+			let mut vec_u16 = vec![0x0000; new_size]; // filled with nulls
+			vec_u16[..num_kept].copy_from_slice(&cur[..num_kept]);
+			Buffer::Heap(vec_u16)
+		};
 	}
 
 	/// Converts into
@@ -351,88 +488,110 @@ impl WString {
 	/// invalid characters. If you're dealing with a string known to be valid,
 	/// [`to_string`](crate::WString::to_string) is more practical.
 	pub fn to_string_checked(&self) -> Result<String, std::string::FromUtf16Error> {
-		self.vec_u16.as_ref()
+		self.buf.as_slice()
 			.map_or(
 				Ok(String::default()),
 				|v| String::from_utf16(&v[..self.len()]), // without terminating null
 			)
 	}
 
-	/// Guesses the [`Encoding`](crate::Encoding) of the given data, also
-	/// returning the size of its
-	/// [BOM](https://en.wikipedia.org/wiki/Byte_order_mark), if any.
-	pub fn guess_encoding(data: &[u8]) -> (Encoding, usize) {
-		let has_bom = |bom_bytes: &[u8]| -> bool {
-			data.len() >= bom_bytes.len()
-				&& data[..bom_bytes.len()].cmp(bom_bytes) == Ordering::Equal
-		};
-
-		const UTF8: [u8; 3] = [0xef, 0xbb, 0xbf];
-		if has_bom(&UTF8) { // UTF-8 BOM
-			return (Encoding::Utf8, UTF8.len());
-		}
-
-		const UTF16BE: [u8; 2] = [0xfe, 0xff];
-		if has_bom(&UTF16BE) {
-			return (Encoding::Utf16be, UTF16BE.len());
-		}
-
-		const UTF16LE: [u8; 2] = [0xff, 0xfe];
-		if has_bom(&UTF16LE) {
-			return (Encoding::Utf16le, UTF16LE.len());
-		}
-
-		const UTF32BE: [u8; 4] = [0x00, 0x00, 0xfe, 0xff];
-		if has_bom(&UTF32BE) {
-			return (Encoding::Utf32be, UTF32BE.len())
-		}
+	/// Converts into an
+	/// [`OsString`](https://doc.rust-lang.org/std/ffi/struct.OsString.html),
+	/// without any validation. An internal null pointer will simply be
+	/// converted into an empty string.
+	#[cfg(windows)]
+	pub fn to_os_string(&self) -> OsString {
+		self.as_wstr().to_os_string()
+	}
 
-		const UTF32LE: [u8; 4] = [0xff, 0xfe, 0x00, 0x00];
-		if has_bom(&UTF32LE) {
-			return (Encoding::Utf32le, UTF32LE.len())
+	/// Detects the [`Encoding`](crate::Encoding) of the given data, also
+	/// returning the size of its
+	/// [BOM](https://en.wikipedia.org/wiki/Byte_order_mark), if any, and how
+	/// confident the detection is.
+	///
+	/// If there's no BOM, the data is analyzed:
+	/// * UTF-32 and UTF-16, both big and little-endian, are recognized by the
+	///   position of their null bytes;
+	/// * UTF-8 is recognized by validating all its multi-byte sequences;
+	/// * pure ASCII data is reported as [`Encoding::Ansi`](crate::Encoding::Ansi);
+	/// * anything else is assumed to be
+	///   [`Encoding::Win1252`](crate::Encoding::Win1252), which is a superset
+	///   of ISO-8859-1.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::{Encoding, WString};
+	///
+	/// let data = [0x61, 0x00, 0x62, 0x00, 0x63, 0x00];
+	/// let guess = WString::detect_encoding(&data);
+	/// assert_eq!(guess.encoding, Encoding::Utf16le);
+	/// assert_eq!(guess.bom_size, 0);
+	/// println!("Confidence: {:.0}%", guess.confidence * 100.0);
+	/// ```
+	pub fn detect_encoding(data: &[u8]) -> EncodingGuess {
+		if let Some((encoding, bom_size)) = bom_encoding(data) {
+			return EncodingGuess { encoding, bom_size, confidence: 1.0 };
 		}
 
-		const SCSU: [u8; 3] = [0x0e, 0xfe, 0xff];
-		if has_bom(&SCSU) {
-			return (Encoding::Scsu, SCSU.len())
-		}
+		let guess = |encoding, confidence| EncodingGuess { encoding, bom_size: 0, confidence };
+		let has_nulls = data.contains(&0x00);
 
-		const BOCU1: [u8; 3] = [0xfb, 0xee, 0x28];
-		if has_bom(&BOCU1) {
-			return (Encoding::Bocu1, BOCU1.len())
+		if has_nulls {
+			let best = [
+				guess(Encoding::Utf32be, utf32_confidence(data, true)),
+				guess(Encoding::Utf32le, utf32_confidence(data, false)),
+				guess(Encoding::Utf16be, utf16_confidence(data, true)),
+				guess(Encoding::Utf16le, utf16_confidence(data, false)),
+			].into_iter()
+				.fold(guess(Encoding::Unknown, 0.0),
+					|best, cur| if cur.confidence > best.confidence { cur } else { best });
+			if best.confidence > 0.5 {
+				return best;
+			}
 		}
 
-		// No BOM found, guess UTF-8 without BOM, or Windows-1252 (superset of
-		// ISO-8859-1).
-		let mut can_be_win1252 = false;
-		for (c0, c1) in data.windows(2)
-			.map(|chunk| unsafe { (*chunk.get_unchecked(0), *chunk.get_unchecked(1)) })
-		{
-			if c0 > 0x7f { // 127
-				can_be_win1252 = true;
-				if c0 == 0xc2 && (c1 >= 0xa1 && c1 <= 0xbf) // http://www.utf8-chartable.de
-					|| c0 == 0xc3 && (c1 >= 0x80 && c1 <= 0xbf)
-				{
-					return (Encoding::Utf8, 0); // UTF-8 without BOM
-				}
-			} else if c1 > 0x7f {
-				can_be_win1252 = true;
-			}
+		if data.is_ascii() {
+			guess(Encoding::Ansi, if has_nulls { 0.5 } else { 1.0 })
+		} else if let Some(confidence) = utf8_confidence(data) {
+			guess(Encoding::Utf8, confidence)
+		} else {
+			guess(Encoding::Win1252, win1252_confidence(data))
 		}
+	}
 
-		(if can_be_win1252 { Encoding::Win1252 } else { Encoding::Ansi }, 0)
+	/// Guesses the [`Encoding`](crate::Encoding) of the given data, also
+	/// returning the size of its
+	/// [BOM](https://en.wikipedia.org/wiki/Byte_order_mark), if any.
+	///
+	/// This is a shorthand to
+	/// [`WString::detect_encoding`](crate::WString::detect_encoding), without
+	/// the confidence.
+	pub fn guess_encoding(data: &[u8]) -> (Encoding, usize) {
+		let guess = Self::detect_encoding(data);
+		(guess.encoding, guess.bom_size)
 	}
 
-	/// Guesses the encoding with
-	/// [`WString::guess_encoding`](crate::WString::guess_encoding) and parses
+	/// Detects the encoding with
+	/// [`WString::detect_encoding`](crate::WString::detect_encoding) and parses
 	/// the data as string.
 	///
 	/// If you're sure the data has UTF-8 encoding, you can also use the
 	/// built-in
 	/// [`String::from_utf8`](https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8).
 	///
-	/// To serialize the string back into UTF-8 bytes, use the built-in
-	/// [`String::into_bytes`](https://doc.rust-lang.org/std/string/struct.String.html#method.into_bytes).
+	/// To serialize the string back into bytes, use
+	/// [`WString::serialize`](crate::WString::serialize).
+	///
+	/// The decoding is done in pure Rust, including the
+	/// [SCSU](https://www.unicode.org/reports/tr6/) and
+	/// [BOCU-1](https://www.unicode.org/notes/tn6/) compression schemes.
+	/// Invalid sequences are replaced by U+FFFD, the
+	/// [replacement character](https://en.wikipedia.org/wiki/Specials_(Unicode_block)#Replacement_character).
+	/// Decoding stops at the first null char.
+	///
+	/// Fails with [`ERROR::UNSUPPORTED_TYPE`](crate::co::ERROR::UNSUPPORTED_TYPE)
+	/// if the encoding cannot be decoded.
 	pub fn parse_str(data: &[u8]) -> WinResult<WString> {
 		let mut data = data;
 		if data.is_empty() { // nothing to parse
@@ -442,20 +601,55 @@ impl WString {
 		let (encoding, sz_bom) = Self::guess_encoding(data);
 		data = &data[sz_bom..]; // skip BOM, if any
 
-		Ok(Self {
-			vec_u16: Some(match encoding {
-				Encoding::Ansi => Self::parse_ansi_str(data),
-				Encoding::Win1252 => MultiByteToWideChar(co::CP::WINDOWS_1252, co::MBC::NoValue, data)?,
-				Encoding::Utf8 => MultiByteToWideChar(co::CP::UTF8, co::MBC::NoValue, data)?,
-				Encoding::Utf16be => Self::parse_utf16_str(data, true),
-				Encoding::Utf16le => Self::parse_utf16_str(data, false),
-				Encoding::Utf32be
-				| Encoding::Utf32le
-				| Encoding::Scsu
-				| Encoding::Bocu1
-				| Encoding::Unknown => panic!("Encoding {} not implemented.", encoding),
-			}),
-		})
+		let mut str16 = match encoding {
+			Encoding::Ansi => Self::parse_ansi_str(data),
+			Encoding::Win1252 => Self::parse_win1252_str(data),
+			Encoding::Utf8 => Self::parse_utf8_str(data),
+			Encoding::Utf16be => Self::parse_utf16_str(data, true),
+			Encoding::Utf16le => Self::parse_utf16_str(data, false),
+			Encoding::Utf32be => Self::parse_utf32_str(data, true),
+			Encoding::Utf32le => Self::parse_utf32_str(data, false),
+			Encoding::Scsu => Self::parse_scsu_str(data),
+			Encoding::Bocu1 => Self::parse_bocu1_str(data),
+			Encoding::Unknown => return Err(co::ERROR::UNSUPPORTED_TYPE),
+		};
+		replace_unpaired_surrogates(&mut str16); // UTF-16, SCSU and BOCU-1 can carry them
+
+		Ok(Self { buf: Buffer::Heap(str16) })
+	}
+
+	/// Serializes the string into raw bytes of the given
+	/// [`Encoding`](crate::Encoding), optionally prepending its
+	/// [BOM](https://en.wikipedia.org/wiki/Byte_order_mark). The terminating
+	/// null is not written.
+	///
+	/// Only UTF encodings have a BOM, so `with_bom` is ignored for
+	/// [`Encoding::Ansi`](crate::Encoding::Ansi), which accepts only ASCII
+	/// chars, and [`Encoding::Win1252`](crate::Encoding::Win1252).
+	///
+	/// Fails with
+	/// [`ERROR::NO_UNICODE_TRANSLATION`](crate::co::ERROR::NO_UNICODE_TRANSLATION)
+	/// if the string has an invalid UTF-16 sequence or a char which cannot be
+	/// represented in the encoding, or with
+	/// [`ERROR::INVALID_PARAMETER`](crate::co::ERROR::INVALID_PARAMETER) if
+	/// the encoding cannot be written, like SCSU and BOCU-1.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use winsafe::{Encoding, WString};
+	///
+	/// let ws = WString::from_str("café");
+	/// let bytes = ws.serialize(Encoding::Utf16le, true)?;
+	/// assert_eq!(bytes, [0xff, 0xfe, 0x63, 0x00, 0x61, 0x00, 0x66, 0x00, 0xe9, 0x00]);
+	/// # Ok::<_, winsafe::co::ERROR>(())
+	/// ```
+	pub fn serialize(&self,
+		encoding: Encoding, with_bom: bool) -> WinResult<Vec<u8>>
+	{
+		let s = self.to_string_checked()
+			.map_err(|_| co::ERROR::NO_UNICODE_TRANSLATION)?;
+		encode_str(&s, encoding, with_bom)
 	}
 
 	fn parse_ansi_str(data: &[u8]) -> Vec<u16> {
@@ -468,7 +662,32 @@ impl WString {
 		}
 
 		let mut str16 = Vec::with_capacity(the_len + 1); // room for terminating null
-		data.iter().for_each(|by| str16.push(*by as _)); // u8 to u18 raw conversion
+		data[..the_len].iter().for_each(|by| str16.push(*by as _)); // u8 to u16 raw conversion
+		str16.push(0x0000); // terminating null
+		str16
+	}
+
+	fn parse_win1252_str(data: &[u8]) -> Vec<u16> {
+		let mut str16 = Vec::with_capacity(data.len() + 1); // room for terminating null
+		for by in data.iter().take_while(|by| **by != 0x00) { // stop at terminating null amidst data
+			str16.push(match *by {
+				0x80..=0x9f => WIN1252_80_9F[(*by - 0x80) as usize],
+				by => by as _, // all other bytes match Unicode code points
+			});
+		}
+
+		str16.push(0x0000); // terminating null
+		str16
+	}
+
+	fn parse_utf8_str(data: &[u8]) -> Vec<u16> {
+		let mut str16 = Vec::with_capacity(data.len() + 1); // room for terminating null
+		str16.extend(
+			String::from_utf8_lossy(data) // invalid sequences become U+FFFD
+				.encode_utf16()
+				.take_while(|ch| *ch != 0x0000), // stop at terminating null amidst data
+		);
+
 		str16.push(0x0000); // terminating null
 		str16
 	}
@@ -481,7 +700,7 @@ impl WString {
 		};
 
 		let mut str16 = Vec::<u16>::with_capacity(data.len() / 2 + 1); // room for terminating null
-		for (b0, b1) in data.windows(2)
+		for (b0, b1) in data.chunks_exact(2)
 			.map(|chunk| unsafe { (*chunk.get_unchecked(0), *chunk.get_unchecked(1)) })
 		{
 			if b0 == 0x00 && b1 == 0x00 {
@@ -499,4 +718,771 @@ impl WString {
 		str16.push(0x0000); // terminating null
 		str16
 	}
+
+	fn parse_utf32_str(data: &[u8], is_big_endian: bool) -> Vec<u16> {
+		let mut str16 = Vec::<u16>::with_capacity(data.len() / 4 + 1); // room for terminating null
+		let mut buf = [0u16; 2];
+
+		let chunks = data.chunks_exact(4);
+		let has_leftover = !chunks.remainder().is_empty();
+		for chunk in chunks {
+			let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+			let code = if is_big_endian {
+				u32::from_be_bytes(bytes)
+			} else {
+				u32::from_le_bytes(bytes)
+			};
+
+			if code == 0x0000_0000 {
+				str16.push(0x0000); // found terminating null amidst data, stop processing
+				return str16;
+			}
+
+			let ch = char::from_u32(code)
+				.unwrap_or(char::REPLACEMENT_CHARACTER); // surrogates or beyond U+10FFFF
+			str16.extend_from_slice(ch.encode_utf16(&mut buf));
+		}
+
+		if has_leftover { // truncated last char
+			str16.push(char::REPLACEMENT_CHARACTER as _);
+		}
+
+		str16.push(0x0000); // terminating null
+		str16
+	}
+
+	/// Decodes [SCSU](https://www.unicode.org/reports/tr6/), the Standard
+	/// Compression Scheme for Unicode.
+	fn parse_scsu_str(data: &[u8]) -> Vec<u16> {
+		const STATIC_WINDOWS: [u32; 8] = [
+			0x0000, 0x0080, 0x0100, 0x0300, 0x2000, 0x2080, 0x2100, 0x3000];
+		let mut windows: [u32; 8] = [ // dynamic windows, initial offsets
+			0x0080, 0x00c0, 0x0400, 0x0600, 0x0900, 0x3040, 0x30a0, 0xff00];
+		let mut active = 0; // index of the active dynamic window
+		let mut unicode_mode = false;
+
+		let mut str16 = Vec::<u16>::with_capacity(data.len() + 1); // room for terminating null
+		let mut bytes = data.iter().copied();
+
+		while let Some(by) = bytes.next() {
+			let code = if unicode_mode {
+				match by {
+					0xe0..=0xe7 => { // UC0 to UC7: change to window, single-byte mode
+						active = (by - 0xe0) as usize;
+						unicode_mode = false;
+						continue;
+					},
+					0xe8..=0xef => match bytes.next().and_then(scsu_window_offset) { // UD0 to UD7
+						Some(offset) => {
+							active = (by - 0xe8) as usize;
+							windows[active] = offset;
+							unicode_mode = false;
+							continue;
+						},
+						None => None,
+					},
+					0xf0 => bytes.next() // UQU: quote Unicode
+						.and_then(|hi| bytes.next().map(|lo| u16::from_be_bytes([hi, lo]) as u32)),
+					0xf1 => match bytes.next() // UDX: define extended window
+						.and_then(|hi| bytes.next().map(|lo| scsu_extended_window(hi, lo)))
+					{
+						Some((idx, offset)) => {
+							active = idx;
+							windows[active] = offset;
+							unicode_mode = false;
+							continue;
+						},
+						None => None,
+					},
+					0xf2 => None, // reserved
+					hi => bytes.next().map(|lo| u16::from_be_bytes([hi, lo]) as u32),
+				}
+			} else {
+				match by {
+					0x00 | 0x09 | 0x0a | 0x0d | 0x20..=0x7f => Some(by as u32),
+					0x80..=0xff => Some(windows[active] + (by - 0x80) as u32),
+					0x01..=0x08 => bytes.next().map(|quoted| { // SQ0 to SQ7: quote from window
+						let idx = (by - 0x01) as usize;
+						if quoted < 0x80 {
+							STATIC_WINDOWS[idx] + quoted as u32
+						} else {
+							windows[idx] + (quoted - 0x80) as u32
+						}
+					}),
+					0x0b => match bytes.next() // SDX: define extended window
+						.and_then(|hi| bytes.next().map(|lo| scsu_extended_window(hi, lo)))
+					{
+						Some((idx, offset)) => {
+							active = idx;
+							windows[active] = offset;
+							continue;
+						},
+						None => None,
+					},
+					0x0e => bytes.next() // SQU: quote Unicode
+						.and_then(|hi| bytes.next().map(|lo| u16::from_be_bytes([hi, lo]) as u32)),
+					0x0f => { // SCU: change to Unicode mode
+						unicode_mode = true;
+						continue;
+					},
+					0x10..=0x17 => { // SC0 to SC7: change to window
+						active = (by - 0x10) as usize;
+						continue;
+					},
+					0x18..=0x1f => match bytes.next().and_then(scsu_window_offset) { // SD0 to SD7
+						Some(offset) => {
+							active = (by - 0x18) as usize;
+							windows[active] = offset;
+							continue;
+						},
+						None => None,
+					},
+					_ => None, // 0x0c is reserved
+				}
+			};
+
+			match code {
+				Some(0x0000) => break, // found terminating null amidst data, stop processing
+				Some(code) => push_code_point(&mut str16, code),
+				None => str16.push(char::REPLACEMENT_CHARACTER as _), // reserved tag or truncated data
+			}
+		}
+
+		str16.push(0x0000); // terminating null
+		str16
+	}
+
+	/// Decodes [BOCU-1](https://www.unicode.org/notes/tn6/), the Binary Ordered
+	/// Compression for Unicode. The data is expected to come right after the
+	/// BOM.
+	fn parse_bocu1_str(data: &[u8]) -> Vec<u16> {
+		const ASCII_PREV: i32 = 0x40;
+		const MIDDLE: i32 = 0x90;
+		const RESET: u8 = 0xff;
+
+		let mut prev = bocu1_prev(0xfeff); // state after the BOM, which was already consumed
+		let mut str16 = Vec::<u16>::with_capacity(data.len() + 1); // room for terminating null
+		let mut bytes = data.iter().copied();
+
+		while let Some(by) = bytes.next() {
+			let code = match by {
+				0x00..=0x20 => { // C0 controls and space are encoded as themselves
+					if by != 0x20 {
+						prev = ASCII_PREV;
+					}
+					Some(by as i32)
+				},
+				RESET => {
+					prev = ASCII_PREV;
+					continue;
+				},
+				0x50..=0xcf => { // single-byte difference
+					let code = prev + (by as i32 - MIDDLE);
+					prev = bocu1_prev(code);
+					Some(code)
+				},
+				lead => bocu1_diff(lead, &mut bytes)
+					.map(|diff| prev + diff)
+					.filter(|code| (0..=0x10_ffff).contains(code))
+					.inspect(|code| prev = bocu1_prev(*code)),
+			};
+
+			match code {
+				Some(0x0000) => break, // found terminating null amidst data, stop processing
+				Some(code) => push_code_point(&mut str16, code as _),
+				None => str16.push(char::REPLACEMENT_CHARACTER as _), // invalid trail byte or truncated data
+			}
+		}
+
+		str16.push(0x0000); // terminating null
+		str16
+	}
+}
+
+/// Returns the encoding and the BOM size, if the data starts with a BOM.
+fn bom_encoding(data: &[u8]) -> Option<(Encoding, usize)> {
+	const BOMS: [(&[u8], Encoding); 7] = [
+		(&[0xef, 0xbb, 0xbf], Encoding::Utf8),
+		(&[0xfe, 0xff], Encoding::Utf16be),
+		(&[0xff, 0xfe, 0x00, 0x00], Encoding::Utf32le), // must come before UTF-16 LE, which is a prefix
+		(&[0xff, 0xfe], Encoding::Utf16le),
+		(&[0x00, 0x00, 0xfe, 0xff], Encoding::Utf32be),
+		(&[0x0e, 0xfe, 0xff], Encoding::Scsu),
+		(&[0xfb, 0xee, 0x28], Encoding::Bocu1),
+	];
+
+	BOMS.iter()
+		.find(|(bom, _)| data.starts_with(bom))
+		.map(|(bom, encoding)| (*encoding, bom.len()))
+}
+
+/// Confidence that BOM-less data is UTF-32: every unit must be a valid char,
+/// and nulls can only appear at the end.
+fn utf32_confidence(data: &[u8], is_big_endian: bool) -> f32 {
+	if !data.len().is_multiple_of(4) {
+		return 0.0;
+	}
+
+	let mut num_chars = 0;
+	let mut found_null = false;
+	for chunk in data.chunks_exact(4) {
+		let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+		let code = if is_big_endian {
+			u32::from_be_bytes(bytes)
+		} else {
+			u32::from_le_bytes(bytes)
+		};
+
+		if code == 0x0000_0000 {
+			found_null = true;
+		} else if found_null || char::from_u32(code).is_none() {
+			return 0.0; // null amidst data, or invalid code point
+		} else {
+			num_chars += 1;
+		}
+	}
+
+	match num_chars {
+		0 => 0.0,
+		1 => 0.75,
+		_ => 0.99,
+	}
+}
+
+/// Confidence that BOM-less data is UTF-16: chars in the Latin-1 range have
+/// their null byte in the high-order position, surrogates must be paired and
+/// nulls can only appear at the end.
+fn utf16_confidence(data: &[u8], is_big_endian: bool) -> f32 {
+	let mut num_units = 0;
+	let mut num_latin1 = 0; // high-order byte is zero
+	let mut num_swapped = 0; // low-order byte is zero, hinting the other endianness
+	let mut found_null = false;
+	let mut pending_high_surrogate = false;
+
+	for chunk in data.chunks_exact(2) {
+		let (hi, lo) = if is_big_endian { (chunk[0], chunk[1]) } else { (chunk[1], chunk[0]) };
+		let unit = u16::from_be_bytes([hi, lo]);
+
+		if unit == 0x0000 {
+			found_null = true;
+			continue;
+		} else if found_null {
+			return 0.0; // null amidst data
+		}
+
+		match unit {
+			0xd800..=0xdbff if !pending_high_surrogate => pending_high_surrogate = true,
+			0xdc00..=0xdfff if pending_high_surrogate => pending_high_surrogate = false,
+			0xd800..=0xdfff => return 0.0, // unpaired surrogate
+			_ if pending_high_surrogate => return 0.0,
+			_ => {},
+		}
+
+		num_units += 1;
+		if hi == 0x00 {
+			if lo >= 0x20 || matches!(lo, b'\t' | b'\n' | b'\r') { // control chars are no evidence
+				num_latin1 += 1;
+			}
+		} else if lo == 0x00 {
+			num_swapped += 1;
+		}
+	}
+
+	if pending_high_surrogate || num_latin1 == 0 || num_swapped >= num_latin1 {
+		0.0
+	} else {
+		0.5 + 0.49 * (num_latin1 as f32 / num_units as f32)
+	}
+}
+
+/// Confidence that non-ASCII data is UTF-8, or `None` if it has an invalid
+/// sequence. A sequence truncated at the very end is tolerated.
+fn utf8_confidence(data: &[u8]) -> Option<f32> {
+	let valid = match std::str::from_utf8(data) {
+		Ok(valid) => valid,
+		Err(e) if e.error_len().is_none() => // truncated at the end
+			unsafe { std::str::from_utf8_unchecked(&data[..e.valid_up_to()]) },
+		Err(_) => return None,
+	};
+
+	// The more multi-byte sequences, the less likely they're a coincidence.
+	Some(match valid.chars().filter(|ch| !ch.is_ascii()).count() {
+		0 => 0.5,
+		1 => 0.8,
+		2..=3 => 0.9,
+		_ => 0.99,
+	})
+}
+
+/// Confidence that non-ASCII, non-UTF-8 data is Windows-1252, lowered by
+/// undefined bytes and unusual control chars.
+fn win1252_confidence(data: &[u8]) -> f32 {
+	let num_suspicious = data.iter()
+		.filter(|by| match **by {
+			0x81 | 0x8d | 0x8f | 0x90 | 0x9d => true, // undefined in Windows-1252
+			b'\t' | b'\n' | 0x0c | b'\r' => false,
+			by => by < 0x20,
+		})
+		.count();
+	let num_non_ascii = data.iter().filter(|by| !by.is_ascii()).count();
+
+	let ratio = num_suspicious as f32 / (num_suspicious + num_non_ascii).max(1) as f32;
+	(0.9 * (1.0 - ratio)).max(0.1)
+}
+
+/// Replaces the surrogates which are not part of a valid pair by U+FFFD, so the
+/// decoded string can always be converted to a `String`.
+fn replace_unpaired_surrogates(str16: &mut [u16]) {
+	let mut idx = 0;
+	while idx < str16.len() {
+		match str16[idx] {
+			0xd800..=0xdbff if matches!(str16.get(idx + 1), Some(0xdc00..=0xdfff)) => idx += 2,
+			0xd800..=0xdfff => {
+				str16[idx] = char::REPLACEMENT_CHARACTER as _;
+				idx += 1;
+			},
+			_ => idx += 1,
+		}
+	}
+}
+
+/// Pushes a code point as one or two UTF-16 units.
+fn push_code_point(str16: &mut Vec<u16>, code: u32) {
+	if code >= 0x1_0000 {
+		let code = code - 0x1_0000;
+		str16.push(0xd800 | (code >> 10) as u16); // high surrogate
+		str16.push(0xdc00 | (code & 0x3ff) as u16); // low surrogate
+	} else {
+		str16.push(code as _);
+	}
+}
+
+/// Returns the SCSU dynamic window offset for the byte following a window
+/// definition tag, or `None` if it's reserved.
+fn scsu_window_offset(by: u8) -> Option<u32> {
+	match by {
+		0x01..=0x67 => Some(by as u32 * 0x80),
+		0x68..=0xa7 => Some(by as u32 * 0x80 + 0xac00),
+		0xf9 => Some(0x00c0),
+		0xfa => Some(0x0250),
+		0xfb => Some(0x0370),
+		0xfc => Some(0x0530),
+		0xfd => Some(0x3040),
+		0xfe => Some(0x30a0),
+		0xff => Some(0xff60),
+		_ => None, // reserved
+	}
+}
+
+/// Returns the SCSU window index and its offset, for the two bytes following
+/// an extended window definition tag.
+fn scsu_extended_window(hi: u8, lo: u8) -> (usize, u32) {
+	let idx = (hi >> 5) as usize;
+	let offset = 0x1_0000 + ((((hi & 0x1f) as u32) << 8 | lo as u32) << 7);
+	(idx, offset)
+}
+
+/// Returns the BOCU-1 state after decoding the given code point.
+fn bocu1_prev(code: i32) -> i32 {
+	match code {
+		0x3040..=0x309f => 0x3070, // Hiragana
+		0x4e00..=0x9fa5 => 0x7711, // CJK Unified Ideographs
+		0xac00..=0xd7a3 => 0xc1d1, // Hangul syllables
+		_ => (code & !0x7f) + 0x40, // middle of the 128-char block
+	}
+}
+
+/// Decodes a BOCU-1 multi-byte difference, consuming its trail bytes.
+fn bocu1_diff(lead: u8, bytes: &mut impl Iterator<Item = u8>) -> Option<i32> {
+	const TRAIL_COUNT: i32 = 243;
+	const REACH_POS_1: i32 = 63;
+	const REACH_POS_2: i32 = REACH_POS_1 + 43 * TRAIL_COUNT;
+	const REACH_POS_3: i32 = REACH_POS_2 + 3 * TRAIL_COUNT * TRAIL_COUNT;
+	const REACH_NEG_1: i32 = -64;
+	const REACH_NEG_2: i32 = REACH_NEG_1 - 43 * TRAIL_COUNT;
+	const REACH_NEG_3: i32 = REACH_NEG_2 - 3 * TRAIL_COUNT * TRAIL_COUNT;
+
+	let lead = lead as i32;
+	let (mut diff, count) = match lead {
+		0xd0..=0xfa => ((lead - 0xd0) * TRAIL_COUNT + REACH_POS_1 + 1, 1),
+		0xfb..=0xfd => ((lead - 0xfb) * TRAIL_COUNT * TRAIL_COUNT + REACH_POS_2 + 1, 2),
+		0xfe => (REACH_POS_3 + 1, 3),
+		0x25..=0x4f => ((lead - 0x50) * TRAIL_COUNT + REACH_NEG_1, 1),
+		0x22..=0x24 => ((lead - 0x25) * TRAIL_COUNT * TRAIL_COUNT + REACH_NEG_2, 2),
+		_ => (-TRAIL_COUNT * TRAIL_COUNT * TRAIL_COUNT + REACH_NEG_3, 3), // 0x21
+	};
+
+	for place in (0..count).rev() {
+		let trail = match bytes.next()? {
+			by @ 0x01..=0x06 => by as i32 - 0x01, // some C0 controls are also used
+			by @ 0x10..=0x19 => by as i32 - 0x10 + 6,
+			by @ 0x1c..=0x1f => by as i32 - 0x1c + 16,
+			by @ 0x21..=0xff => by as i32 - 0x21 + 20,
+			_ => return None, // not a valid trail byte
+		};
+		diff += trail * TRAIL_COUNT.pow(place);
+	}
+	Some(diff)
+}
+
+/// Encodes a string into raw bytes, like
+/// [`WString::serialize`](crate::WString::serialize), but straight from a
+/// `&str`.
+pub(crate) fn encode_str(s: &str,
+	encoding: Encoding, with_bom: bool) -> WinResult<Vec<u8>>
+{
+	let mut buf = Vec::<u8>::with_capacity(s.len() + 4);
+
+	match encoding {
+		Encoding::Ansi => for ch in s.chars() {
+			if !ch.is_ascii() {
+				return Err(co::ERROR::NO_UNICODE_TRANSLATION);
+			}
+			buf.push(ch as _);
+		},
+		Encoding::Win1252 => for ch in s.chars() {
+			let code = ch as u32;
+			buf.push(match code {
+				0x00..=0x7f | 0xa0..=0xff => code as _,
+				_ => WIN1252_80_9F.iter()
+					.position(|w| *w as u32 == code)
+					.map(|idx| 0x80 + idx as u8)
+					.ok_or(co::ERROR::NO_UNICODE_TRANSLATION)?,
+			});
+		},
+		Encoding::Utf8 => {
+			if with_bom {
+				buf.extend_from_slice(&[0xef, 0xbb, 0xbf]);
+			}
+			buf.extend_from_slice(s.as_bytes());
+		},
+		Encoding::Utf16be | Encoding::Utf16le => {
+			let is_be = encoding == Encoding::Utf16be;
+			if with_bom {
+				buf.extend_from_slice(if is_be { &[0xfe, 0xff] } else { &[0xff, 0xfe] });
+			}
+			s.encode_utf16().for_each(|w|
+				buf.extend_from_slice(&if is_be { w.to_be_bytes() } else { w.to_le_bytes() }));
+		},
+		Encoding::Utf32be | Encoding::Utf32le => {
+			let is_be = encoding == Encoding::Utf32be;
+			if with_bom {
+				buf.extend_from_slice(
+					if is_be { &[0x00, 0x00, 0xfe, 0xff] } else { &[0xff, 0xfe, 0x00, 0x00] });
+			}
+			s.chars().for_each(|ch|
+				buf.extend_from_slice(&if is_be { (ch as u32).to_be_bytes() } else { (ch as u32).to_le_bytes() }));
+		},
+		Encoding::Scsu
+		| Encoding::Bocu1
+		| Encoding::Unknown => return Err(co::ERROR::INVALID_PARAMETER),
+	}
+
+	Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn lone_high_surrogate() {
+		let le = [0xff, 0xfe, b'a', 0x00, 0x3d, 0xd8, b'b', 0x00]; // D83D followed by 'b'
+		assert_eq!(WString::parse_str(&le).unwrap().to_string(), "a\u{fffd}b");
+
+		let be = [0xfe, 0xff, 0x00, b'a', 0xd8, 0x3d]; // D83D at the end
+		assert_eq!(WString::parse_str(&be).unwrap().to_string(), "a\u{fffd}");
+	}
+
+	#[test]
+	fn lone_low_surrogate() {
+		let le = [0xff, 0xfe, b'[', 0x00, b'a', 0x00, b']', 0x00, 0x00, 0xdc];
+		assert_eq!(WString::parse_str(&le).unwrap().to_string(), "[a]\u{fffd}");
+
+		let swapped = [0xff, 0xfe, 0x00, 0xde, 0x3d, 0xd8]; // low before high
+		assert_eq!(WString::parse_str(&swapped).unwrap().to_string(), "\u{fffd}\u{fffd}");
+	}
+
+	#[test]
+	fn valid_surrogate_pair() {
+		let le = [0xff, 0xfe, 0x3d, 0xd8, 0x00, 0xde]; // U+1F600
+		assert_eq!(WString::parse_str(&le).unwrap().to_string(), "\u{1f600}");
+	}
+
+	#[test]
+	fn serialize_known_bytes() {
+		let ws = WString::from_str("é😀"); // U+00E9, U+1F600
+		let cases: [(Encoding, &[u8], &[u8]); 5] = [
+			(Encoding::Utf8, &[0xef, 0xbb, 0xbf], &[0xc3, 0xa9, 0xf0, 0x9f, 0x98, 0x80]),
+			(Encoding::Utf16le, &[0xff, 0xfe], &[0xe9, 0x00, 0x3d, 0xd8, 0x00, 0xde]),
+			(Encoding::Utf16be, &[0xfe, 0xff], &[0x00, 0xe9, 0xd8, 0x3d, 0xde, 0x00]),
+			(Encoding::Utf32le, &[0xff, 0xfe, 0x00, 0x00], &[0xe9, 0x00, 0x00, 0x00, 0x00, 0xf6, 0x01, 0x00]),
+			(Encoding::Utf32be, &[0x00, 0x00, 0xfe, 0xff], &[0x00, 0x00, 0x00, 0xe9, 0x00, 0x01, 0xf6, 0x00]),
+		];
+
+		for (encoding, bom, body) in cases {
+			assert_eq!(ws.serialize(encoding, false).unwrap(), body, "{}", encoding);
+			assert_eq!(ws.serialize(encoding, true).unwrap(), [bom, body].concat(), "{}", encoding);
+		}
+	}
+
+	#[test]
+	fn serialize_round_trip() {
+		let text = "Grüße, 世界! 🦀\r\n";
+		let ws = WString::from_str(text);
+
+		for encoding in [Encoding::Utf8, Encoding::Utf16le, Encoding::Utf16be,
+			Encoding::Utf32le, Encoding::Utf32be]
+		{
+			for with_bom in [false, true] {
+				let bytes = ws.serialize(encoding, with_bom).unwrap();
+				assert_eq!(encode_str(text, encoding, with_bom).unwrap(), bytes);
+
+				let guess = WString::detect_encoding(&bytes);
+				assert_eq!((guess.encoding, guess.bom_size > 0), (encoding, with_bom));
+				assert_eq!(WString::parse_str(&bytes).unwrap().to_string(), text,
+					"{}, BOM {}", encoding, with_bom);
+			}
+		}
+	}
+
+	#[test]
+	fn serialize_win1252() {
+		let bytes = (0x20..=0xff).collect::<Vec<u8>>();
+		let ws = WString::parse_str(&bytes).unwrap();
+		let text = ws.to_string();
+		assert_eq!(text.chars().nth(0x80 - 0x20), Some('€'));
+		assert_eq!(text.chars().nth(0x81 - 0x20), Some('\u{81}')); // undefined, kept as C1 control
+		assert_eq!(text.chars().nth(0x8a - 0x20), Some('Š'));
+		assert_eq!(text.chars().nth(0x9f - 0x20), Some('Ÿ'));
+		assert_eq!(text.chars().nth(0xe9 - 0x20), Some('é'));
+
+		assert_eq!(ws.serialize(Encoding::Win1252, true).unwrap(), bytes); // no BOM in Win1252
+		assert_eq!(encode_str("‘Œ’ – ™", Encoding::Win1252, false).unwrap(),
+			[0x91, 0x8c, 0x92, 0x20, 0x96, 0x20, 0x99]);
+	}
+
+	#[test]
+	fn serialize_unrepresentable() {
+		let no_translation = Err(co::ERROR::NO_UNICODE_TRANSLATION);
+		assert_eq!(encode_str("café", Encoding::Ansi, false), no_translation);
+		assert_eq!(encode_str("日本", Encoding::Win1252, false), no_translation);
+		assert_eq!(encode_str("\u{80}", Encoding::Win1252, false), no_translation); // 0x80 is €
+		assert_eq!(WString::from_wchars_slice(&[0x61, 0xd800]).serialize(Encoding::Utf8, false),
+			no_translation); // unpaired surrogate
+
+		assert_eq!(encode_str("a", Encoding::Scsu, false), Err(co::ERROR::INVALID_PARAMETER));
+		assert_eq!(encode_str("a", Encoding::Bocu1, false), Err(co::ERROR::INVALID_PARAMETER));
+	}
+
+	fn parse_scsu(data: &[u8]) -> String {
+		WString::parse_str(&[&[0x0e, 0xfe, 0xff], data].concat()).unwrap().to_string()
+	}
+
+	fn parse_bocu1(data: &[u8]) -> String {
+		WString::parse_str(&[&[0xfb, 0xee, 0x28], data].concat()).unwrap().to_string()
+	}
+
+	#[test]
+	fn scsu_windows() {
+		// Samples from UTS #6, section 9.
+		assert_eq!(parse_scsu(&[0xd6, 0x6c, 0x20, 0x66, 0x6c, 0x69, 0x65, 0xdf, 0x74]), "Öl fließt");
+		assert_eq!(parse_scsu(&[0x12, 0x9c, 0xbe, 0xc1, 0xba, 0xb2, 0xb0]), "Москва"); // SC2
+		assert_eq!(parse_scsu(&[0x12, 0x9c, 0x20, 0x10, 0xe9]), "М é"); // SC2, then SC0
+		assert_eq!(parse_scsu(&[0x19, 0xfb, 0xa1, 0xc9, 0x12, 0x91]), "Αι\u{411}"); // SD1 to Greek, then SC2
+		assert_eq!(parse_scsu(&[0x1a, 0x07, 0x91]), "Α"); // SD2 to 0x0380
+	}
+
+	#[test]
+	fn scsu_quotes() {
+		assert_eq!(parse_scsu(&[0x02, 0x69, 0x41]), "éA"); // SQ1, static window
+		assert_eq!(parse_scsu(&[0x03, 0x9c, 0xe9]), "Мé"); // SQ2, dynamic window, active one kept
+		assert_eq!(parse_scsu(&[0x01, 0x0c, 0x62]), "\u{c}b"); // SQ0 quotes a tag byte
+		assert_eq!(parse_scsu(&[0x0e, 0x20, 0xac, 0x0e, 0x00, 0x0f]), "€\u{f}"); // SQU
+	}
+
+	#[test]
+	fn scsu_unicode_mode() {
+		assert_eq!(parse_scsu(&[0x0f, 0x4e, 0x16, 0x75, 0x4c, 0x00, 0x41, 0xe0, 0x42]), "世界AB"); // SCU, UC0
+		assert_eq!(parse_scsu(&[0x0f, 0xf0, 0xe0, 0x00, 0xe2, 0x9c]), "\u{e000}М"); // UQU, UC2
+		assert_eq!(parse_scsu(&[0x0f, 0xe9, 0x07, 0x91]), "Α"); // UD1
+	}
+
+	#[test]
+	fn scsu_supplementary() {
+		assert_eq!(parse_scsu(&[0x0b, 0x01, 0xec, 0x80, 0x81]), "😀😁"); // SDX to window 0
+		assert_eq!(parse_scsu(&[0x0b, 0x41, 0xec, 0x80, 0x10, 0x41, 0x12, 0x80]), "😀A😀"); // SDX to window 2, SC0, SC2
+		assert_eq!(parse_scsu(&[0x0f, 0xf1, 0x01, 0xec, 0x81]), "😁"); // UDX
+		assert_eq!(parse_scsu(&[0x0e, 0xd8, 0x3d, 0x0e, 0xde, 0x00]), "😀"); // SQU surrogates
+		assert_eq!(parse_scsu(&[0x0f, 0xd8, 0x3d, 0xde, 0x00]), "😀");
+	}
+
+	#[test]
+	fn scsu_invalid() {
+		assert_eq!(parse_scsu(&[0x41, 0x0c, 0x42]), "A\u{fffd}B"); // reserved tag
+		assert_eq!(parse_scsu(&[0x19, 0x00, 0x80]), "\u{fffd}\u{80}"); // reserved window offset
+		assert_eq!(parse_scsu(&[0x0f, 0xf2, 0x00, 0x41]), "\u{fffd}A");
+		assert_eq!(parse_scsu(&[0x41, 0x0e, 0x20]), "A\u{fffd}"); // truncated SQU
+		assert_eq!(parse_scsu(&[0x0b, 0x01]), "\u{fffd}"); // truncated SDX
+		assert_eq!(parse_scsu(&[0x0f, 0x4e]), "\u{fffd}");
+		assert_eq!(parse_scsu(&[0x0f, 0xd8, 0x3d, 0x00, 0x41]), "\u{fffd}A"); // unpaired surrogate
+		assert_eq!(parse_scsu(&[0x41, 0x00, 0x42]), "A"); // stops at null
+
+		for a in 0..=0xff {
+			for b in 0..=0xff {
+				for tail in [&[][..], &[0x80], &[0xd8, 0x00]] {
+					parse_scsu(&[&[a, b], tail].concat()); // must not panic
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn bocu1_differences() {
+		assert_eq!(WString::detect_encoding(&[0xfb, 0xee, 0x28]).encoding, Encoding::Bocu1);
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xb2, 0xb3]), "abc"); // 3-byte negative, 1-byte
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xd0, 0x76, 0x4f, 0xe1]), "aéa"); // 2-byte
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xfb, 0x33, 0xc0, 0x24, 0xae, 0x44]), "a世a"); // 3-byte
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xfc, 0xff, 0x5d]), "a😀");
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xfe, 0x01, 0x31, 0x95, 0x21, 0xff, 0xdb, 0x59]),
+			"a\u{30000}a"); // 4-byte
+	}
+
+	#[test]
+	fn bocu1_resets() {
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0x52, 0xff, 0xb2]), "ab"); // reset to ASCII
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0xda, 0x20, 0xba]), "é ê"); // space keeps the state
+		assert_eq!(parse_bocu1(&[0x24, 0x1e, 0xda, 0x0a, 0xd0, 0x77]), "é\nê"); // line feed resets it
+		assert_eq!(parse_bocu1(&[0x09, 0xb2, 0x0d, 0x0a, 0xb3]), "\tb\r\nc");
+	}
+
+	#[test]
+	fn bocu1_invalid() {
+		assert_eq!(parse_bocu1(&[0x0a, 0xd0, 0x07, 0xb2]), "\n\u{fffd}b"); // invalid trail byte
+		assert_eq!(parse_bocu1(&[0x0a, 0xb2, 0xfb, 0x33]), "\nb\u{fffd}"); // truncated
+		assert_eq!(parse_bocu1(&[0x0a, 0x21, 0x21, 0x21, 0x21]), "\n\u{fffd}"); // below zero
+		assert_eq!(parse_bocu1(&[0x0a, 0xb2, 0x00, 0xb3]), "\nb"); // stops at null
+
+		for a in 0..=0xff {
+			for b in 0..=0xff {
+				for tail in [&[][..], &[0x21], &[0xff, 0xfe, 0xfe]] {
+					parse_bocu1(&[&[a, b], tail].concat()); // must not panic
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn detect_boms() {
+		let cases: [(&[u8], Encoding, usize); 7] = [
+			(&[0xef, 0xbb, 0xbf, b'a'], Encoding::Utf8, 3),
+			(&[0xfe, 0xff, 0x00, b'a'], Encoding::Utf16be, 2),
+			(&[0xff, 0xfe, b'a', 0x00], Encoding::Utf16le, 2),
+			(&[0x00, 0x00, 0xfe, 0xff, 0x00, 0x00, 0x00, b'a'], Encoding::Utf32be, 4),
+			(&[0xff, 0xfe, 0x00, 0x00, b'a', 0x00, 0x00, 0x00], Encoding::Utf32le, 4),
+			(&[0x0e, 0xfe, 0xff, b'a'], Encoding::Scsu, 3),
+			(&[0xfb, 0xee, 0x28, 0xb2], Encoding::Bocu1, 3),
+		];
+
+		for (data, encoding, bom_size) in cases {
+			assert_eq!(WString::detect_encoding(data),
+				EncodingGuess { encoding, bom_size, confidence: 1.0 });
+			assert_eq!(WString::guess_encoding(data), (encoding, bom_size));
+		}
+	}
+
+	#[test]
+	fn detect_without_bom() {
+		let guess = WString::detect_encoding(&encode_str("[a]\r\nk=v", Encoding::Utf16le, false).unwrap());
+		assert_eq!((guess.encoding, guess.bom_size), (Encoding::Utf16le, 0));
+		assert!(guess.confidence > 0.9);
+		let guess = WString::detect_encoding(&encode_str("[a]\r\nk=v", Encoding::Utf16be, false).unwrap());
+		assert_eq!((guess.encoding, guess.bom_size), (Encoding::Utf16be, 0));
+
+		assert_eq!(WString::detect_encoding(b"[a]\r\nk=v"),
+			EncodingGuess { encoding: Encoding::Ansi, bom_size: 0, confidence: 1.0 });
+		assert_eq!(WString::detect_encoding("k=café".as_bytes()).encoding, Encoding::Utf8);
+		assert_eq!(WString::detect_encoding(b"k=caf\xe9 au lait").encoding, Encoding::Win1252);
+	}
+
+	#[test]
+	fn detect_confidence() {
+		let confidence = |data: &[u8]| WString::detect_encoding(data).confidence;
+
+		// Fewer Latin-1 chars in UTF-16 are weaker evidence.
+		let latin = encode_str("abcd", Encoding::Utf16le, false).unwrap();
+		let mixed = encode_str("a世界b", Encoding::Utf16le, false).unwrap();
+		assert!(confidence(&mixed) < confidence(&latin));
+
+		// A single multi-byte sequence may be a coincidence.
+		assert!(confidence("café".as_bytes()) < confidence("çà et là, où".as_bytes()));
+
+		// Undefined Windows-1252 bytes are suspicious.
+		assert!(confidence(b"caf\xe9 \x81\x8d") < confidence(b"caf\xe9 na\xefve"));
+
+		// ASCII with nulls, which fit no UTF-16 or UTF-32 pattern.
+		assert_eq!(WString::detect_encoding(&[b'a', 0x00, 0x00, b'b']),
+			EncodingGuess { encoding: Encoding::Ansi, bom_size: 0, confidence: 0.5 });
+	}
+
+	#[test]
+	fn detect_tiny_input() {
+		assert_eq!(WString::detect_encoding(&[]).encoding, Encoding::Ansi);
+		assert_eq!(WString::detect_encoding(b"a").encoding, Encoding::Ansi);
+		assert_eq!(WString::detect_encoding(&[0x00]).encoding, Encoding::Ansi);
+		assert_eq!(WString::detect_encoding(&[0xff]).encoding, Encoding::Win1252);
+		assert_eq!(WString::detect_encoding(&[0xc3]).encoding, Encoding::Utf8); // truncated sequence
+
+		for by in 0..=0xff {
+			WString::parse_str(&[by]).unwrap(); // must not panic
+		}
+		assert!(WString::parse_str(&[]).unwrap().is_null());
+	}
+
+	#[test]
+	fn sso_from_str() {
+		assert!(matches!(WString::default().buf, Buffer::Null));
+		assert!(matches!(WString::from_opt_str(None).buf, Buffer::Null));
+
+		let inline = WString::from_str("0123456789012345678"); // 19 chars, plus null
+		assert!(matches!(inline.buf, Buffer::Stack { len: SSO_LEN, .. }));
+		assert_eq!(inline.buffer_size(), SSO_LEN);
+		assert_eq!(inline.to_string(), "0123456789012345678");
+
+		let heap = WString::from_str("01234567890123456789"); // 20 chars, plus null
+		assert!(matches!(heap.buf, Buffer::Heap(_)));
+		assert_eq!(heap.buffer_size(), SSO_LEN + 1);
+		assert_eq!(heap.to_string(), "01234567890123456789");
+
+		for ws in [WString::from_str(""), inline, heap, WString::from_str("ção 😀")] {
+			assert_eq!(ws.as_slice().last(), Some(&0x0000));
+			assert_eq!(ws.as_wstr().as_slice_with_nul(), ws.as_slice());
+		}
+	}
+
+	#[test]
+	fn sso_realloc() {
+		let mut ws = WString::from_str("abc");
+		ws.realloc_buffer(SSO_LEN);
+		assert!(matches!(ws.buf, Buffer::Stack { len: SSO_LEN, .. }));
+		assert_eq!(ws.to_string(), "abc");
+
+		ws.realloc_buffer(SSO_LEN + 1); // stack to heap
+		assert!(matches!(ws.buf, Buffer::Heap(_)));
+		assert_eq!((ws.buffer_size(), ws.to_string()), (SSO_LEN + 1, "abc".to_owned()));
+
+		ws.realloc_buffer(2); // heap is kept, chars are truncated
+		assert!(matches!(ws.buf, Buffer::Heap(_)));
+		assert_eq!(ws.as_slice(), [0x61, 0x62]);
+		assert_eq!(ws.len(), 2); // no null
+		assert!(ws.as_wstr().is_empty());
+
+		ws.realloc_buffer(0);
+		assert!(ws.is_null());
+
+		let mut ws = WString::new_alloc_buffer(4);
+		assert!(matches!(ws.buf, Buffer::Stack { len: 4, .. }));
+		ws.as_mut_slice()[..2].copy_from_slice(&[0x68, 0x69]);
+		assert_eq!((ws.len(), ws.to_string()), (2, "hi".to_owned()));
+	}
 }
@@ -44,6 +44,7 @@
 //! | `msimg` | Msimg32.dll |
 //! | `ole` | OLE and basic COM support |
 //! | `oleaut` | [OLE Automation](https://docs.microsoft.com/en-us/windows/win32/api/_automat/) |
+//! | `serde` | [Serde](https://serde.rs) support, like [`ini_serde`](crate::ini_serde) and [`reg_serde`](crate::reg_serde) |
 //! | `shell` | Shell32.dll, the COM-based [Windows Shell](https://docs.microsoft.com/en-us/windows/win32/shell/shell-entry) |
 //! | `shlwapi` | Shlwapi.dll, for some [Shell](https://docs.microsoft.com/en-us/windows/win32/api/shlwapi/) functions |
 //! | `user` | User32.dll, the basic Windows UI support |
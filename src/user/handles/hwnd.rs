@@ -2,8 +2,8 @@
 
 use crate::{co, user};
 use crate::ffi_types::BOOL;
-use crate::kernel::decl::{GetLastError, HINSTANCE, SetLastError, WinResult,
-	WString};
+use crate::kernel::decl::{GetLastError, HINSTANCE, SetLastError, ToWStr,
+	WinResult, WString};
 use crate::kernel::privs::bool_to_winresult;
 use crate::prelude::{Handle, MsgSend};
 use crate::user::decl::{ALTTABINFO, AtomStr, HACCEL, HDC, HMENU, HMONITOR, HRGN,
@@ -1225,12 +1225,12 @@ pub trait UserHwnd: Handle {
 
 	/// [`SetWindowText`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowtextw)
 	/// method.
-	fn SetWindowText(self, text: &str) -> WinResult<()> {
+	fn SetWindowText(self, text: &(impl ToWStr + ?Sized)) -> WinResult<()> {
 		bool_to_winresult(
 			unsafe {
 				user::ffi::SetWindowTextW(
 					self.as_ptr(),
-					WString::from_str(text).as_ptr(),
+					text.to_wstr().as_ptr(),
 				)
 			},
 		)
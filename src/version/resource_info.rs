@@ -1,10 +1,16 @@
 use crate::co;
-use crate::kernel::decl::{LANGID, WinResult, WString};
-use crate::version::decl::{GetFileVersionInfo, VarQueryValue, VS_FIXEDFILEINFO};
+use crate::kernel::decl::{LANGID, WinResult};
+use crate::version::decl::{GetFileVersionInfo, VS_FIXEDFILEINFO};
 
 /// Retrieves data from an embedded resource, which can be read from an
 /// executable file or a DLL.
 ///
+/// The
+/// [`VS_VERSIONINFO`](https://docs.microsoft.com/en-us/windows/win32/menurc/vs-versioninfo)
+/// tree is parsed in pure Rust, so the resource data can also come from a PE
+/// file read on any platform, with
+/// [`ResourceInfo::parse`](crate::ResourceInfo::parse).
+///
 /// # Examples
 ///
 /// Reading version information:
@@ -45,51 +51,140 @@ use crate::version::decl::{GetFileVersionInfo, VarQueryValue, VS_FIXEDFILEINFO};
 /// }
 /// # Ok::<_, winsafe::co::ERROR>(())
 /// ```
+///
+/// Listing all the strings of all string tables:
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::{HINSTANCE, ResourceInfo};
+///
+/// let exe_name = HINSTANCE::NULL.GetModuleFileName()?;
+/// let res_info = ResourceInfo::read_from(&exe_name)?;
+///
+/// for table in res_info.string_tables() {
+///     println!("{:04x}{:04x}",
+///         u16::from(table.lang_id()), u16::from(table.code_page()));
+///     for (key, val) in table.strings() {
+///         println!("  {} = {}", key, val);
+///     }
+/// }
+/// # Ok::<_, winsafe::co::ERROR>(())
+/// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "version")))]
 pub struct ResourceInfo {
-	res_buf: Vec<u8>,
+	fixed_info: Option<VS_FIXEDFILEINFO>,
+	string_tables: Vec<StringTable>,
+	vars: Vec<(String, Vec<u8>)>,
+}
+
+/// A `StringTable` entry of the `StringFileInfo` block.
+struct StringTable {
+	lang_id: LANGID,
+	code_page: co::CP,
+	strings: Vec<(String, String)>,
 }
 
 impl ResourceInfo {
-	/// Reads and stores the resource data from an executable file or a DLL.
+	/// Reads and parses the resource data from an executable file or a DLL.
 	pub fn read_from(exe_file: &str) -> WinResult<ResourceInfo> {
-		Ok(Self { res_buf: GetFileVersionInfo(exe_file)? })
+		Self::parse(&GetFileVersionInfo(exe_file)?)
 	}
 
-	/// Returns an iterator over the language blocks, if any, which are composed
-	/// of a language ID and a code page.
+	/// Parses a raw `VS_VERSIONINFO` resource, as returned by
+	/// [`GetFileVersionInfo`](crate::GetFileVersionInfo) or as stored in the
+	/// `RT_VERSION` resource of a PE file. Any trailing data after the root
+	/// block is ignored.
+	///
+	/// Fails with [`co::ERROR::INVALID_DATA`](crate::co::ERROR::INVALID_DATA)
+	/// if any of the block lengths is inconsistent, if the blocks are nested
+	/// too deeply, or if the fixed file information has a wrong size or
+	/// signature.
+	pub fn parse(res_buf: &[u8]) -> WinResult<ResourceInfo> {
+		let (root, _) = Node::parse(res_buf, 0, res_buf.len(), 1)?;
+		if root.key != "VS_VERSION_INFO" {
+			return Err(co::ERROR::INVALID_DATA);
+		}
+
+		let fixed_info = if root.value.is_empty() {
+			None
+		} else {
+			Some(VS_FIXEDFILEINFO::from_bytes(root.value)
+				.ok_or(co::ERROR::INVALID_DATA)?)
+		};
+
+		let mut string_tables = Vec::default();
+		let mut vars = Vec::default();
+
+		for block in root.children.iter() {
+			match block.key.as_str() {
+				"StringFileInfo" => for table in block.children.iter() {
+					let (lang_id, code_page) = parse_table_key(&table.key)
+						.ok_or(co::ERROR::INVALID_DATA)?;
+					string_tables.push(StringTable {
+						lang_id,
+						code_page,
+						strings: table.children.iter()
+							.map(|s| (s.key.clone(), s.text()))
+							.collect(),
+					});
+				},
+				"VarFileInfo" => for var in block.children.iter() {
+					vars.push((var.key.clone(), var.value.to_vec()));
+				},
+				_ => {}, // unknown blocks are ignored
+			}
+		}
+
+		Ok(Self { fixed_info, string_tables, vars })
+	}
+
+	/// Returns an iterator over the language blocks listed in
+	/// `VarFileInfo\Translation`, if any, which are composed of a language ID
+	/// and a code page.
 	///
 	/// These blocks allow retrieval of version information strings in their
 	/// respective languages.
-	pub fn blocks<'a>(&'a self) -> impl Iterator<Item = ResourceInfoBlock> + 'a {
-		unsafe {
-			VarQueryValue::<(LANGID, co::CP)>(&self.res_buf, "\\VarFileInfo\\Translation")
-				.ok()
-				.map(|(plangs, sz)|
-					std::slice::from_raw_parts(
-						plangs,
-						sz as usize / std::mem::size_of::<(LANGID, co::CP)>(),
-					)
-				)
-				.unwrap_or(&[])
-				.iter()
-				.map(|(lang_id, code_page)|
-					ResourceInfoBlock {
-						res_info: self,
-						lang_id: *lang_id,
-						code_page: *code_page,
-					}
-				)
-		}
+	pub fn blocks(&self) -> impl Iterator<Item = ResourceInfoBlock<'_>> + '_ {
+		self.var("Translation")
+			.unwrap_or(&[])
+			.chunks_exact(4)
+			.map(|pair| ResourceInfoBlock {
+				res_info: self,
+				lang_id: LANGID(u16::from_le_bytes([pair[0], pair[1]])),
+				code_page: co::CP(u16::from_le_bytes([pair[2], pair[3]])),
+			})
+	}
+
+	/// Returns an iterator over all the `StringTable` blocks actually present
+	/// in `StringFileInfo`, regardless of what is listed in
+	/// `VarFileInfo\Translation`.
+	pub fn string_tables(&self) -> impl Iterator<Item = ResourceInfoBlock<'_>> + '_ {
+		self.string_tables.iter()
+			.map(|table| ResourceInfoBlock {
+				res_info: self,
+				lang_id: table.lang_id,
+				code_page: table.code_page,
+			})
+	}
+
+	/// Returns the raw value of the given `Var` entry in `VarFileInfo`, if
+	/// any. The name is case-insensitive.
+	pub fn var(&self, name: &str) -> Option<&[u8]> {
+		self.vars.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, val)| val.as_ref())
+	}
+
+	/// Returns an iterator over all the `Var` entries in `VarFileInfo`, with
+	/// their names and raw values.
+	pub fn vars(&self) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+		self.vars.iter()
+			.map(|(key, val)| (key.as_str(), val.as_ref()))
 	}
 
 	/// Returns the version information, if any.
 	pub fn version_info(&self) -> Option<&VS_FIXEDFILEINFO> {
-		unsafe {
-			VarQueryValue::<VS_FIXEDFILEINFO>(&self.res_buf, "\\")
-				.ok()
-				.map(|(pvsf, _)| &*pvsf)
-		}
+		self.fixed_info.as_ref()
 	}
 }
 
@@ -106,31 +201,535 @@ impl<'a> ResourceInfoBlock<'a> {
 	pub const fn lang_id(&self) -> LANGID { self.lang_id }
 	pub const fn code_page(&self) -> co::CP { self.code_page }
 
-	pub fn comments(&self) -> Option<String> { self.generic_string_info("Comments") }
-	pub fn company_name(&self) -> Option<String> { self.generic_string_info("CompanyName") }
-	pub fn file_description(&self) -> Option<String> { self.generic_string_info("FileDescrition") }
-	pub fn file_version(&self) -> Option<String> { self.generic_string_info("FileVersion") }
-	pub fn internal_name(&self) -> Option<String> { self.generic_string_info("InternalName") }
-	pub fn legal_copyright(&self) -> Option<String> { self.generic_string_info("LegalCopyright") }
-	pub fn legal_trademarks(&self) -> Option<String> { self.generic_string_info("LegalTrademarks") }
-	pub fn original_filename(&self) -> Option<String> { self.generic_string_info("OriginalFilename") }
-	pub fn product_name(&self) -> Option<String> { self.generic_string_info("ProductName") }
-	pub fn product_version(&self) -> Option<String> { self.generic_string_info("ProductVersion") }
-	pub fn private_build(&self) -> Option<String> { self.generic_string_info("PrivateBuild") }
-	pub fn special_build(&self) -> Option<String> { self.generic_string_info("SpecialBuild") }
-
-	fn generic_string_info(&self, info: &str) -> Option<String> {
-		unsafe {
-			VarQueryValue::<u16>(
-				&self.res_info.res_buf,
-				&format!("\\StringFileInfo\\{:04x}{:04x}\\{}",
-					u16::from(self.lang_id), u16::from(self.code_page), info),
-			).ok()
-				.map(|(pstr, len)| {
-					WString::from_wchars_slice(
-						std::slice::from_raw_parts(pstr, len as _),
-					).to_string()
-				})
+	pub fn comments(&self) -> Option<String> { self.string("Comments") }
+	pub fn company_name(&self) -> Option<String> { self.string("CompanyName") }
+	pub fn file_description(&self) -> Option<String> { self.string("FileDescription") }
+	pub fn file_version(&self) -> Option<String> { self.string("FileVersion") }
+	pub fn internal_name(&self) -> Option<String> { self.string("InternalName") }
+	pub fn legal_copyright(&self) -> Option<String> { self.string("LegalCopyright") }
+	pub fn legal_trademarks(&self) -> Option<String> { self.string("LegalTrademarks") }
+	pub fn original_filename(&self) -> Option<String> { self.string("OriginalFilename") }
+	pub fn product_name(&self) -> Option<String> { self.string("ProductName") }
+	pub fn product_version(&self) -> Option<String> { self.string("ProductVersion") }
+	pub fn private_build(&self) -> Option<String> { self.string("PrivateBuild") }
+	pub fn special_build(&self) -> Option<String> { self.string("SpecialBuild") }
+
+	/// Returns the value of an arbitrary string of this block, if any. The key
+	/// is case-insensitive.
+	pub fn string(&self, key: &str) -> Option<String> {
+		self.strings()
+			.find(|(k, _)| k.eq_ignore_ascii_case(key))
+			.map(|(_, v)| v.to_owned())
+	}
+
+	/// Returns an iterator over all the key/value strings of this block, in
+	/// the order they are stored. If the block has no corresponding
+	/// `StringTable`, the iterator is empty.
+	pub fn strings(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+		let (lang_id, code_page) = (self.lang_id, self.code_page);
+		self.res_info.string_tables.iter()
+			.find(move |table| table.lang_id == lang_id && table.code_page == code_page)
+			.map(|table| table.strings.as_slice())
+			.unwrap_or(&[])
+			.iter()
+			.map(|(k, v)| (k.as_str(), v.as_str()))
+	}
+}
+
+/// Builds a
+/// [`VS_VERSIONINFO`](https://docs.microsoft.com/en-us/windows/win32/menurc/vs-versioninfo)
+/// resource, which can be parsed back with
+/// [`ResourceInfo::parse`](crate::ResourceInfo::parse), or written into an
+/// executable file with
+/// [`HUPDATERSRC::UpdateResource`](crate::prelude::KernelHupdatersrc::UpdateResource).
+///
+/// If no translation is explicitly added, `VarFileInfo\Translation` will list
+/// the language/code page pairs of all the string tables, in the order they
+/// were created.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use winsafe::prelude::*;
+/// use winsafe::{co, HUPDATERSRC, IdStr, LANGID, ResourceInfoBuilder,
+///     RtStr, VS_FIXEDFILEINFO};
+///
+/// let lang_id = LANGID::new(co::LANG::ENGLISH, co::SUBLANG::ENGLISH_US);
+///
+/// let mut fixed_info = VS_FIXEDFILEINFO::default();
+/// fixed_info.dwStrucVersion = 0x0001_0000;
+/// fixed_info.set_dwFileVersion([1, 2, 0, 0]);
+/// fixed_info.set_dwProductVersion([1, 2, 0, 0]);
+/// fixed_info.dwFileOS = co::VOS::NT_WINDOWS32;
+/// fixed_info.dwFileType = co::VFT::APP;
+///
+/// let res_buf = ResourceInfoBuilder::new()
+///     .fixed_info(fixed_info)
+///     .string(lang_id, co::CP::UTF16, "CompanyName", "My Company")
+///     .string(lang_id, co::CP::UTF16, "FileVersion", "1.2.0.0")
+///     .string(lang_id, co::CP::UTF16, "BuildServer", "ci-03")
+///     .serialize()?;
+///
+/// let hupsrc = HUPDATERSRC::BeginUpdateResource("C:\\Temp\\setup.exe", false)?;
+/// hupsrc.UpdateResource(RtStr::Rt(co::RT::VERSION), IdStr::Id(1),
+///     lang_id, &res_buf)?;
+/// hupsrc.EndUpdateResource(false)?;
+/// # Ok::<_, co::ERROR>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "version")))]
+#[derive(Default)]
+pub struct ResourceInfoBuilder {
+	fixed_info: Option<VS_FIXEDFILEINFO>,
+	string_tables: Vec<StringTable>,
+	translations: Vec<(LANGID, co::CP)>,
+}
+
+impl ResourceInfoBuilder {
+	/// Creates a new, empty builder, without fixed file information.
+	pub fn new() -> ResourceInfoBuilder {
+		Self::default()
+	}
+
+	/// Sets the fixed file information of the root block.
+	pub fn fixed_info(&mut self, fixed_info: VS_FIXEDFILEINFO) -> &mut ResourceInfoBuilder {
+		self.fixed_info = Some(fixed_info);
+		self
+	}
+
+	/// Sets an information string in the string table of the given language
+	/// and code page, creating the table if needed. An existing string with
+	/// the same key, compared case-insensitively, is replaced.
+	pub fn string(&mut self,
+		lang_id: LANGID, code_page: co::CP,
+		key: &str, value: &str) -> &mut ResourceInfoBuilder
+	{
+		let idx = match self.string_tables.iter()
+			.position(|table| table.lang_id == lang_id && table.code_page == code_page)
+		{
+			Some(idx) => idx,
+			None => {
+				self.string_tables.push(StringTable {
+					lang_id,
+					code_page,
+					strings: Vec::default(),
+				});
+				self.string_tables.len() - 1
+			},
+		};
+
+		let strings = &mut self.string_tables[idx].strings;
+		match strings.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+			Some((_, v)) => *v = value.to_owned(),
+			None => strings.push((key.to_owned(), value.to_owned())),
 		}
+		self
+	}
+
+	/// Adds a language/code page pair to `VarFileInfo\Translation`.
+	pub fn translation(&mut self,
+		lang_id: LANGID, code_page: co::CP) -> &mut ResourceInfoBuilder
+	{
+		self.translations.push((lang_id, code_page));
+		self
+	}
+
+	/// Serializes the resource into its binary format, with all the blocks
+	/// aligned to 32-bit boundaries.
+	///
+	/// Fails with
+	/// [`co::ERROR::ARITHMETIC_OVERFLOW`](crate::co::ERROR::ARITHMETIC_OVERFLOW)
+	/// if any block exceeds the 64 KB limit of the format.
+	pub fn serialize(&self) -> WinResult<Vec<u8>> {
+		let fixed_info = self.fixed_info.as_ref()
+			.map(|fixed_info| fixed_info.to_bytes())
+			.unwrap_or_default();
+
+		let translations = if self.translations.is_empty() {
+			self.string_tables.iter()
+				.map(|table| (table.lang_id, table.code_page))
+				.collect()
+		} else {
+			self.translations.clone()
+		};
+
+		let mut buf = Vec::default();
+		write_node(&mut buf, "VS_VERSION_INFO", false, &fixed_info, fixed_info.len(), |buf| {
+			if !self.string_tables.is_empty() {
+				write_node(buf, "StringFileInfo", true, &[], 0, |buf| {
+					for table in self.string_tables.iter() {
+						let table_key = format!("{:04x}{:04x}",
+							u16::from(table.lang_id), u16::from(table.code_page));
+						write_node(buf, &table_key, true, &[], 0, |buf| {
+							for (key, val) in table.strings.iter() {
+								let wval = to_wchars(val);
+								write_node(buf, key, true, &wval, wval.len() / 2, |_| Ok(()))?;
+							}
+							Ok(())
+						})?;
+					}
+					Ok(())
+				})?;
+			}
+
+			if !translations.is_empty() {
+				write_node(buf, "VarFileInfo", true, &[], 0, |buf| {
+					let pairs = translations.iter()
+						.flat_map(|(lang_id, code_page)| {
+							let mut pair = [0; 4];
+							pair[..2].copy_from_slice(&u16::from(*lang_id).to_le_bytes());
+							pair[2..].copy_from_slice(&u16::from(*code_page).to_le_bytes());
+							pair
+						})
+						.collect::<Vec<_>>();
+					write_node(buf, "Translation", false, &pairs, pairs.len(), |_| Ok(()))
+				})?;
+			}
+			Ok(())
+		})?;
+
+		Ok(buf)
+	}
+}
+
+/// A parsed block of the `VS_VERSIONINFO` tree, borrowing its value from the
+/// resource buffer.
+struct Node<'a> {
+	key: String,
+	value: &'a [u8],
+	children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+	/// Maximum nesting of blocks; a valid resource has 4 levels, so this only
+	/// stops crafted data from exhausting the stack.
+	const MAX_DEPTH: usize = 8;
+
+	/// Parses the block starting at `start`, which must end up to `limit`,
+	/// along with all its children. Returns the block and its end offset.
+	fn parse(buf: &'a [u8],
+		start: usize, limit: usize, depth: usize) -> WinResult<(Node<'a>, usize)>
+	{
+		let invalid = co::ERROR::INVALID_DATA;
+		if depth > Self::MAX_DEPTH {
+			return Err(invalid);
+		}
+
+		let length = read_u16(buf, start).ok_or(invalid)? as usize;
+		let end = start + length;
+		if length < 6 || end > limit {
+			return Err(invalid);
+		}
+		let value_length = read_u16(buf, start + 2).ok_or(invalid)? as usize;
+		let is_text = match read_u16(buf, start + 4).ok_or(invalid)? {
+			0 => false,
+			1 => true,
+			_ => return Err(invalid),
+		};
+
+		let mut key = Vec::default();
+		let mut off = start + 6;
+		loop {
+			if off + 2 > end {
+				return Err(invalid); // key not null-terminated within the block
+			}
+			let ch = read_u16(buf, off).ok_or(invalid)?;
+			off += 2;
+			if ch == 0 {
+				break;
+			}
+			key.push(ch);
+		}
+
+		let value_start = align4(off).min(end);
+		let value_end = if is_text {
+			// Text lengths are in WCHARs; some resource compilers write them in
+			// bytes, so we just clamp to the block.
+			(value_start + value_length * 2).min(end)
+		} else {
+			value_start + value_length
+		};
+		if value_end > end {
+			return Err(invalid);
+		}
+
+		let mut children = Vec::default();
+		let mut off = align4(value_end);
+		while off < end {
+			let (child, child_end) = Self::parse(buf, off, end, depth + 1)?;
+			children.push(child);
+			off = align4(child_end);
+		}
+
+		Ok((Self {
+			key: String::from_utf16_lossy(&key),
+			value: &buf[value_start..value_end],
+			children,
+		}, end))
+	}
+
+	/// Decodes the value as a string, up to the first null.
+	fn text(&self) -> String {
+		let wchars = self.value.chunks_exact(2)
+			.map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+			.take_while(|ch| *ch != 0)
+			.collect::<Vec<_>>();
+		String::from_utf16_lossy(&wchars)
+	}
+}
+
+/// Rounds up to the next 32-bit boundary.
+const fn align4(off: usize) -> usize {
+	(off + 3) & !3
+}
+
+/// Pads the buffer with zeros up to the next 32-bit boundary.
+fn pad4(buf: &mut Vec<u8>) {
+	buf.resize(align4(buf.len()), 0);
+}
+
+/// Encodes a string as null-terminated UTF-16LE bytes.
+fn to_wchars(s: &str) -> Vec<u8> {
+	s.encode_utf16()
+		.chain(std::iter::once(0))
+		.flat_map(|ch| ch.to_le_bytes())
+		.collect()
+}
+
+/// Appends a block with its header, key and value, then lets `children`
+/// append the child blocks. The `value_length` is in bytes for binary values,
+/// and in WCHARs for text values.
+fn write_node<F>(buf: &mut Vec<u8>,
+	key: &str, is_text: bool,
+	value: &[u8], value_length: usize, children: F) -> WinResult<()>
+	where F: FnOnce(&mut Vec<u8>) -> WinResult<()>,
+{
+	let to_u16 = |n: usize| u16::try_from(n)
+		.map_err(|_| co::ERROR::ARITHMETIC_OVERFLOW);
+
+	pad4(buf);
+	let start = buf.len();
+	buf.extend_from_slice(&[0, 0]); // wLength, filled below
+	buf.extend_from_slice(&to_u16(value_length)?.to_le_bytes());
+	buf.extend_from_slice(&(is_text as u16).to_le_bytes());
+	buf.extend_from_slice(&to_wchars(key));
+	pad4(buf);
+	buf.extend_from_slice(value);
+	children(buf)?;
+
+	let length = to_u16(buf.len() - start)?;
+	buf[start..start + 2].copy_from_slice(&length.to_le_bytes());
+	Ok(())
+}
+
+fn read_u16(buf: &[u8], off: usize) -> Option<u16> {
+	buf.get(off..off + 2)
+		.map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+}
+
+/// Parses a `StringTable` key like `040904b0` into language ID and code page.
+fn parse_table_key(key: &str) -> Option<(LANGID, co::CP)> {
+	if key.len() != 8 || !key.is_ascii() {
+		return None;
+	}
+	Some((
+		LANGID(u16::from_str_radix(&key[..4], 16).ok()?),
+		co::CP(u16::from_str_radix(&key[4..], 16).ok()?),
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Writes a raw block, with an arbitrary `wValueLength`.
+	fn block(key: &str, is_text: bool,
+		value: &[u8], value_length: u16, children: &[Vec<u8>]) -> Vec<u8>
+	{
+		let mut buf = vec![0, 0];
+		buf.extend_from_slice(&value_length.to_le_bytes());
+		buf.extend_from_slice(&(is_text as u16).to_le_bytes());
+		buf.extend_from_slice(&to_wchars(key));
+		pad4(&mut buf);
+		buf.extend_from_slice(value);
+		for child in children.iter() {
+			pad4(&mut buf);
+			buf.extend_from_slice(child);
+		}
+		let length = buf.len() as u16;
+		buf[..2].copy_from_slice(&length.to_le_bytes());
+		buf
+	}
+
+	fn root(children: &[Vec<u8>]) -> Vec<u8> {
+		block("VS_VERSION_INFO", false, &[], 0, children)
+	}
+
+	fn sample() -> Vec<u8> {
+		let lang_id = LANGID::new(co::LANG::ENGLISH, co::SUBLANG::ENGLISH_US);
+		let mut fixed_info = VS_FIXEDFILEINFO::default();
+		fixed_info.set_dwFileVersion([1, 2, 3, 4]);
+		ResourceInfoBuilder::new()
+			.fixed_info(fixed_info)
+			.string(lang_id, co::CP::UTF16, "CompanyName", "My Company")
+			.string(lang_id, co::CP::UTF16, "FileVersion", "1.2.3.4")
+			.serialize()
+			.unwrap()
+	}
+
+	#[test]
+	fn parse_sample() {
+		let mut buf = sample();
+		buf.extend_from_slice(&[1, 2, 3]); // trailing data is ignored
+		let res_info = ResourceInfo::parse(&buf).unwrap();
+		assert_eq!(res_info.version_info().unwrap().dwFileVersion(), [1, 2, 3, 4]);
+		let block = res_info.blocks().next().unwrap();
+		assert_eq!(block.company_name().as_deref(), Some("My Company"));
+		assert_eq!(block.string("fileversion").as_deref(), Some("1.2.3.4"));
+	}
+
+	#[test]
+	fn truncated() {
+		let buf = sample();
+		for len in 0..buf.len() {
+			assert_eq!(ResourceInfo::parse(&buf[..len]).err(), Some(co::ERROR::INVALID_DATA),
+				"length {}", len);
+		}
+	}
+
+	#[test]
+	fn bad_headers() {
+		let fixed = VS_FIXEDFILEINFO::default().to_bytes();
+		let invalid = Some(co::ERROR::INVALID_DATA);
+
+		let buf = block("VS_VERSION_INFO", false, &fixed, 0x1000, &[]); // value past the block
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+		let buf = block("VS_VERSION_INFO", false, &fixed, 12, &[]); // fixed info size
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+		let mut buf = block("VS_VERSION_INFO", false, &fixed, 52, &[]);
+		buf[4] = 2; // wType
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+		let mut buf = block("VS_VERSION_INFO", false, &fixed, 52, &[]);
+		buf[40] ^= 0xff; // signature, right after the aligned key
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+		let buf = block("VS_VERSION_INF", false, &fixed, 52, &[]);
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+
+		let mut buf = root(&[]);
+		buf[..2].copy_from_slice(&4u16.to_le_bytes()); // shorter than the header
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+		let mut buf = root(&[]);
+		buf[..2].copy_from_slice(&12u16.to_le_bytes()); // key cut before its null
+		assert_eq!(ResourceInfo::parse(&buf).err(), invalid);
+
+		let table = block("StringFileInfo", true, &[], 0, &[
+			block("0409", true, &[], 0, &[]), // table key too short
+		]);
+		assert_eq!(ResourceInfo::parse(&root(&[table])).err(), invalid);
+	}
+
+	#[test]
+	fn text_length_clamped() {
+		let string = block("Comments", true, &to_wchars("hi"), 500, &[]); // too long
+		let buf = root(&[block("StringFileInfo", true, &[], 0, &[
+			block("040904b0", true, &[], 0, &[string]),
+		])]);
+		let res_info = ResourceInfo::parse(&buf).unwrap();
+		assert_eq!(res_info.string_tables().next().unwrap().comments().as_deref(), Some("hi"));
+	}
+
+	#[test]
+	fn misaligned_lengths() {
+		let buf = root(&[block("VarFileInfo", true, &[], 0, &[
+			block("A", false, &[1, 2, 3], 3, &[]), // next block starts after padding
+			block("B", false, &[4, 5, 6, 7, 8], 5, &[]),
+		])]);
+		let res_info = ResourceInfo::parse(&buf).unwrap();
+		assert_eq!(res_info.vars().collect::<Vec<_>>(),
+			[("A", &[1u8, 2, 3][..]), ("B", &[4, 5, 6, 7, 8][..])]);
+		assert_eq!(res_info.var("b"), Some(&[4u8, 5, 6, 7, 8][..]));
+		assert_eq!(res_info.blocks().count(), 0); // no Translation
+	}
+
+	#[test]
+	fn nesting_limit() {
+		let nested = |levels: usize| {
+			let mut buf = block("X", false, &[], 0, &[]);
+			for _ in 2..levels {
+				buf = block("X", false, &[], 0, &[buf]);
+			}
+			root(&[buf])
+		};
+		assert!(ResourceInfo::parse(&nested(Node::MAX_DEPTH)).is_ok());
+		assert_eq!(ResourceInfo::parse(&nested(Node::MAX_DEPTH + 1)).err(),
+			Some(co::ERROR::INVALID_DATA));
+		assert_eq!(ResourceInfo::parse(&nested(1000)).err(), Some(co::ERROR::INVALID_DATA));
+	}
+
+	#[test]
+	fn builder_round_trip() {
+		let en = LANGID::new(co::LANG::ENGLISH, co::SUBLANG::ENGLISH_US);
+		let pt = LANGID::new(co::LANG::PORTUGUESE, co::SUBLANG::PORTUGUESE_BRAZILIAN);
+		let mut fixed_info = VS_FIXEDFILEINFO::default();
+		fixed_info.set_dwFileVersion([10, 0, 19041, 1]);
+		fixed_info.dwFileType = co::VFT::DLL;
+
+		let buf = ResourceInfoBuilder::new()
+			.fixed_info(fixed_info)
+			.string(en, co::CP::UTF16, "ProductName", "Tool")
+			.string(en, co::CP::UTF16, "Comments", "")
+			.string(pt, co::CP::WINDOWS_1252, "ProductName", "Ferramenta")
+			.string(pt, co::CP::WINDOWS_1252, "FileDescription", "ação")
+			.string(en, co::CP::UTF16, "productname", "Tool 2") // replaces
+			.serialize()
+			.unwrap();
+		assert_eq!(buf.len() % 4, 0);
+
+		let res_info = ResourceInfo::parse(&buf).unwrap();
+		let fixed = res_info.version_info().unwrap();
+		assert_eq!(fixed.dwFileVersion(), [10, 0, 19041, 1]);
+		assert_eq!(fixed.dwFileType, co::VFT::DLL);
+
+		let blocks = res_info.blocks()
+			.map(|block| (block.lang_id(), block.code_page(),
+				block.strings().map(|(k, v)| (k.to_owned(), v.to_owned())).collect::<Vec<_>>()))
+			.collect::<Vec<_>>();
+		assert_eq!(blocks, [
+			(en, co::CP::UTF16, vec![
+				("ProductName".to_owned(), "Tool 2".to_owned()),
+				("Comments".to_owned(), "".to_owned()),
+			]),
+			(pt, co::CP::WINDOWS_1252, vec![
+				("ProductName".to_owned(), "Ferramenta".to_owned()),
+				("FileDescription".to_owned(), "ação".to_owned()),
+			]),
+		]);
+		assert_eq!(res_info.string_tables().count(), 2);
+
+		let en_block = res_info.blocks().next().unwrap();
+		assert_eq!(en_block.comments().as_deref(), Some("")); // present, but empty
+		assert_eq!(en_block.company_name(), None);
+	}
+
+	#[test]
+	fn builder_translations() {
+		let en = LANGID::new(co::LANG::ENGLISH, co::SUBLANG::ENGLISH_US);
+		let buf = ResourceInfoBuilder::new()
+			.string(en, co::CP::UTF16, "ProductName", "Tool")
+			.translation(LANGID::USER_DEFAULT, co::CP::UTF16)
+			.translation(en, co::CP::UTF16)
+			.serialize()
+			.unwrap();
+
+		let res_info = ResourceInfo::parse(&buf).unwrap();
+		assert!(res_info.version_info().is_none());
+		assert_eq!(res_info.var("Translation").unwrap().len(), 8);
+		let blocks = res_info.blocks().collect::<Vec<_>>();
+		assert_eq!(blocks[0].lang_id(), LANGID::USER_DEFAULT);
+		assert_eq!(blocks[0].product_name(), None); // listed, but has no table
+		assert_eq!(blocks[1].product_name().as_deref(), Some("Tool"));
+
+		let empty = ResourceInfoBuilder::new().serialize().unwrap();
+		let res_info = ResourceInfo::parse(&empty).unwrap();
+		assert_eq!(res_info.vars().count() + res_info.string_tables().count(), 0);
 	}
 }
@@ -33,6 +33,55 @@ impl Default for VS_FIXEDFILEINFO {
 }
 
 impl VS_FIXEDFILEINFO {
+	/// Reads the struct from its little-endian binary layout, validating the
+	/// size and the signature.
+	pub(in crate::version) fn from_bytes(data: &[u8]) -> Option<VS_FIXEDFILEINFO> {
+		if data.len() != std::mem::size_of::<Self>() {
+			return None;
+		}
+		let mut dwords = data.chunks_exact(4)
+			.map(|dw| u32::from_le_bytes([dw[0], dw[1], dw[2], dw[3]]));
+		let mut next = || dwords.next().unwrap_or_default();
+
+		let obj = Self {
+			dwSignature: next(),
+			dwStrucVersion: next(),
+			dwFileVersionMS: next(),
+			dwFileVersionLS: next(),
+			dwProductVersionMS: next(),
+			dwProductVersionLS: next(),
+			dwFileFlagsMask: co::VS_FF(next()),
+			dwFileFlags: co::VS_FF(next()),
+			dwFileOS: co::VOS(next()),
+			dwFileType: co::VFT(next()),
+			dwFileSubtype: co::VFT2(next()),
+			dwFileDateMS: next(),
+			dwFileDateLS: next(),
+		};
+		(obj.dwSignature == 0xfeef_04bd).then_some(obj)
+	}
+
+	/// Writes the struct in its little-endian binary layout.
+	pub(in crate::version) fn to_bytes(&self) -> Vec<u8> {
+		[
+			self.dwSignature,
+			self.dwStrucVersion,
+			self.dwFileVersionMS,
+			self.dwFileVersionLS,
+			self.dwProductVersionMS,
+			self.dwProductVersionLS,
+			self.dwFileFlagsMask.0,
+			self.dwFileFlags.0,
+			self.dwFileOS.0,
+			self.dwFileType.0,
+			self.dwFileSubtype.0,
+			self.dwFileDateMS,
+			self.dwFileDateLS,
+		].iter()
+			.flat_map(|dw| dw.to_le_bytes())
+			.collect()
+	}
+
 	/// Returns the `dwFileVersionMS` and `dwFileVersionLS` fields.
 	pub fn dwFileVersion(&self) -> [u16; 4] {
 		[HIWORD(self.dwFileVersionMS), LOWORD(self.dwFileVersionMS),